@@ -24,10 +24,10 @@ use node_runtime::{
 	DemocracyConfig,GrandpaConfig, ImOnlineConfig, SessionConfig, SessionKeys, StakerStatus,
 	StakingConfig, ElectionsConfig, IndicesConfig, SocietyConfig, SudoConfig, SystemConfig,
 	TechnicalCommitteeConfig, wasm_binary_unwrap,
-	ClaimsConfig,
+	ClaimsConfig, AssetsConfig, BridgeEosConfig, ConvertConfig, SwapConfig, VoucherConfig,
 };
 use node_runtime::Block;
-use node_runtime::constants::currency::*;
+use node_runtime::constants::{currency::*, time::DAYS};
 use node_runtime::claims::EthereumAddress;
 use sc_service::ChainType;
 use hex_literal::hex;
@@ -38,7 +38,7 @@ use pallet_im_online::sr25519::{AuthorityId as ImOnlineId};
 use sp_authority_discovery::AuthorityId as AuthorityDiscoveryId;
 use sp_runtime::{Perbill, traits::{Verify, IdentifyAccount}};
 
-pub use node_primitives::{AccountId, Balance, Signature};
+pub use node_primitives::{AccountId, Balance, Signature, Token, TokenSymbol};
 pub use node_runtime::GenesisConfig;
 
 type AccountPublic = <Signature as Verify>::Signer;
@@ -204,6 +204,13 @@ pub fn authority_keys_from_seed(seed: &str) -> (
 	)
 }
 
+/// Helper function to describe a token registered at genesis
+fn genesis_token(token_symbol: TokenSymbol, symbol: &[u8], precision: u16, name: &[u8]) -> (TokenSymbol, Token<Balance>) {
+	let mut token = Token::new(symbol.to_vec(), precision, 0);
+	token.name = name.to_vec();
+	(token_symbol, token)
+}
+
 /// Helper function to create GenesisConfig for testing
 pub fn testnet_genesis(
 	initial_authorities: Vec<(
@@ -297,7 +304,7 @@ pub fn testnet_genesis(
 			},
 		}),
 		pallet_sudo: Some(SudoConfig {
-			key: root_key,
+			key: root_key.clone(),
 		}),
 		pallet_babe: Some(BabeConfig {
 			authorities: vec![],
@@ -328,6 +335,64 @@ pub fn testnet_genesis(
 			],
 			vesting: vec![],
 		}),
+		pallet_assets: Some(AssetsConfig {
+			account_assets: vec![],
+			next_asset_id: 0u32, // bumped over the tokens registered below
+			token_details: vec![
+				genesis_token(TokenSymbol::aUSD, b"aUSD", 18, b"Keysians USD"),
+				genesis_token(TokenSymbol::DOT, b"DOT", 12, b"Polkadot"),
+				genesis_token(TokenSymbol::vDOT, b"vDOT", 12, b"Keysians staked DOT"),
+				genesis_token(TokenSymbol::KSM, b"KSM", 12, b"Kusama"),
+				genesis_token(TokenSymbol::vKSM, b"vKSM", 12, b"Keysians staked KSM"),
+				genesis_token(TokenSymbol::EOS, b"EOS", 4, b"EOS"),
+				genesis_token(TokenSymbol::vEOS, b"vEOS", 4, b"Keysians staked EOS"),
+			],
+			token_pairs: vec![
+				(TokenSymbol::DOT, TokenSymbol::vDOT),
+				(TokenSymbol::KSM, TokenSymbol::vKSM),
+				(TokenSymbol::EOS, TokenSymbol::vEOS),
+			],
+			prices: vec![],
+		}),
+		pallet_convert: Some(ConvertConfig {
+			convert_price: vec![
+				(TokenSymbol::DOT, 10_000), // 1:1 against their vtokens
+				(TokenSymbol::KSM, 10_000),
+				(TokenSymbol::EOS, 10_000),
+			],
+			unbonding_period: vec![
+				(TokenSymbol::DOT, 28 * DAYS),
+				(TokenSymbol::KSM, 7 * DAYS),
+				(TokenSymbol::EOS, 3 * DAYS),
+			],
+		}),
+		pallet_swap: Some(SwapConfig {
+			pools: vec![
+				(
+					root_key.clone(),
+					vec![
+						(TokenSymbol::aUSD, 1_000 * DOLLARS, 1),
+						(TokenSymbol::DOT, 1_000 * DOLLARS, 1),
+						(TokenSymbol::vDOT, 1_000 * DOLLARS, 1),
+						(TokenSymbol::KSM, 1_000 * DOLLARS, 1),
+						(TokenSymbol::vKSM, 1_000 * DOLLARS, 1),
+						(TokenSymbol::EOS, 1_000 * DOLLARS, 1),
+						(TokenSymbol::vEOS, 1_000 * DOLLARS, 1),
+					],
+					150, // 0.15%
+					1_000 * DOLLARS,
+				),
+			],
+		}),
+		pallet_bridge_eos: Some(BridgeEosConfig {
+			bridge_contract_account: (b"keysiancross".to_vec(), 2),
+			notary_keys: initial_authorities.iter().map(|x| x.0.clone()).collect(),
+			cross_chain_privilege: endowed_accounts.iter().map(|x| (x.clone(), true)).collect(),
+			all_crosschain_privilege: Vec::new(),
+		}),
+		pallet_voucher: Some(VoucherConfig {
+			voucher: vec![],
+		}),
 	}
 }
 
@@ -20,9 +20,14 @@
 
 #![cfg_attr(not(feature = "std"), no_std)]
 
+use codec::{Decode, Encode};
+#[cfg(feature = "std")]
+use serde::{Deserialize, Serialize};
 use sp_runtime::{
-	generic, traits::{Verify, BlakeTwo256, IdentifyAccount}, OpaqueExtrinsic, MultiSignature
+	generic, traits::{Verify, BlakeTwo256, IdentifyAccount, Saturating}, OpaqueExtrinsic, MultiSignature,
+	RuntimeDebug,
 };
+use sp_std::prelude::*;
 
 /// An index to a block.
 pub type BlockNumber = u32;
@@ -40,6 +45,18 @@ pub type AccountIndex = u32;
 /// Balance of an account.
 pub type Balance = u128;
 
+/// Identifier of an asset.
+pub type AssetId = u32;
+
+/// Price of an asset.
+pub type Price = u64;
+
+/// Convert price between a token and its vtoken.
+pub type ConvertPrice = u128;
+
+/// Precision of an asset bridged from another blockchain.
+pub type Precision = u32;
+
 /// Type used for expressing timestamp.
 pub type Moment = u64;
 
@@ -63,6 +80,424 @@ pub type Block = generic::Block<Header, OpaqueExtrinsic>;
 /// Block ID.
 pub type BlockId = generic::BlockId<Block>;
 
+/// Identifier of a named lock or reserve on an account asset.
+pub type LockIdentifier = [u8; 8];
+
+/// Identifier of a token registered in Keysians.
+///
+/// Tokens are registered in the assets module at runtime, the constants below are the ones
+/// created at genesis.
+#[derive(Encode, Decode, Default, Clone, Copy, Eq, PartialEq, Ord, PartialOrd, RuntimeDebug)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+pub struct TokenSymbol(pub u32);
+
+#[allow(non_upper_case_globals)]
+impl TokenSymbol {
+	/// Keysians stable coin.
+	pub const aUSD: Self = Self(0);
+	/// Polkadot native token.
+	pub const DOT: Self = Self(1);
+	/// Keysians derivative of staked DOT.
+	pub const vDOT: Self = Self(2);
+	/// Kusama native token.
+	pub const KSM: Self = Self(3);
+	/// Keysians derivative of staked KSM.
+	pub const vKSM: Self = Self(4);
+	/// EOS native token.
+	pub const EOS: Self = Self(5);
+	/// Keysians derivative of staked EOS.
+	pub const vEOS: Self = Self(6);
+	/// IOST native token.
+	pub const IOST: Self = Self(7);
+	/// Keysians derivative of staked IOST.
+	pub const vIOST: Self = Self(8);
+}
+
+impl From<u32> for TokenSymbol {
+	fn from(id: u32) -> Self {
+		Self(id)
+	}
+}
+
+impl From<TokenSymbol> for u32 {
+	fn from(symbol: TokenSymbol) -> Self {
+		symbol.0
+	}
+}
+
+/// Details of a token.
+#[derive(Encode, Decode, Default, Clone, Eq, PartialEq, RuntimeDebug)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+pub struct Token<Balance> {
+	/// The symbol bytes of the token, like `b"DOT"`.
+	pub symbol: Vec<u8>,
+	/// How many decimals the token has.
+	pub precision: u16,
+	/// Total amount of the token issued.
+	pub total_supply: Balance,
+	/// Full name of the token, like `b"Polkadot"`.
+	pub name: Vec<u8>,
+	/// Where to find more metadata of the token, like an icon or a website.
+	pub metadata_uri: Vec<u8>,
+	/// Accounts holding less than this are reaped, the rest is handled as dust.
+	pub min_balance: Balance,
+}
+
+impl<Balance: Default> Token<Balance> {
+	/// Create a new token without name and metadata.
+	pub fn new(symbol: Vec<u8>, precision: u16, total_supply: Balance) -> Self {
+		Self {
+			symbol,
+			precision,
+			total_supply,
+			name: Vec::new(),
+			metadata_uri: Vec::new(),
+			min_balance: Default::default(),
+		}
+	}
+}
+
+/// The asset an account holds for a token.
+#[derive(Encode, Decode, Default, Clone, Eq, PartialEq, RuntimeDebug)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+pub struct AccountAsset<Balance, Cost, Income> {
+	/// Total balance of the account, including the reserved part.
+	pub balance: Balance,
+	/// The part of balance which is reserved by some modules.
+	pub reserved: Balance,
+	/// The part of free balance which is frozen by locks, the biggest lock wins.
+	pub frozen: Balance,
+	/// The part of balance which can be used, free balance which is not frozen.
+	pub available: Balance,
+	/// Total value of the balance received, at the token price when it was received.
+	pub cost: Cost,
+	/// Total value of the balance sent or destroyed, at the token price when it was sent.
+	pub income: Income,
+}
+
+impl<Balance: Saturating + Copy, Cost, Income> AccountAsset<Balance, Cost, Income> {
+	/// The part of balance which is not reserved.
+	pub fn free(&self) -> Balance {
+		self.balance.saturating_sub(self.reserved)
+	}
+
+	/// Recalculate available balance after balance, reserved or frozen changed.
+	pub fn update_available(&mut self) {
+		self.available = self.free().saturating_sub(self.frozen);
+	}
+}
+
+/// Token and vtoken pool of a token pair in convert module.
+#[derive(Encode, Decode, Default, Clone, Eq, PartialEq, RuntimeDebug)]
+pub struct ConvertPool<Balance> {
+	/// Total amount of token in the pool.
+	pub token_pool: Balance,
+	/// Total amount of vtoken in the pool.
+	pub vtoken_pool: Balance,
+	/// Rewards to be issued to the pool in current round.
+	pub current_reward: Balance,
+	/// Rewards collected for the next round.
+	pub pending_reward: Balance,
+}
+
+impl<Balance: Default + Copy> ConvertPool<Balance> {
+	/// Create a convert pool without any reward.
+	pub fn new(token_pool: Balance, vtoken_pool: Balance) -> Self {
+		Self {
+			token_pool,
+			vtoken_pool,
+			..Default::default()
+		}
+	}
+
+	/// Start a new round, pending rewards become the current rewards.
+	pub fn new_round(&mut self) {
+		self.current_reward = self.pending_reward;
+		self.pending_reward = Default::default();
+	}
+}
+
+/// Asset handler shared by all Keysians modules.
+pub trait AssetTrait<AssetId, AccountId, Balance, Cost, Income> {
+	/// Error type of the asset handler.
+	type Error;
+
+	/// Create a new token.
+	fn asset_create(symbol: Vec<u8>, precision: u16) -> Result<(AssetId, Token<Balance>), Self::Error>;
+
+	/// Issue some amount of token to the target.
+	fn asset_issue(token_symbol: TokenSymbol, target: &AccountId, amount: Balance);
+
+	/// Redeem some amount of token from the target.
+	fn asset_redeem(token_symbol: TokenSymbol, target: &AccountId, amount: Balance);
+
+	/// Destroy some amount of token of the target.
+	fn asset_destroy(token_symbol: TokenSymbol, target: &AccountId, amount: Balance);
+
+	/// Move some balance of `from` to `to` for another module, which checks the balance is
+	/// available. The account asset of `from` is only reaped once it's empty, no dust is taken.
+	fn asset_move(token_symbol: TokenSymbol, from: &AccountId, to: &AccountId, amount: Balance);
+
+	/// Find the token owned by `who` which matches symbol and precision.
+	fn asset_id_exists(who: &AccountId, symbol: &[u8], precision: u16) -> Option<TokenSymbol>;
+
+	/// Whether the token exists.
+	fn token_exists(token_symbol: TokenSymbol) -> bool;
+
+	/// Get the asset of the target.
+	fn get_account_asset(token_symbol: TokenSymbol, target: &AccountId) -> AccountAsset<Balance, Cost, Income>;
+
+	/// Get details of the token.
+	fn get_token(token_symbol: TokenSymbol) -> Token<Balance>;
+
+	/// Get the (token, vtoken) pair the token belongs to, if it is paired.
+	fn get_token_pair(token_symbol: TokenSymbol) -> Option<(TokenSymbol, TokenSymbol)>;
+
+	/// Create or replace the lock `id` on the free balance of the account.
+	fn set_lock(id: LockIdentifier, token_symbol: TokenSymbol, who: &AccountId, amount: Balance);
+
+	/// Remove the lock `id` from the account.
+	fn remove_lock(id: LockIdentifier, token_symbol: TokenSymbol, who: &AccountId);
+
+	/// Move some available balance of the account into the reserve `id`.
+	fn reserve(id: LockIdentifier, token_symbol: TokenSymbol, who: &AccountId, amount: Balance) -> Result<(), Self::Error>;
+
+	/// Move some balance of the reserve `id` back to free balance, returns the amount unreserved.
+	fn unreserve(id: LockIdentifier, token_symbol: TokenSymbol, who: &AccountId, amount: Balance) -> Balance;
+
+	/// Destroy some balance of the reserve `id`, returns the amount slashed.
+	fn slash_reserved(id: LockIdentifier, token_symbol: TokenSymbol, who: &AccountId, amount: Balance) -> Balance;
+
+	/// Get the balance of the reserve `id` of the account.
+	fn reserved_balance(id: LockIdentifier, token_symbol: TokenSymbol, who: &AccountId) -> Balance;
+
+	/// Move some available balance of `owner` to `target` on behalf of `spender`, within the
+	/// allowance `owner` approved to `spender`.
+	fn asset_transfer_from(
+		token_symbol: TokenSymbol,
+		owner: &AccountId,
+		spender: &AccountId,
+		target: &AccountId,
+		amount: Balance,
+	) -> Result<(), Self::Error>;
+
+	/// Whether the token, or the account for the token, is frozen. No balance of a frozen account
+	/// asset should be moved.
+	fn is_frozen(token_symbol: TokenSymbol, who: &AccountId) -> bool;
+}
+
+/// Handler for redeeming asset to another blockchain.
+pub trait AssetRedeem<AssetId, AccountId, Balance> {
+	/// Redeem asset of the target, `to_name` is the receiver on the other blockchain.
+	fn asset_redeem(token_symbol: TokenSymbol, target: AccountId, amount: Balance, to_name: Option<Vec<u8>>);
+}
+
+impl<A, AC, B> AssetRedeem<A, AC, B> for () {
+	fn asset_redeem(_: TokenSymbol, _: AC, _: B, _: Option<Vec<u8>>) {}
+}
+
+/// Fetch the convert price of a token.
+pub trait FetchConvertPrice<TokenSymbol, ConvertPrice> {
+	/// Get current convert price of the token.
+	fn fetch_convert_price(token_symbol: TokenSymbol) -> ConvertPrice;
+}
+
+impl<TS, CP: Default> FetchConvertPrice<TS, CP> for () {
+	fn fetch_convert_price(_: TS) -> CP {
+		Default::default()
+	}
+}
+
+/// Set the price of a token.
+pub trait TokenPriceHandler<Price> {
+	/// Set price for the token whose symbol is `symbol`.
+	fn set_token_price(symbol: Vec<u8>, price: Price);
+}
+
+impl<P> TokenPriceHandler<P> for () {
+	fn set_token_price(_: Vec<u8>, _: P) {}
+}
+
+/// Provide manipulation resistant prices of tokens.
+pub trait PriceProvider<TokenSymbol, Price> {
+	/// Average price of `token_symbol` quoted in `quote`, a price of 1 is `precision`.
+	fn get_price(token_symbol: TokenSymbol, quote: TokenSymbol, precision: Price) -> Option<Price>;
+}
+
+impl<TS, P> PriceProvider<TS, P> for () {
+	fn get_price(_: TS, _: TS, _: P) -> Option<P> {
+		None
+	}
+}
+
+/// Swap tokens through the pools.
+pub trait SwapHandler<PoolId, TokenSymbol, AccountId, Balance> {
+	/// Error while swapping.
+	type Error;
+	/// Marginal amount of `token_out` paid by the pool for a `token_in`, swap fee included, an
+	/// amount of 1 is `precision`.
+	fn spot_price(pool_id: PoolId, token_in: TokenSymbol, token_out: TokenSymbol, precision: Balance) -> Option<Balance>;
+	/// Swap exact `token_amount_in` of `who` for at least `min_token_amount_out`, returns the
+	/// amount of `token_out` received.
+	fn swap_exact_in(
+		who: &AccountId,
+		pool_id: PoolId,
+		token_in: TokenSymbol,
+		token_amount_in: Balance,
+		token_out: TokenSymbol,
+		min_token_amount_out: Balance,
+	) -> Result<Balance, Self::Error>;
+	/// Weight of `swap_exact_in`.
+	fn swap_exact_in_weight() -> u64;
+	/// Maximum amount of `token_in` a swap in the pool pays, `None` if the pool or the token
+	/// doesn't exist.
+	fn max_amount_in(pool_id: PoolId, token_in: TokenSymbol) -> Option<Balance>;
+}
+
+/// Reward which is added to the asset pool.
+pub trait AssetReward<TokenSymbol, Balance> {
+	/// Output after reward is set.
+	type Output;
+	/// Error while setting reward.
+	type Error;
+	/// Add reward to the pool of the token.
+	fn set_asset_reward(token_symbol: TokenSymbol, reward: Balance) -> Result<Self::Output, Self::Error>;
+}
+
+/// Handler for the staking rewards.
+pub trait RewardHandler<TokenSymbol, Balance> {
+	/// Send the reward of the token.
+	fn send_reward(token_symbol: TokenSymbol, reward: Balance);
+}
+
+impl<TS, B> RewardHandler<TS, B> for () {
+	fn send_reward(_: TS, _: B) {}
+}
+
+/// Handler for the dust left when an account asset below the minimum balance is reaped.
+pub trait DustHandler<TokenSymbol, AccountId, Balance> {
+	/// Handle the dust removed from `who`. It has been taken out of total supply already.
+	fn handle_dust(token_symbol: TokenSymbol, who: &AccountId, amount: Balance);
+}
+
+/// Burn the dust.
+impl<TS, A, B> DustHandler<TS, A, B> for () {
+	fn handle_dust(_: TS, _: &A, _: B) {}
+}
+
+/// Handler for the assets moved from an account to another.
+pub trait TransferHandler<TokenSymbol, AccountId, Balance> {
+	/// Handle `amount` of the token moved from `from` to `to`, the balances have been updated.
+	fn handle_transfer(token_symbol: TokenSymbol, from: &AccountId, to: &AccountId, amount: Balance);
+
+	/// Whether holders can burn the token themselves, or lose its dust once their account asset
+	/// is reaped. Tokens whose supply is recorded by the handler cannot.
+	fn is_burnable(token_symbol: TokenSymbol) -> bool;
+
+	/// Weight of `handle_transfer`, for the most expensive transfer.
+	fn handle_transfer_weight() -> u64;
+}
+
+impl<TS, A, B> TransferHandler<TS, A, B> for () {
+	fn handle_transfer(_: TS, _: &A, _: &A, _: B) {}
+
+	fn handle_transfer_weight() -> u64 {
+		0
+	}
+
+	fn is_burnable(_: TS) -> bool {
+		true
+	}
+}
+
+/// Blockchains which Keysians bridges to.
+#[allow(non_camel_case_types)]
+#[derive(Encode, Decode, Clone, Copy, Eq, PartialEq, RuntimeDebug)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+pub enum BlockchainType {
+	/// Keysians itself.
+	KEYSIANS,
+	/// EOS blockchain.
+	EOS,
+	/// IOST blockchain.
+	IOST,
+}
+
+impl Default for BlockchainType {
+	fn default() -> Self {
+		Self::KEYSIANS
+	}
+}
+
+/// Symbol of an asset on another blockchain.
+#[derive(Encode, Decode, Default, Clone, Eq, PartialEq, RuntimeDebug)]
+pub struct BridgeAssetSymbol<Precision> {
+	/// Blockchain the asset lives on.
+	pub blockchain: BlockchainType,
+	/// The symbol bytes of the asset.
+	pub symbol: Vec<u8>,
+	/// How many decimals the asset has.
+	pub precision: Precision,
+}
+
+impl<Precision> BridgeAssetSymbol<Precision> {
+	/// Create a new bridge asset symbol.
+	pub fn new(blockchain: BlockchainType, symbol: Vec<u8>, precision: Precision) -> Self {
+		Self {
+			blockchain,
+			symbol,
+			precision,
+		}
+	}
+}
+
+/// An amount of asset sent across the bridge.
+#[derive(Encode, Decode, Clone, Eq, PartialEq, RuntimeDebug)]
+pub struct BridgeAssetBalance<AccountId, Precision, Balance> {
+	/// Symbol of the asset on the other blockchain.
+	pub symbol: BridgeAssetSymbol<Precision>,
+	/// Amount of the asset.
+	pub amount: Balance,
+	/// Memo of the cross-chain transaction.
+	pub memo: Vec<u8>,
+	/// Who sends the asset.
+	pub from: AccountId,
+	/// The token on Keysians.
+	pub token_symbol: TokenSymbol,
+}
+
+/// Handler for the asset coming from another blockchain.
+pub trait BridgeAssetFrom<AccountId, Precision, Balance> {
+	/// Receive the asset for the target.
+	fn bridge_asset_from(target: AccountId, bridge_asset: BridgeAssetBalance<AccountId, Precision, Balance>);
+}
+
+impl<A, P, B> BridgeAssetFrom<A, P, B> for () {
+	fn bridge_asset_from(_: A, _: BridgeAssetBalance<A, P, B>) {}
+}
+
+/// Handler for the asset sent to another blockchain.
+pub trait BridgeAssetTo<AccountId, Precision, Balance> {
+	/// Error type of the bridge.
+	type Error;
+	/// Send the asset to the target on another blockchain.
+	fn bridge_asset_to(target: Vec<u8>, bridge_asset: BridgeAssetBalance<AccountId, Precision, Balance>) -> Result<(), Self::Error>;
+	/// Redeem the token to the validator address.
+	fn redeem(token_symbol: TokenSymbol, amount: Balance, validator_address: Vec<u8>) -> Result<(), Self::Error>;
+	/// Stake the token to the validator address.
+	fn stake(token_symbol: TokenSymbol, amount: Balance, validator_address: Vec<u8>) -> Result<(), Self::Error>;
+	/// Unstake the token from the validator address.
+	fn unstake(token_symbol: TokenSymbol, amount: Balance, validator_address: Vec<u8>) -> Result<(), Self::Error>;
+}
+
+impl<A, P, B> BridgeAssetTo<A, P, B> for () {
+	type Error = ();
+	fn bridge_asset_to(_: Vec<u8>, _: BridgeAssetBalance<A, P, B>) -> Result<(), Self::Error> { Ok(()) }
+	fn redeem(_: TokenSymbol, _: B, _: Vec<u8>) -> Result<(), Self::Error> { Ok(()) }
+	fn stake(_: TokenSymbol, _: B, _: Vec<u8>) -> Result<(), Self::Error> { Ok(()) }
+	fn unstake(_: TokenSymbol, _: B, _: Vec<u8>) -> Result<(), Self::Error> { Ok(()) }
+}
+
 /// App-specific crypto used for reporting equivocation/misbehavior in BABE and
 /// GRANDPA. Any rewards for misbehavior reporting will be paid out to this
 /// account.
@@ -16,10 +16,10 @@
 
 //! Some configurable implementations as associated type for the substrate runtime.
 
-use node_primitives::Balance;
+use node_primitives::{AccountId, AssetTrait, Balance, DustHandler, TokenSymbol};
 use sp_runtime::traits::Convert;
 use frame_support::traits::{OnUnbalanced, Currency};
-use crate::{Balances, Authorship, NegativeImbalance};
+use crate::{Assets, Balances, Authorship, NegativeImbalance, Treasury};
 
 pub struct Author;
 impl OnUnbalanced<NegativeImbalance> for Author {
@@ -28,6 +28,14 @@ impl OnUnbalanced<NegativeImbalance> for Author {
 	}
 }
 
+/// Dust of reaped account assets goes to treasury.
+pub struct DustToTreasury;
+impl DustHandler<TokenSymbol, AccountId, Balance> for DustToTreasury {
+	fn handle_dust(token_symbol: TokenSymbol, _who: &AccountId, amount: Balance) {
+		Assets::asset_issue(token_symbol, &Treasury::account_id(), amount);
+	}
+}
+
 /// Struct that handles the conversion of Balance -> `u64`. This is used for staking's election
 /// calculation.
 pub struct CurrencyToVoteHandler;
@@ -39,7 +39,10 @@ use sp_core::{
 	OpaqueMetadata,
 };
 pub use node_primitives::{AccountId, Signature};
-use node_primitives::{AccountIndex, Balance, BlockNumber, Hash, Index, Moment};
+use node_primitives::{
+	AccountIndex, AssetId, Balance, BlockNumber, ConvertPrice, Hash, Index, Moment, Precision, Price,
+	TokenSymbol,
+};
 use sp_api::impl_runtime_apis;
 use sp_runtime::{
 	Permill, Perbill, Perquintill, Percent, ApplyExtrinsicResult,
@@ -76,7 +79,7 @@ pub use pallet_staking::StakerStatus;
 
 /// Implementations of some helper traits passed into runtime modules as associated types.
 pub mod impls;
-use impls::{CurrencyToVoteHandler, Author};
+use impls::{CurrencyToVoteHandler, Author, DustToTreasury};
 
 /// Constant values used within the runtime.
 pub mod constants;
@@ -903,6 +906,137 @@ impl pallet_vesting::Trait for Runtime {
 	type WeightInfo = weights::pallet_vesting::WeightInfo;
 }
 
+parameter_types! {
+	pub const AssetPricePrecision: Price = 1_000_000;
+	pub const MaxPriceUpdates: u32 = 8;
+}
+
+impl pallet_assets::Trait for Runtime {
+	type Event = Event;
+	type Balance = Balance;
+	type AssetId = AssetId;
+	type Price = Price;
+	type Convert = ConvertPrice;
+	type Cost = Balance;
+	type Income = Balance;
+	type AssetRedeem = ();
+	type FetchConvertPrice = Convert;
+	type DustHandler = DustToTreasury;
+	type PriceProvider = Swap;
+	type PricePrecision = AssetPricePrecision;
+	type MaxPriceUpdates = MaxPriceUpdates;
+	type TransferHandler = Swap;
+}
+
+parameter_types! {
+	pub const InitPoolSupply: Balance = 1_000 * DOLLARS;
+	pub const MaximumSwapInRatio: Balance = 2;
+	pub const MinimumBalance: Balance = 1 * DOLLARS;
+	pub const MaximumSwapFee: Balance = 10_000; // 10%
+	pub const MinimumSwapFee: Balance = 1; // 0.0001%
+	pub const FeePrecision: Balance = 100_000;
+	pub const MaximumPriceMovement: Balance = 20_000; // 20%
+	pub const TwapWindow: BlockNumber = 1 * HOURS;
+}
+
+impl pallet_swap::Trait for Runtime {
+	type Event = Event;
+	type Call = Call;
+	type Fee = Balance;
+	type AssetId = AssetId;
+	type Balance = Balance;
+	type Cost = Balance;
+	type Income = Balance;
+	type AssetTrait = Assets;
+	type InvariantValue = Balance;
+	type PoolWeight = Balance;
+	type InitPoolSupply = InitPoolSupply;
+	type MaximumSwapInRatio = MaximumSwapInRatio;
+	type MinimumBalance = MinimumBalance;
+	type MaximumSwapFee = MaximumSwapFee;
+	type MinimumSwapFee = MinimumSwapFee;
+	type FeePrecision = FeePrecision;
+	type MaximumPriceMovement = MaximumPriceMovement;
+	type TwapWindow = TwapWindow;
+	type FetchConvertPrice = Convert;
+	type ConvertPricePrecision = ConvertPricePrecision;
+}
+
+parameter_types! {
+	pub const OrderPricePrecision: Balance = 1_000_000_000_000;
+	pub const MaxOrdersPerBlock: u32 = 20;
+	pub const MaxOpenOrders: u32 = 10_000;
+	pub const MaxOrdersPerAccount: u32 = 16;
+}
+
+impl pallet_orderbook::Trait for Runtime {
+	type Event = Event;
+	type Balance = Balance;
+	type AssetId = AssetId;
+	type Cost = Balance;
+	type Income = Balance;
+	type AssetTrait = Assets;
+	type PoolId = pallet_swap::PoolId;
+	type SwapHandler = Swap;
+	type PricePrecision = OrderPricePrecision;
+	type MaxOrdersPerBlock = MaxOrdersPerBlock;
+	type MaxOpenOrders = MaxOpenOrders;
+	type MaxOrdersPerAccount = MaxOrdersPerAccount;
+}
+
+parameter_types! {
+	pub const ConvertDuration: BlockNumber = 1 * DAYS;
+	pub const ConvertPricePrecision: ConvertPrice = 10_000;
+	pub const MaxUnbondingRequests: u32 = 16;
+}
+
+impl pallet_convert::Trait for Runtime {
+	type ConvertPrice = ConvertPrice;
+	type RatePerBlock = ConvertPrice;
+	type AssetId = AssetId;
+	type Balance = Balance;
+	type Cost = Balance;
+	type Income = Balance;
+	type AssetTrait = Assets;
+	type Event = Event;
+	type ConvertDuration = ConvertDuration;
+	type ConvertPricePrecision = ConvertPricePrecision;
+	type PriceProvider = Swap;
+	type MaxUnbondingRequests = MaxUnbondingRequests;
+	type PoolId = pallet_swap::PoolId;
+	type SwapHandler = Swap;
+}
+
+impl pallet_bridge_eos::Trait for Runtime {
+	type AuthorityId = pallet_bridge_eos::sr25519::AuthorityId;
+	type Event = Event;
+	type Balance = Balance;
+	type AssetId = AssetId;
+	type Cost = Balance;
+	type Income = Balance;
+	type Precision = Precision;
+	type BridgeAssetFrom = ();
+	type AssetTrait = Assets;
+	type Call = Call;
+}
+
+impl pallet_proxy_validator::Trait for Runtime {
+	type Event = Event;
+	type Balance = Balance;
+	type AssetId = AssetId;
+	type Cost = Balance;
+	type Income = Balance;
+	type Precision = Precision;
+	type AssetTrait = Assets;
+	type BridgeAssetTo = BridgeEos;
+	type RewardHandler = Convert;
+}
+
+impl pallet_voucher::Trait for Runtime {
+	type Event = Event;
+	type Balance = Balance;
+}
+
 construct_runtime!(
 	pub enum Runtime where
 		Block = Block,
@@ -944,6 +1078,15 @@ construct_runtime!(
 
 		// Claims. Usable initially.
 		Claims: claims::{Module, Call, Storage, Event<T>, Config<T>, ValidateUnsigned},
+
+		// Keysians modules
+		Assets: pallet_assets::{Module, Call, Storage, Event<T>, Config<T>},
+		Convert: pallet_convert::{Module, Call, Storage, Event, Config<T>},
+		Swap: pallet_swap::{Module, Call, Storage, Event<T>, Config<T>},
+		Orderbook: pallet_orderbook::{Module, Call, Storage, Event<T>},
+		BridgeEos: pallet_bridge_eos::{Module, Call, Storage, Event<T>, Config<T>, ValidateUnsigned},
+		ProxyValidator: pallet_proxy_validator::{Module, Call, Storage, Event<T>},
+		Voucher: pallet_voucher::{Module, Call, Storage, Event<T>, Config<T>},
 	}
 );
 
@@ -1173,6 +1316,116 @@ impl_runtime_apis! {
 		}
 	}
 
+	impl pallet_assets_rpc_runtime_api::AssetsApi<Block, TokenSymbol, AccountId, Balance> for Runtime {
+		fn asset_balances(token_symbol: TokenSymbol, who: AccountId) -> u64 {
+			Assets::asset_balances(token_symbol, who)
+		}
+
+		fn asset_tokens(who: AccountId) -> Vec<TokenSymbol> {
+			Assets::asset_tokens(who)
+		}
+
+		fn asset_pnl(token_symbol: TokenSymbol, who: AccountId) -> pallet_assets_rpc_runtime_api::AssetPnl {
+			let (cost, income, value) = Assets::asset_pnl(token_symbol, who);
+			pallet_assets_rpc_runtime_api::AssetPnl::new(cost, income, value)
+		}
+
+		fn account_asset(
+			token_symbol: TokenSymbol,
+			who: AccountId,
+		) -> pallet_assets_rpc_runtime_api::AccountAssetInfo<Balance> {
+			Assets::account_assets((token_symbol, who)).into()
+		}
+
+		fn account_assets(who: AccountId) -> Vec<(TokenSymbol, pallet_assets_rpc_runtime_api::AccountAssetInfo<Balance>)> {
+			Assets::all_account_assets(who)
+				.into_iter()
+				.map(|(token_symbol, asset)| (token_symbol, asset.into()))
+				.collect()
+		}
+
+		fn token_info(token_symbol: TokenSymbol) -> Option<pallet_assets_rpc_runtime_api::TokenInfo<Balance>> {
+			Assets::token_info(token_symbol).map(Into::into)
+		}
+
+		fn total_issuance(token_symbol: TokenSymbol) -> Balance {
+			Assets::total_issuance(token_symbol)
+		}
+
+		fn token_holders(
+			token_symbol: TokenSymbol,
+			start: Option<AccountId>,
+			count: u32,
+		) -> Vec<(AccountId, pallet_assets_rpc_runtime_api::AccountAssetInfo<Balance>)> {
+			Assets::token_holders(token_symbol, start, count)
+				.into_iter()
+				.map(|(who, asset)| (who, asset.into()))
+				.collect()
+		}
+	}
+
+	impl pallet_convert_rpc_runtime_api::ConvertPriceApi<Block, TokenSymbol, ConvertPrice> for Runtime {
+		fn get_convert_rate(token_symbol: TokenSymbol) -> ConvertPrice {
+			Convert::get_convert(token_symbol)
+		}
+
+		fn get_market_rate(token_symbol: TokenSymbol) -> Option<ConvertPrice> {
+			Convert::get_market_price(token_symbol)
+		}
+	}
+
+	impl pallet_swap_rpc_runtime_api::SwapApi<Block, pallet_swap::PoolId, TokenSymbol, Balance> for Runtime {
+		fn best_route(
+			token_in: TokenSymbol,
+			token_amount_in: Balance,
+			token_out: TokenSymbol,
+		) -> Option<pallet_swap_rpc_runtime_api::SwapRoute<pallet_swap::PoolId, TokenSymbol, Balance>> {
+			Swap::best_route(token_in, token_amount_in, token_out).map(|(path, amount_out)| {
+				pallet_swap_rpc_runtime_api::SwapRoute { path, amount_out }
+			})
+		}
+
+		fn twap(pool_id: pallet_swap::PoolId, base: TokenSymbol, quote: TokenSymbol, window: u32) -> Option<u128> {
+			Swap::twap((pool_id, base, quote), window)
+		}
+
+		fn quote_swap(
+			pool_id: pallet_swap::PoolId,
+			token_in: TokenSymbol,
+			token_amount_in: Balance,
+			token_out: TokenSymbol,
+		) -> Option<pallet_swap_rpc_runtime_api::SwapQuote<Balance>> {
+			Swap::quote_swap(pool_id, token_in, token_amount_in, token_out).ok().map(|(amount_out, fee, before, after)| {
+				pallet_swap_rpc_runtime_api::SwapQuote::new(amount_out, fee, before, after)
+			})
+		}
+
+		fn quote_add_single_liquidity(pool_id: pallet_swap::PoolId, token_symbol: TokenSymbol, token_amount_in: Balance) -> Option<Balance> {
+			Swap::quote_add_single_liquidity(pool_id, token_symbol, token_amount_in).ok()
+		}
+
+		fn quote_remove_single_asset_liquidity(pool_id: pallet_swap::PoolId, token_symbol: TokenSymbol, pool_token_in: Balance) -> Option<Balance> {
+			Swap::quote_remove_single_asset_liquidity(pool_id, token_symbol, pool_token_in).ok()
+		}
+
+		fn check_integrity() -> Vec<(pallet_swap::PoolId, pallet_swap_rpc_runtime_api::IntegrityViolation<TokenSymbol, Balance>)> {
+			use pallet_swap::IntegrityError;
+			use pallet_swap_rpc_runtime_api::IntegrityViolation;
+
+			Swap::check_integrity().into_iter().map(|(pool_id, error)| {
+				let violation = match error {
+					IntegrityError::TokenNotExist(token_symbol) => IntegrityViolation::TokenNotExist(token_symbol),
+					IntegrityError::TotalWeightMismatch(total, sum) => IntegrityViolation::TotalWeightMismatch(total, sum),
+					IntegrityError::PoolTokenMismatch => IntegrityViolation::PoolTokenMismatch,
+					IntegrityError::PoolSupplyMismatch(supply, issued) => IntegrityViolation::PoolSupplyMismatch(supply, issued),
+					IntegrityError::MiningSharesMismatch(shares, supply) => IntegrityViolation::MiningSharesMismatch(shares, supply),
+					IntegrityError::PoolBalanceMismatch(token_symbol, balance, held) => IntegrityViolation::PoolBalanceMismatch(token_symbol, balance, held),
+				};
+				(pool_id, violation)
+			}).collect()
+		}
+	}
+
 	impl sp_session::SessionKeys<Block> for Runtime {
 		fn generate_session_keys(seed: Option<Vec<u8>>) -> Vec<u8> {
 			SessionKeys::generate(seed)
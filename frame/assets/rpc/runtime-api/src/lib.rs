@@ -19,19 +19,166 @@
 extern crate alloc;
 
 use alloc::vec::Vec;
-use codec::Codec;
+use core::convert::TryFrom;
+use codec::{Codec, Decode, Encode};
+use node_primitives::{AccountAsset, Token};
+#[cfg(feature = "std")]
+use serde::{Deserialize, Serialize};
 use sp_api::decl_runtime_apis;
 
+/// The asset an account holds for a token, balances are serialized as strings so that u128
+/// values are not truncated.
+#[derive(Encode, Decode, Default, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(Debug, Serialize, Deserialize))]
+#[cfg_attr(feature = "std", serde(rename_all = "camelCase"))]
+#[cfg_attr(feature = "std", serde(bound(serialize = "Balance: std::fmt::Display")))]
+#[cfg_attr(feature = "std", serde(bound(deserialize = "Balance: std::str::FromStr")))]
+pub struct AccountAssetInfo<Balance> {
+	/// Total balance of the account, including the reserved part.
+	#[cfg_attr(feature = "std", serde(with = "serde_balance"))]
+	pub balance: Balance,
+	/// The part of balance which is reserved by some modules.
+	#[cfg_attr(feature = "std", serde(with = "serde_balance"))]
+	pub reserved: Balance,
+	/// The part of free balance which is frozen by locks.
+	#[cfg_attr(feature = "std", serde(with = "serde_balance"))]
+	pub frozen: Balance,
+	/// The part of balance which can be used.
+	#[cfg_attr(feature = "std", serde(with = "serde_balance"))]
+	pub available: Balance,
+	/// Total value of the balance received.
+	#[cfg_attr(feature = "std", serde(with = "serde_balance"))]
+	pub cost: Balance,
+	/// Total value of the balance sent or destroyed.
+	#[cfg_attr(feature = "std", serde(with = "serde_balance"))]
+	pub income: Balance,
+}
+
+impl<Balance> From<AccountAsset<Balance, Balance, Balance>> for AccountAssetInfo<Balance> {
+	fn from(asset: AccountAsset<Balance, Balance, Balance>) -> Self {
+		Self {
+			balance: asset.balance,
+			reserved: asset.reserved,
+			frozen: asset.frozen,
+			available: asset.available,
+			cost: asset.cost,
+			income: asset.income,
+		}
+	}
+}
+
+/// Metadata of a token, balances are serialized as strings.
+#[derive(Encode, Decode, Default, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(Debug, Serialize, Deserialize))]
+#[cfg_attr(feature = "std", serde(rename_all = "camelCase"))]
+#[cfg_attr(feature = "std", serde(bound(serialize = "Balance: std::fmt::Display")))]
+#[cfg_attr(feature = "std", serde(bound(deserialize = "Balance: std::str::FromStr")))]
+pub struct TokenInfo<Balance> {
+	/// Symbol of the token.
+	pub symbol: Vec<u8>,
+	/// Precision of the token.
+	pub precision: u16,
+	/// Total issuance of the token.
+	#[cfg_attr(feature = "std", serde(with = "serde_balance"))]
+	pub total_supply: Balance,
+	/// Name of the token.
+	pub name: Vec<u8>,
+	/// Uri of the token metadata.
+	pub metadata_uri: Vec<u8>,
+	/// Account assets below this balance are reaped.
+	#[cfg_attr(feature = "std", serde(with = "serde_balance"))]
+	pub min_balance: Balance,
+}
+
+impl<Balance> From<Token<Balance>> for TokenInfo<Balance> {
+	fn from(token: Token<Balance>) -> Self {
+		Self {
+			symbol: token.symbol,
+			precision: token.precision,
+			total_supply: token.total_supply,
+			name: token.name,
+			metadata_uri: token.metadata_uri,
+			min_balance: token.min_balance,
+		}
+	}
+}
+
+#[cfg(feature = "std")]
+mod serde_balance {
+	use serde::{Deserialize, Deserializer, Serializer};
+
+	pub fn serialize<S: Serializer, T: std::fmt::Display>(t: &T, serializer: S) -> Result<S::Ok, S::Error> {
+		serializer.serialize_str(&t.to_string())
+	}
+
+	pub fn deserialize<'de, D: Deserializer<'de>, T: std::str::FromStr>(deserializer: D) -> Result<T, D::Error> {
+		let s = String::deserialize(deserializer)?;
+		s.parse::<T>().map_err(|_| serde::de::Error::custom("Parse from string failed"))
+	}
+}
+
+/// Profit and loss of an account asset, all values are at the token price and serialized as
+/// strings.
+#[derive(Encode, Decode, Default, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(Debug, Serialize, Deserialize))]
+#[cfg_attr(feature = "std", serde(rename_all = "camelCase"))]
+pub struct AssetPnl {
+	/// Total value of the balance received.
+	#[cfg_attr(feature = "std", serde(with = "serde_balance"))]
+	pub cost: u128,
+	/// Total value of the balance sent or destroyed.
+	#[cfg_attr(feature = "std", serde(with = "serde_balance"))]
+	pub income: u128,
+	/// Value of the current balance.
+	#[cfg_attr(feature = "std", serde(with = "serde_balance"))]
+	pub value: u128,
+	/// Realized and unrealized profit, `income + value - cost`.
+	#[cfg_attr(feature = "std", serde(with = "serde_balance"))]
+	pub pnl: i128,
+}
+
+impl AssetPnl {
+	/// Calculate the profit and loss from the accounting of an account asset.
+	pub fn new(cost: u128, income: u128, value: u128) -> Self {
+		let gain = income.saturating_add(value);
+		let pnl = if gain >= cost {
+			i128::try_from(gain - cost).unwrap_or(i128::max_value())
+		} else {
+			i128::try_from(cost - gain).map(|loss| -loss).unwrap_or(i128::min_value())
+		};
+
+		Self { cost, income, value, pnl }
+	}
+}
+
 decl_runtime_apis! {
 	pub trait AssetsApi<TokenSymbol, AccountId, Balance> where
 		TokenSymbol: Codec,
 		AccountId: Codec,
 		Balance: Codec,
 	{
-		/// get balances by account id
+		/// get balances by account id, truncated to u64, use `account_asset` instead
 		fn asset_balances(token_symbol: TokenSymbol, who: AccountId) -> u64;
 
 		/// get all issued tokens by account id
 		fn asset_tokens(who: AccountId) -> Vec<TokenSymbol>;
+
+		/// get profit and loss of an asset by account id
+		fn asset_pnl(token_symbol: TokenSymbol, who: AccountId) -> AssetPnl;
+
+		/// get the asset of a token by account id
+		fn account_asset(token_symbol: TokenSymbol, who: AccountId) -> AccountAssetInfo<Balance>;
+
+		/// get the assets of all tokens by account id
+		fn account_assets(who: AccountId) -> Vec<(TokenSymbol, AccountAssetInfo<Balance>)>;
+
+		/// get metadata of a token
+		fn token_info(token_symbol: TokenSymbol) -> Option<TokenInfo<Balance>>;
+
+		/// get total issuance of a token
+		fn total_issuance(token_symbol: TokenSymbol) -> Balance;
+
+		/// get a page of the holders of a token, following the holder `start`
+		fn token_holders(token_symbol: TokenSymbol, start: Option<AccountId>, count: u32) -> Vec<(AccountId, AccountAssetInfo<Balance>)>;
 	}
 }
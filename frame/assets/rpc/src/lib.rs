@@ -23,7 +23,9 @@ use sp_api::ProvideRuntimeApi;
 use sp_blockchain::HeaderBackend;
 use sp_runtime::{generic::BlockId, traits::Block as BlockT};
 pub use self::gen_client::Client as AssetsClient;
-pub use assets_rpc_runtime_api::{self as runtime_api, AssetsApi as AssetsRuntimeApi};
+pub use assets_rpc_runtime_api::{
+	self as runtime_api, AccountAssetInfo, AssetPnl, AssetsApi as AssetsRuntimeApi, TokenInfo,
+};
 
 #[derive(Clone, Debug)]
 pub struct Assets<C, Block> {
@@ -41,8 +43,8 @@ impl<C, Block> Assets<C, Block> {
 }
 
 #[rpc]
-pub trait AssetsApi<BlockHash, TokenSymbol, AccountId, Balance> {
-	/// rpc method get balances by account id
+pub trait AssetsApi<BlockHash, TokenSymbol, AccountId, AssetInfo, TokenDetails> {
+	/// rpc method get balances by account id, truncated to u64, use `assets_getAccountAsset` instead
 	/// useage: curl -H "Content-Type: application/json" -d '{"id":1, "jsonrpc":"2.0", "method": "assets_getBalances", "params": [0, "5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY"]}' http://localhost:9933/
 	#[rpc(name = "assets_getBalances")]
 	fn asset_balances(
@@ -60,9 +62,69 @@ pub trait AssetsApi<BlockHash, TokenSymbol, AccountId, Balance> {
 		who: AccountId,
 		at: Option<BlockHash>
 	) -> JsonRpcResult<Vec<TokenSymbol>>;
+
+	/// rpc method get profit and loss of an asset by account id, values are strings
+	/// useage: curl -H "Content-Type: application/json" -d '{"id":1, "jsonrpc":"2.0", "method": "assets_getPnl", "params": [0, "5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY"]}' http://localhost:9933/
+	#[rpc(name = "assets_getPnl")]
+	fn asset_pnl(
+		&self,
+		token_symbol: TokenSymbol,
+		who: AccountId,
+		at: Option<BlockHash>
+	) -> JsonRpcResult<AssetPnl>;
+
+	/// rpc method get the asset of a token by account id, balances are strings
+	/// useage: curl -H "Content-Type: application/json" -d '{"id":1, "jsonrpc":"2.0", "method": "assets_getAccountAsset", "params": [0, "5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY"]}' http://localhost:9933/
+	#[rpc(name = "assets_getAccountAsset")]
+	fn account_asset(
+		&self,
+		token_symbol: TokenSymbol,
+		who: AccountId,
+		at: Option<BlockHash>
+	) -> JsonRpcResult<AssetInfo>;
+
+	/// rpc method get the assets of all tokens by account id, balances are strings
+	/// useage: curl -H "Content-Type: application/json" -d '{"id":1, "jsonrpc":"2.0", "method": "assets_getAccountAssets", "params": ["5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY"]}' http://localhost:9933/
+	#[rpc(name = "assets_getAccountAssets")]
+	fn account_assets(
+		&self,
+		who: AccountId,
+		at: Option<BlockHash>
+	) -> JsonRpcResult<Vec<(TokenSymbol, AssetInfo)>>;
+
+	/// rpc method get metadata of a token
+	/// useage: curl -H "Content-Type: application/json" -d '{"id":1, "jsonrpc":"2.0", "method": "assets_getTokenInfo", "params": [0]}' http://localhost:9933/
+	#[rpc(name = "assets_getTokenInfo")]
+	fn token_info(
+		&self,
+		token_symbol: TokenSymbol,
+		at: Option<BlockHash>
+	) -> JsonRpcResult<Option<TokenDetails>>;
+
+	/// rpc method get total issuance of a token as a string
+	/// useage: curl -H "Content-Type: application/json" -d '{"id":1, "jsonrpc":"2.0", "method": "assets_getTotalIssuance", "params": [0]}' http://localhost:9933/
+	#[rpc(name = "assets_getTotalIssuance")]
+	fn total_issuance(
+		&self,
+		token_symbol: TokenSymbol,
+		at: Option<BlockHash>
+	) -> JsonRpcResult<String>;
+
+	/// rpc method get a page of the holders of a token following the holder `start`, at most 100 holders a page,
+	/// the last holder of a page is the start of the next page
+	/// useage: curl -H "Content-Type: application/json" -d '{"id":1, "jsonrpc":"2.0", "method": "assets_getTokenHolders", "params": [0, null, 100]}' http://localhost:9933/
+	#[rpc(name = "assets_getTokenHolders")]
+	fn token_holders(
+		&self,
+		token_symbol: TokenSymbol,
+		start: Option<AccountId>,
+		count: u32,
+		at: Option<BlockHash>
+	) -> JsonRpcResult<Vec<(AccountId, AssetInfo)>>;
 }
 
-impl<C, Block, TokenSymbol, AccountId, Balance> AssetsApi<<Block as BlockT>::Hash, TokenSymbol, AccountId, Balance>
+impl<C, Block, TokenSymbol, AccountId, Balance>
+	AssetsApi<<Block as BlockT>::Hash, TokenSymbol, AccountId, AccountAssetInfo<Balance>, TokenInfo<Balance>>
 	for Assets<C, Block>
 where
 	Block: BlockT,
@@ -70,7 +132,7 @@ where
 	C::Api: AssetsRuntimeApi<Block, TokenSymbol, AccountId, Balance>,
 	AccountId: Codec,
 	TokenSymbol: Codec,
-	Balance: Codec,
+	Balance: Codec + std::fmt::Display,
 {
 	fn asset_balances(&self, token_symbol: TokenSymbol, who: AccountId, at: Option<<Block as BlockT>::Hash>) -> JsonRpcResult<u64> {
 		let asset_rpc_api = self.client.runtime_api();
@@ -93,4 +155,89 @@ where
 			data: Some(format!("{:?}", e).into()),
 		})
 	}
+
+	fn asset_pnl(&self, token_symbol: TokenSymbol, who: AccountId, at: Option<<Block as BlockT>::Hash>) -> JsonRpcResult<AssetPnl> {
+		let asset_rpc_api = self.client.runtime_api();
+		let at = BlockId::<Block>::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+
+		asset_rpc_api.asset_pnl(&at, token_symbol, who).map_err(|e| RpcError {
+			code: ErrorCode::InternalError,
+			message: "Failed to get profit and loss for you requested asset id.".to_owned(),
+			data: Some(format!("{:?}", e).into()),
+		})
+	}
+
+	fn account_asset(
+		&self,
+		token_symbol: TokenSymbol,
+		who: AccountId,
+		at: Option<<Block as BlockT>::Hash>
+	) -> JsonRpcResult<AccountAssetInfo<Balance>> {
+		let asset_rpc_api = self.client.runtime_api();
+		let at = BlockId::<Block>::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+
+		asset_rpc_api.account_asset(&at, token_symbol, who).map_err(|e| RpcError {
+			code: ErrorCode::InternalError,
+			message: "Failed to get asset for you requested asset id.".to_owned(),
+			data: Some(format!("{:?}", e).into()),
+		})
+	}
+
+	fn account_assets(
+		&self,
+		who: AccountId,
+		at: Option<<Block as BlockT>::Hash>
+	) -> JsonRpcResult<Vec<(TokenSymbol, AccountAssetInfo<Balance>)>> {
+		let asset_rpc_api = self.client.runtime_api();
+		let at = BlockId::<Block>::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+
+		asset_rpc_api.account_assets(&at, who).map_err(|e| RpcError {
+			code: ErrorCode::InternalError,
+			message: "Failed to get assets for you requested account.".to_owned(),
+			data: Some(format!("{:?}", e).into()),
+		})
+	}
+
+	fn token_info(
+		&self,
+		token_symbol: TokenSymbol,
+		at: Option<<Block as BlockT>::Hash>
+	) -> JsonRpcResult<Option<TokenInfo<Balance>>> {
+		let asset_rpc_api = self.client.runtime_api();
+		let at = BlockId::<Block>::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+
+		asset_rpc_api.token_info(&at, token_symbol).map_err(|e| RpcError {
+			code: ErrorCode::InternalError,
+			message: "Failed to get metadata for you requested asset id.".to_owned(),
+			data: Some(format!("{:?}", e).into()),
+		})
+	}
+
+	fn total_issuance(&self, token_symbol: TokenSymbol, at: Option<<Block as BlockT>::Hash>) -> JsonRpcResult<String> {
+		let asset_rpc_api = self.client.runtime_api();
+		let at = BlockId::<Block>::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+
+		asset_rpc_api.total_issuance(&at, token_symbol).map(|issuance| issuance.to_string()).map_err(|e| RpcError {
+			code: ErrorCode::InternalError,
+			message: "Failed to get total issuance for you requested asset id.".to_owned(),
+			data: Some(format!("{:?}", e).into()),
+		})
+	}
+
+	fn token_holders(
+		&self,
+		token_symbol: TokenSymbol,
+		start: Option<AccountId>,
+		count: u32,
+		at: Option<<Block as BlockT>::Hash>
+	) -> JsonRpcResult<Vec<(AccountId, AccountAssetInfo<Balance>)>> {
+		let asset_rpc_api = self.client.runtime_api();
+		let at = BlockId::<Block>::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+
+		asset_rpc_api.token_holders(&at, token_symbol, start, count).map_err(|e| RpcError {
+			code: ErrorCode::InternalError,
+			message: "Failed to get holders for you requested asset id.".to_owned(),
+			data: Some(format!("{:?}", e).into()),
+		})
+	}
 }
@@ -17,44 +17,94 @@
 // Ensure we're `no_std` when compiling for Wasm.
 #![cfg_attr(not(feature = "std"), no_std)]
 
+use codec::{Decode, Encode};
 use core::convert::TryInto;
 use frame_support::traits::Get;
 use frame_support::{
     decl_error, decl_event, decl_module, decl_storage, dispatch::DispatchResult, ensure,
-    IterableStorageMap, Parameter,
+    storage::generator::StorageDoubleMap as _, transactional, weights::Weight, IterableStorageMap,
+    Parameter,
 };
-use frame_system::{ensure_root, ensure_signed};
+use frame_system::{self as system, ensure_root, ensure_signed};
 use node_primitives::{
-    AccountAsset, AssetRedeem, AssetTrait, FetchConvertPrice, Token, TokenPriceHandler, TokenSymbol,
+    AccountAsset, AssetRedeem, AssetTrait, DustHandler, FetchConvertPrice, LockIdentifier,
+    PriceProvider, Token, TokenPriceHandler, TokenSymbol, TransferHandler,
 };
-use sp_runtime::traits::{
-    AtLeast32Bit, MaybeSerializeDeserialize, Member, One, Saturating, StaticLookup, Zero,
+use sp_runtime::{
+    helpers_128bit::multiply_by_rational,
+    traits::{
+        AtLeast32Bit, MaybeSerializeDeserialize, Member, One, SaturatedConversion, Saturating,
+        StaticLookup, Zero,
+    },
+    RuntimeDebug,
 };
 use sp_std::prelude::*;
 
+mod migrations;
 mod mock;
 mod tests;
 
-lazy_static::lazy_static! {
-    /// (token, precision)
-    pub static ref TOKEN_LIST: [(Vec<u8>, u16); 9] = {
-        let ausd = (b"aUSD".to_vec(), 18);
-        let dot = (b"DOT".to_vec(), 12);
-        let vdot = (b"vDOT".to_vec(), 12);
-        let ksm = (b"KSM".to_vec(), 12);
-        let vksm = (b"vKSM".to_vec(), 12);
-        let eos = (b"EOS".to_vec(), 4);
-        let veos = (b"vEOS".to_vec(), 4);
-        let iost = (b"IOST".to_vec(), 12);
-        let viost = (b"vIOST".to_vec(), 12);
-        [ausd, dot, vdot, ksm, vksm, eos, veos, iost, viost]
-    };
+/// Maximum length of a token symbol.
+const MAX_SYMBOL_LENGTH: usize = 32;
+/// Maximum length of a token name.
+const MAX_NAME_LENGTH: usize = 64;
+/// Maximum length of a token metadata uri.
+const MAX_METADATA_URI_LENGTH: usize = 256;
+/// Maximum number of transfers in a batch.
+const MAX_BATCH_TRANSFERS: usize = 64;
+/// Maximum number of token holders in a page.
+const MAX_HOLDERS_PAGE_SIZE: u32 = 100;
+
+/// Storage layout versions of the pallet, a new release comes with its migration in `migrations`.
+#[derive(Encode, Decode, Clone, Copy, PartialEq, Eq, RuntimeDebug)]
+pub enum Releases {
+    /// Account assets are only indexed by their accounts in `AccountAssetIds`.
+    V1_0_0,
+    /// Account assets are indexed by their tokens in `TokenHolders` as well.
+    V2_0_0,
+}
+
+impl Default for Releases {
+    fn default() -> Self {
+        Releases::V1_0_0
+    }
+}
+
+/// An amount of balance held by a named lock or reserve.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug)]
+pub struct NamedBalance<Balance> {
+    /// An identifier for this lock or reserve. Only one per account per token.
+    pub id: LockIdentifier,
+    /// The amount which is frozen or reserved.
+    pub amount: Balance,
+}
+
+/// An amount of balance an owner allows a spender to transfer on behalf of it.
+#[derive(Encode, Decode, Default, Clone, PartialEq, Eq, RuntimeDebug)]
+pub struct Allowance<Balance, BlockNumber> {
+    /// The amount which can still be transferred by the spender.
+    pub amount: Balance,
+    /// The allowance cannot be used after this block, if any.
+    pub expiry: Option<BlockNumber>,
+}
+
+/// Accounts holding the roles of a token.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug)]
+pub struct TokenRoles<AccountId> {
+    /// Can change the other roles and transfer the ownership.
+    pub owner: AccountId,
+    /// Can issue the token.
+    pub issuer: AccountId,
+    /// Can freeze an account asset or the whole token.
+    pub freezer: AccountId,
+    /// Can thaw an account asset or the whole token.
+    pub admin: AccountId,
 }
 
 /// The module configuration trait.
-pub trait Trait: frame_system::Trait {
+pub trait Trait: system::Trait {
     /// The overarching event type.
-    type Event: From<Event<Self>> + Into<<Self as frame_system::Trait>::Event>;
+    type Event: From<Event<Self>> + Into<<Self as system::Trait>::Event>;
 
     /// The units in which we record balances.
     type Balance: Member
@@ -119,13 +169,29 @@ pub trait Trait: frame_system::Trait {
 
     /// Handler for fetch convert rate from convert runtime
     type FetchConvertPrice: FetchConvertPrice<TokenSymbol, Self::Convert>;
+
+    /// Handler for the dust of reaped account assets
+    type DustHandler: DustHandler<TokenSymbol, Self::AccountId, Self::Balance>;
+
+    /// Market prices of tokens, `Prices` follow them in aUSD
+    type PriceProvider: PriceProvider<TokenSymbol, Self::Price>;
+
+    /// The price of a token worth one aUSD, prices are quoted with this precision.
+    type PricePrecision: Get<Self::Price>;
+
+    /// Maximum number of tokens whose prices follow the market in a block.
+    type MaxPriceUpdates: Get<u32>;
+
+    /// Handler for the assets transferred between accounts
+    type TransferHandler: TransferHandler<TokenSymbol, Self::AccountId, Self::Balance>;
 }
 
 decl_event! {
     pub enum Event<T>
-        where <T as frame_system::Trait>::AccountId,
+        where <T as system::Trait>::AccountId,
             <T as Trait>::Balance,
             <T as Trait>::AssetId,
+            <T as system::Trait>::BlockNumber,
     {
         /// Some assets were created.
         Created(AssetId, Token<Balance>),
@@ -139,6 +205,26 @@ decl_event! {
         AccountAssetCreated(AccountId, AssetId),
         /// Bind Asset with AccountId
         AccountAssetDestroy(AccountId, AssetId),
+        /// A vtoken was paired with its token, (token, vtoken).
+        TokenPaired(TokenSymbol, TokenSymbol),
+        /// Minimum balance of a token was set.
+        MinBalanceSet(TokenSymbol, Balance),
+        /// An account asset below minimum balance was reaped, the remaining balance is dust.
+        DustRemoved(TokenSymbol, AccountId, Balance),
+        /// The allowance of a spender was changed, (token, owner, spender, amount, expiry).
+        Approved(TokenSymbol, AccountId, AccountId, Balance, Option<BlockNumber>),
+        /// A batch of transfers was made, (sender, number of transfers).
+        BatchTransferred(AccountId, u32),
+        /// The roles of a token were changed.
+        RolesChanged(TokenSymbol, TokenRoles<AccountId>),
+        /// An account asset was frozen.
+        AccountFrozen(TokenSymbol, AccountId),
+        /// An account asset was thawed.
+        AccountThawed(TokenSymbol, AccountId),
+        /// A token was frozen.
+        TokenFrozen(TokenSymbol),
+        /// A token was thawed.
+        TokenThawed(TokenSymbol),
     }
 }
 
@@ -164,6 +250,28 @@ decl_error! {
         InvalidConvertRate,
         /// Vtoken id is not equal to token id
         InvalidTokenPair,
+        /// Token name is too long
+        TokenNameTooLong,
+        /// Token metadata uri is too long
+        MetadataUriTooLong,
+        /// The token has been paired already
+        TokenPairExisted,
+        /// Balance of the account asset would be below the minimum balance of the token
+        BelowMinimumBalance,
+        /// Amount is more than the spender is allowed to transfer
+        AllowanceExceeded,
+        /// The allowance has expired
+        AllowanceExpired,
+        /// There are too many transfers in a batch
+        TooManyTransfers,
+        /// The origin doesn't hold the role of the token
+        NoPermission,
+        /// The account asset is frozen
+        AccountFrozen,
+        /// The token is frozen
+        TokenFrozen,
+        /// The token can only be burnt by the module recording its supply
+        TokenNotBurnable,
     }
 }
 
@@ -176,31 +284,63 @@ decl_storage! {
         pub Prices get(fn prices) config(): map hasher(blake2_128_concat) TokenSymbol => T::Price;
         /// The next asset identifier up for grabs.
         pub NextAssetId get(fn next_asset_id) config(): T::AssetId;
+        /// The next asset identifier whose price follows the market.
+        pub PriceCursor get(fn price_cursor): T::AssetId;
         /// Details of the token corresponding to an asset id.
         pub Tokens get(fn token_details) config(): map hasher(blake2_128_concat) TokenSymbol => Token<T::Balance>;
         /// A collection of asset which an account owned
         pub AccountAssetIds get(fn account_asset_ids): map hasher(blake2_128_concat) T::AccountId => Vec<TokenSymbol>;
+        /// The accounts holding a token, an entry for each account asset.
+        pub TokenHolders get(fn token_holder): double_map hasher(blake2_128_concat) TokenSymbol,
+            hasher(blake2_128_concat) T::AccountId => ();
+        /// Storage layout version, new chains start with the latest one.
+        StorageVersion build(|_| Releases::V2_0_0): Releases;
+        /// Index from the symbol bytes of a token to its identifier.
+        pub TokenSymbolIds get(fn token_symbol_id): map hasher(blake2_128_concat) Vec<u8> => Option<TokenSymbol>;
+        /// The (token, vtoken) pair a token belongs to, both token and vtoken are keys.
+        pub TokenPairs get(fn token_pair): map hasher(blake2_128_concat) TokenSymbol => Option<(TokenSymbol, TokenSymbol)>;
+        /// Named locks on the free balance of an account asset, the biggest one is frozen.
+        pub Locks get(fn locks): map hasher(blake2_128_concat) (TokenSymbol, T::AccountId) => Vec<NamedBalance<T::Balance>>;
+        /// Named reserves of an account asset, the sum of them is reserved.
+        pub Reserves get(fn reserves): map hasher(blake2_128_concat) (TokenSymbol, T::AccountId) => Vec<NamedBalance<T::Balance>>;
+        /// The allowance an account asset owner approved to a spender.
+        pub Allowances get(fn allowances): double_map hasher(blake2_128_concat) (TokenSymbol, T::AccountId),
+            hasher(blake2_128_concat) T::AccountId => Option<Allowance<T::Balance, T::BlockNumber>>;
+        /// The roles of a token, tokens without roles are managed by root only.
+        pub Roles get(fn token_roles): map hasher(blake2_128_concat) TokenSymbol => Option<TokenRoles<T::AccountId>>;
+        /// Account assets which cannot be moved.
+        pub FrozenAccounts get(fn account_frozen): map hasher(blake2_128_concat) (TokenSymbol, T::AccountId) => bool;
+        /// Tokens which cannot be moved by any account.
+        pub FrozenTokens get(fn token_frozen): map hasher(blake2_128_concat) TokenSymbol => bool;
     }
     add_extra_genesis {
+        // (token, vtoken) pairs registered at genesis.
+        config(token_pairs): Vec<(TokenSymbol, TokenSymbol)>;
         build(|config: &GenesisConfig<T>| {
             // initalize assets for account
             for ((token_symbol, who), asset) in config.account_assets.iter() {
                 <AccountAssets<T>>::insert((token_symbol, who), asset);
+                <TokenHolders<T>>::insert(token_symbol, who, ());
             }
-            // initialze three assets id for these tokens
-            <NextAssetId<T>>::put(config.next_asset_id);
-
-            // now, not support iost, so leave 6 here.
-            for i in 0..=6 {
-                // initialize token
-                let current_token = &TOKEN_LIST[i as usize];
-                let token = Token::new(current_token.0.clone(), current_token.1, 0.into());
 
-                let token_symbol = TokenSymbol::from(i as u32);
+            // register all tokens, next asset id must be bigger than any of them.
+            let mut next_asset_id = config.next_asset_id;
+            for (token_symbol, token) in config.token_details.iter() {
                 <Tokens<T>>::insert(token_symbol, token);
+                <TokenSymbolIds>::insert(&token.symbol, token_symbol);
 
-                // initialize price
-                <Prices<T>>::insert(token_symbol, T::Price::from(0u32));
+                let id: T::AssetId = (*token_symbol).into();
+                if id >= next_asset_id {
+                    next_asset_id = id + One::one();
+                }
+            }
+            <NextAssetId<T>>::put(next_asset_id);
+
+            for (token, vtoken) in config.token_pairs.iter() {
+                assert!(<Tokens<T>>::contains_key(token), "paired token is not registered");
+                assert!(<Tokens<T>>::contains_key(vtoken), "paired vtoken is not registered");
+                <TokenPairs>::insert(token, (token, vtoken));
+                <TokenPairs>::insert(vtoken, (token, vtoken));
             }
         });
     }
@@ -212,37 +352,150 @@ decl_module! {
 
         fn deposit_event() = default;
 
+        fn on_runtime_upgrade() -> Weight {
+            migrations::migrate::<T>()
+        }
+
+        fn on_initialize() -> Weight {
+            // follow the market prices of a few tokens in turn, tokens without a market price keep
+            // their current price
+            let next_asset_id = Self::next_asset_id();
+            let updates = T::MaxPriceUpdates::get().min(next_asset_id.saturated_into());
+            let mut cursor = Self::price_cursor();
+            for _ in 0..updates {
+                if cursor >= next_asset_id {
+                    cursor = Zero::zero();
+                }
+                let token_symbol: TokenSymbol = cursor.into();
+                cursor += One::one();
+                if token_symbol == TokenSymbol::aUSD || !<Tokens<T>>::contains_key(token_symbol) {
+                    continue;
+                }
+                if let Some(price) = T::PriceProvider::get_price(token_symbol, TokenSymbol::aUSD, T::PricePrecision::get()) {
+                    <Prices<T>>::insert(token_symbol, price);
+                }
+            }
+            <PriceCursor<T>>::put(cursor);
+
+            // a market price is read from the oracle pair and two cumulative prices
+            let updates = Weight::from(updates);
+            T::DbWeight::get().reads_writes(2 + 5 * updates, 1 + updates)
+        }
+
         /// Create a new class of fungible assets. It will have an
         /// identifier `AssetId` instance: this will be specified in the `Created` event.
-        #[weight = T::DbWeight::get().writes(1)]
-        pub fn create(origin, symbol: Vec<u8>, precision: u16) -> DispatchResult {
+        /// If `owner` is given, it holds all roles of the token at first.
+        #[weight = T::DbWeight::get().writes(2)]
+        pub fn create(
+            origin,
+            symbol: Vec<u8>,
+            precision: u16,
+            owner: Option<<T::Lookup as StaticLookup>::Source>,
+        ) -> DispatchResult {
             ensure_root(origin)?;
 
             ensure!(!symbol.is_empty(), Error::<T>::EmptyTokenSymbol);
-            ensure!(symbol.len() <= 32, Error::<T>::TokenSymbolTooLong);
+            ensure!(symbol.len() <= MAX_SYMBOL_LENGTH, Error::<T>::TokenSymbolTooLong);
             ensure!(precision <= 18, Error::<T>::InvalidPrecision); // increase to precision 18
+            let owner = owner.map(T::Lookup::lookup).transpose()?;
 
             let (id, token) = Self::asset_create(symbol, precision)?;
 
             Self::deposit_event(RawEvent::Created(id, token));
 
+            if let Some(owner) = owner {
+                let token_symbol: TokenSymbol = id.into();
+                let roles = TokenRoles {
+                    owner: owner.clone(),
+                    issuer: owner.clone(),
+                    freezer: owner.clone(),
+                    admin: owner,
+                };
+                <Roles<T>>::insert(token_symbol, &roles);
+
+                Self::deposit_event(RawEvent::RolesChanged(token_symbol, roles));
+            }
+
             Ok(())
         }
 
-        /// Issue any amount of fungible assets.
+        /// Register a new token with its metadata. If `paired_token` is given, the new token is
+        /// registered as the vtoken of it.
+        #[weight = T::DbWeight::get().reads_writes(3, 5)]
+        pub fn register_token(
+            origin,
+            symbol: Vec<u8>,
+            precision: u16,
+            name: Vec<u8>,
+            metadata_uri: Vec<u8>,
+            paired_token: Option<TokenSymbol>,
+        ) -> DispatchResult {
+            ensure_root(origin)?;
+
+            ensure!(!symbol.is_empty(), Error::<T>::EmptyTokenSymbol);
+            ensure!(symbol.len() <= MAX_SYMBOL_LENGTH, Error::<T>::TokenSymbolTooLong);
+            ensure!(precision <= 18, Error::<T>::InvalidPrecision);
+            ensure!(name.len() <= MAX_NAME_LENGTH, Error::<T>::TokenNameTooLong);
+            ensure!(metadata_uri.len() <= MAX_METADATA_URI_LENGTH, Error::<T>::MetadataUriTooLong);
+            if let Some(token_symbol) = paired_token {
+                ensure!(<Tokens<T>>::contains_key(token_symbol), Error::<T>::TokenNotExist);
+                ensure!(!<TokenPairs>::contains_key(token_symbol), Error::<T>::TokenPairExisted);
+            }
+
+            let (id, _) = Self::asset_create(symbol, precision)?;
+            let vtoken_symbol: TokenSymbol = id.into();
+            let token = <Tokens<T>>::mutate(vtoken_symbol, |token| {
+                token.name = name;
+                token.metadata_uri = metadata_uri;
+                token.clone()
+            });
+
+            Self::deposit_event(RawEvent::Created(id, token));
+
+            if let Some(token_symbol) = paired_token {
+                <TokenPairs>::insert(token_symbol, (token_symbol, vtoken_symbol));
+                <TokenPairs>::insert(vtoken_symbol, (token_symbol, vtoken_symbol));
+
+                Self::deposit_event(RawEvent::TokenPaired(token_symbol, vtoken_symbol));
+            }
+
+            Ok(())
+        }
+
+        /// Set the minimum balance of a token. Account assets below it are reaped once their
+        /// balance decreases.
         #[weight = T::DbWeight::get().reads_writes(1, 1)]
+        pub fn set_min_balance(
+            origin,
+            token_symbol: TokenSymbol,
+            #[compact] min_balance: T::Balance,
+        ) {
+            ensure_root(origin)?;
+
+            ensure!(<Tokens<T>>::contains_key(token_symbol), Error::<T>::TokenNotExist);
+
+            <Tokens<T>>::mutate(token_symbol, |token| {
+                token.min_balance = min_balance;
+            });
+
+            Self::deposit_event(RawEvent::MinBalanceSet(token_symbol, min_balance));
+        }
+
+        /// Issue any amount of fungible assets, by root or the issuer of the token.
+        #[weight = T::DbWeight::get().reads_writes(2, 1)]
         pub fn issue(
             origin,
             token_symbol: TokenSymbol,
             target: <T::Lookup as StaticLookup>::Source,
             #[compact] amount: T::Balance,
         ) {
-            ensure_root(origin)?;
+            Self::ensure_role(origin, token_symbol, |roles| &roles.issuer)?;
 
             ensure!(<Tokens<T>>::contains_key(token_symbol), Error::<T>::TokenNotExist);
 
             let target = T::Lookup::lookup(target)?;
             ensure!(!amount.is_zero(), Error::<T>::ZeroAmountOfBalance);
+            Self::ensure_above_min_balance(token_symbol, &target, amount)?;
 
             Self::asset_issue(token_symbol, &target, amount);
 
@@ -250,7 +503,7 @@ decl_module! {
         }
 
         /// Move some assets from one holder to another.
-        #[weight = T::DbWeight::get().reads_writes(1, 1)]
+        #[weight = weight_for::transfer::<T>()]
         pub fn transfer(
             origin,
             token_symbol: TokenSymbol,
@@ -258,19 +511,37 @@ decl_module! {
             #[compact] amount: T::Balance,
         ) {
             let origin = ensure_signed(origin)?;
-
-            let origin_account = (token_symbol, origin.clone());
-            let origin_balance = <AccountAssets<T>>::get(&origin_account).balance;
             let target = T::Lookup::lookup(target)?;
 
-            ensure!(!amount.is_zero(), Error::<T>::ZeroAmountOfBalance);
-            ensure!(origin_balance >= amount, Error::<T>::InvalidBalanceForTransaction);
-
-            Self::asset_transfer(token_symbol, origin.clone(), target.clone(), amount);
+            Self::do_transfer(token_symbol, &origin, &target, amount)?;
 
             Self::deposit_event(RawEvent::Transferred(token_symbol, origin, target, amount));
         }
 
+        /// Move several assets to several holders, (token, target, amount) for each transfer.
+        /// Either all of them succeed or none is made.
+        #[weight = weight_for::transfer_batch::<T>(transfers.len())]
+        #[transactional]
+        pub fn transfer_batch(
+            origin,
+            transfers: Vec<(TokenSymbol, <T::Lookup as StaticLookup>::Source, T::Balance)>,
+        ) {
+            let origin = ensure_signed(origin)?;
+
+            ensure!(transfers.len() <= MAX_BATCH_TRANSFERS, Error::<T>::TooManyTransfers);
+
+            let count = transfers.len() as u32;
+            for (token_symbol, target, amount) in transfers {
+                let target = T::Lookup::lookup(target)?;
+
+                Self::do_transfer(token_symbol, &origin, &target, amount)?;
+
+                Self::deposit_event(RawEvent::Transferred(token_symbol, origin.clone(), target, amount));
+            }
+
+            Self::deposit_event(RawEvent::BatchTransferred(origin, count));
+        }
+
         /// Destroy any amount of assets of `id` owned by `origin`.
         #[weight = T::DbWeight::get().reads_writes(1, 1)]
         pub fn destroy(
@@ -282,8 +553,10 @@ decl_module! {
 
             let origin_account = (token_symbol, origin.clone());
 
-            let balance = <AccountAssets<T>>::get(&origin_account).balance;
+            let balance = <AccountAssets<T>>::get(&origin_account).available;
             ensure!(amount <= balance , Error::<T>::InvalidBalanceForTransaction);
+            Self::ensure_not_frozen(token_symbol, &origin)?;
+            ensure!(T::TransferHandler::is_burnable(token_symbol), Error::<T>::TokenNotBurnable);
 
             Self::asset_destroy(token_symbol, &origin, amount);
 
@@ -301,13 +574,218 @@ decl_module! {
 
             let origin_account = (token_symbol, origin.clone());
 
-            let balance = <AccountAssets<T>>::get(&origin_account).balance;
+            let balance = <AccountAssets<T>>::get(&origin_account).available;
             ensure!(amount <= balance , Error::<T>::InvalidBalanceForTransaction);
+            Self::ensure_not_frozen(token_symbol, &origin)?;
+            ensure!(T::TransferHandler::is_burnable(token_symbol), Error::<T>::TokenNotBurnable);
 
             T::AssetRedeem::asset_redeem(token_symbol, origin.clone(), amount, to_name);
 
             Self::asset_destroy(token_symbol, &origin, amount);
         }
+
+        /// Allow `spender` to transfer at most `amount` of `origin`'s assets until the block
+        /// `expiry`, replacing any allowance approved before. A zero amount removes the allowance.
+        #[weight = T::DbWeight::get().reads_writes(1, 1)]
+        pub fn approve(
+            origin,
+            spender: <T::Lookup as StaticLookup>::Source,
+            token_symbol: TokenSymbol,
+            #[compact] amount: T::Balance,
+            expiry: Option<T::BlockNumber>,
+        ) {
+            let origin = ensure_signed(origin)?;
+            let spender = T::Lookup::lookup(spender)?;
+
+            ensure!(<Tokens<T>>::contains_key(token_symbol), Error::<T>::TokenNotExist);
+            if let Some(expiry) = expiry {
+                let now = <system::Module<T>>::block_number();
+                ensure!(expiry >= now, Error::<T>::AllowanceExpired);
+            }
+
+            let key = (token_symbol, origin.clone());
+            if amount.is_zero() {
+                <Allowances<T>>::remove(&key, &spender);
+            } else {
+                <Allowances<T>>::insert(&key, &spender, Allowance { amount, expiry });
+            }
+
+            Self::deposit_event(RawEvent::Approved(token_symbol, origin, spender, amount, expiry));
+        }
+
+        /// Increase the allowance of `spender` by `amount`, the expiry is kept.
+        #[weight = T::DbWeight::get().reads_writes(1, 1)]
+        pub fn increase_allowance(
+            origin,
+            spender: <T::Lookup as StaticLookup>::Source,
+            token_symbol: TokenSymbol,
+            #[compact] amount: T::Balance,
+        ) {
+            let origin = ensure_signed(origin)?;
+            let spender = T::Lookup::lookup(spender)?;
+
+            ensure!(<Tokens<T>>::contains_key(token_symbol), Error::<T>::TokenNotExist);
+            ensure!(!amount.is_zero(), Error::<T>::ZeroAmountOfBalance);
+
+            let key = (token_symbol, origin.clone());
+            let mut allowance = <Allowances<T>>::get(&key, &spender).unwrap_or_default();
+            ensure!(!Self::allowance_expired(&allowance), Error::<T>::AllowanceExpired);
+
+            allowance.amount = allowance.amount.saturating_add(amount);
+            <Allowances<T>>::insert(&key, &spender, &allowance);
+
+            Self::deposit_event(RawEvent::Approved(
+                token_symbol,
+                origin,
+                spender,
+                allowance.amount,
+                allowance.expiry,
+            ));
+        }
+
+        /// Decrease the allowance of `spender` by `amount`, the allowance is removed once it
+        /// reaches zero.
+        #[weight = T::DbWeight::get().reads_writes(1, 1)]
+        pub fn decrease_allowance(
+            origin,
+            spender: <T::Lookup as StaticLookup>::Source,
+            token_symbol: TokenSymbol,
+            #[compact] amount: T::Balance,
+        ) {
+            let origin = ensure_signed(origin)?;
+            let spender = T::Lookup::lookup(spender)?;
+
+            let key = (token_symbol, origin.clone());
+            let mut allowance = <Allowances<T>>::get(&key, &spender).unwrap_or_default();
+            ensure!(allowance.amount >= amount, Error::<T>::AllowanceExceeded);
+
+            allowance.amount -= amount;
+            if allowance.amount.is_zero() {
+                <Allowances<T>>::remove(&key, &spender);
+            } else {
+                <Allowances<T>>::insert(&key, &spender, &allowance);
+            }
+
+            Self::deposit_event(RawEvent::Approved(
+                token_symbol,
+                origin,
+                spender,
+                allowance.amount,
+                allowance.expiry,
+            ));
+        }
+
+        /// Move some assets of `owner` to `target` within the allowance `owner` approved to
+        /// `origin`.
+        #[weight = weight_for::transfer_from::<T>()]
+        pub fn transfer_from(
+            origin,
+            owner: <T::Lookup as StaticLookup>::Source,
+            target: <T::Lookup as StaticLookup>::Source,
+            token_symbol: TokenSymbol,
+            #[compact] amount: T::Balance,
+        ) {
+            let origin = ensure_signed(origin)?;
+            let owner = T::Lookup::lookup(owner)?;
+            let target = T::Lookup::lookup(target)?;
+
+            ensure!(!amount.is_zero(), Error::<T>::ZeroAmountOfBalance);
+
+            Self::asset_transfer_from(token_symbol, &owner, &origin, &target, amount)?;
+
+            Self::deposit_event(RawEvent::Transferred(token_symbol, owner, target, amount));
+        }
+
+        /// Change the issuer, freezer and admin of a token, by root or the owner of the token.
+        #[weight = T::DbWeight::get().reads_writes(1, 1)]
+        pub fn set_team(
+            origin,
+            token_symbol: TokenSymbol,
+            issuer: <T::Lookup as StaticLookup>::Source,
+            freezer: <T::Lookup as StaticLookup>::Source,
+            admin: <T::Lookup as StaticLookup>::Source,
+        ) {
+            Self::ensure_role(origin, token_symbol, |roles| &roles.owner)?;
+            let issuer = T::Lookup::lookup(issuer)?;
+            let freezer = T::Lookup::lookup(freezer)?;
+            let admin = T::Lookup::lookup(admin)?;
+
+            let mut roles = <Roles<T>>::get(token_symbol).ok_or(Error::<T>::NoPermission)?;
+            roles.issuer = issuer;
+            roles.freezer = freezer;
+            roles.admin = admin;
+            <Roles<T>>::insert(token_symbol, &roles);
+
+            Self::deposit_event(RawEvent::RolesChanged(token_symbol, roles));
+        }
+
+        /// Transfer the ownership of a token, by root or the owner of the token.
+        #[weight = T::DbWeight::get().reads_writes(1, 1)]
+        pub fn transfer_ownership(
+            origin,
+            token_symbol: TokenSymbol,
+            new_owner: <T::Lookup as StaticLookup>::Source,
+        ) {
+            Self::ensure_role(origin, token_symbol, |roles| &roles.owner)?;
+            let new_owner = T::Lookup::lookup(new_owner)?;
+
+            let mut roles = <Roles<T>>::get(token_symbol).ok_or(Error::<T>::NoPermission)?;
+            roles.owner = new_owner;
+            <Roles<T>>::insert(token_symbol, &roles);
+
+            Self::deposit_event(RawEvent::RolesChanged(token_symbol, roles));
+        }
+
+        /// Disallow any movement of the assets of `who`, by root or the freezer of the token.
+        #[weight = T::DbWeight::get().reads_writes(1, 1)]
+        pub fn freeze(
+            origin,
+            token_symbol: TokenSymbol,
+            who: <T::Lookup as StaticLookup>::Source,
+        ) {
+            Self::ensure_role(origin, token_symbol, |roles| &roles.freezer)?;
+            let who = T::Lookup::lookup(who)?;
+
+            <FrozenAccounts<T>>::insert((token_symbol, &who), true);
+
+            Self::deposit_event(RawEvent::AccountFrozen(token_symbol, who));
+        }
+
+        /// Allow movement of the assets of `who` again, by root or the admin of the token.
+        #[weight = T::DbWeight::get().reads_writes(1, 1)]
+        pub fn thaw(
+            origin,
+            token_symbol: TokenSymbol,
+            who: <T::Lookup as StaticLookup>::Source,
+        ) {
+            Self::ensure_role(origin, token_symbol, |roles| &roles.admin)?;
+            let who = T::Lookup::lookup(who)?;
+
+            <FrozenAccounts<T>>::remove((token_symbol, &who));
+
+            Self::deposit_event(RawEvent::AccountThawed(token_symbol, who));
+        }
+
+        /// Disallow any movement of a token, by root or the freezer of the token.
+        #[weight = T::DbWeight::get().reads_writes(1, 1)]
+        pub fn freeze_token(origin, token_symbol: TokenSymbol) {
+            Self::ensure_role(origin, token_symbol, |roles| &roles.freezer)?;
+            ensure!(<Tokens<T>>::contains_key(token_symbol), Error::<T>::TokenNotExist);
+
+            <FrozenTokens>::insert(token_symbol, true);
+
+            Self::deposit_event(RawEvent::TokenFrozen(token_symbol));
+        }
+
+        /// Allow movement of a token again, by root or the admin of the token.
+        #[weight = T::DbWeight::get().reads_writes(1, 1)]
+        pub fn thaw_token(origin, token_symbol: TokenSymbol) {
+            Self::ensure_role(origin, token_symbol, |roles| &roles.admin)?;
+
+            <FrozenTokens>::remove(token_symbol);
+
+            Self::deposit_event(RawEvent::TokenThawed(token_symbol));
+        }
     }
 }
 
@@ -317,11 +795,7 @@ impl<T: Trait> AssetTrait<T::AssetId, T::AccountId, T::Balance, T::Cost, T::Inco
         symbol: Vec<u8>,
         precision: u16,
     ) -> Result<(T::AssetId, Token<T::Balance>), Self::Error> {
-        for (_, token) in <Tokens<T>>::iter() {
-            if token.symbol.eq(&symbol) {
-                return Err(Error::<T>::TokenExisted);
-            }
-        }
+        ensure!(!<TokenSymbolIds>::contains_key(&symbol), Error::<T>::TokenExisted);
 
         let id = Self::next_asset_id();
         <NextAssetId<T>>::mutate(|id| *id += One::one());
@@ -335,19 +809,18 @@ impl<T: Trait> AssetTrait<T::AssetId, T::AccountId, T::Balance, T::Cost, T::Inco
 
         // Insert to storage
         <Tokens<T>>::insert(token_symbol, token.clone());
+        <TokenSymbolIds>::insert(symbol, token_symbol);
 
         Ok((id, token))
     }
 
     fn asset_issue(token_symbol: TokenSymbol, target: &T::AccountId, amount: T::Balance) {
-        let convert_rate = T::FetchConvertPrice::fetch_convert_price(token_symbol);
+        let value = Self::asset_value(token_symbol, amount);
         let target_asset = (token_symbol, target.clone());
         <AccountAssets<T>>::mutate(&target_asset, |asset| {
             asset.balance = asset.balance.saturating_add(amount);
-            asset.available = asset.available.saturating_add(amount);
-            asset.cost = asset
-                .cost
-                .saturating_add(amount.saturating_mul(convert_rate.into()).into());
+            asset.update_available();
+            asset.cost = asset.cost.saturating_add(value.into());
         });
 
         // save asset id for this account
@@ -361,6 +834,7 @@ impl<T: Trait> AssetTrait<T::AssetId, T::AccountId, T::Balance, T::Cost, T::Inco
         } else {
             <AccountAssetIds<T>>::insert(&target, vec![token_symbol]);
         }
+        <TokenHolders<T>>::insert(token_symbol, &target, ());
 
         <Tokens<T>>::mutate(token_symbol, |token| {
             token.total_supply = token.total_supply.saturating_add(amount);
@@ -372,19 +846,30 @@ impl<T: Trait> AssetTrait<T::AssetId, T::AccountId, T::Balance, T::Cost, T::Inco
     }
 
     fn asset_destroy(token_symbol: TokenSymbol, target: &T::AccountId, amount: T::Balance) {
-        let convert_rate = T::FetchConvertPrice::fetch_convert_price(token_symbol);
+        let value = Self::asset_value(token_symbol, amount);
         let target_asset = (token_symbol, target);
         <AccountAssets<T>>::mutate(target_asset, |asset| {
             asset.balance = asset.balance.saturating_sub(amount);
-            asset.available = asset.available.saturating_sub(amount);
-            asset.income = asset
-                .income
-                .saturating_add(amount.saturating_mul(convert_rate.into()).into());
+            asset.update_available();
+            asset.income = asset.income.saturating_add(value.into());
         });
 
         <Tokens<T>>::mutate(token_symbol, |token| {
             token.total_supply = token.total_supply.saturating_sub(amount);
         });
+
+        Self::reap_account_asset(token_symbol, target);
+    }
+
+    fn asset_move(token_symbol: TokenSymbol, from: &T::AccountId, to: &T::AccountId, amount: T::Balance) {
+        Self::move_balance(token_symbol, from, to, amount);
+
+        // keep the dust of the module accounts
+        if <AccountAssets<T>>::get((token_symbol, from)).balance.is_zero() {
+            Self::reap_account_asset(token_symbol, from);
+        }
+
+        T::TransferHandler::handle_transfer(token_symbol, from, to, amount);
     }
 
     fn asset_id_exists(who: &T::AccountId, symbol: &[u8], precision: u16) -> Option<TokenSymbol> {
@@ -413,32 +898,145 @@ impl<T: Trait> AssetTrait<T::AssetId, T::AccountId, T::Balance, T::Cost, T::Inco
         <Tokens<T>>::get(&token_symbol)
     }
 
-    fn lock_asset(who: &T::AccountId, token_symbol: TokenSymbol, locked: T::Balance) {
-        let target_asset = (token_symbol, who);
-        <AccountAssets<T>>::mutate(target_asset, |asset| {
-            asset.locked = locked;
-            asset.available = asset.balance - asset.locked;
+    fn get_token_pair(token_symbol: TokenSymbol) -> Option<(TokenSymbol, TokenSymbol)> {
+        <TokenPairs>::get(token_symbol)
+    }
+
+    fn set_lock(id: LockIdentifier, token_symbol: TokenSymbol, who: &T::AccountId, amount: T::Balance) {
+        let key = (token_symbol, who.clone());
+        let mut locks = <Locks<T>>::get(&key);
+        match locks.iter_mut().find(|l| l.id == id) {
+            Some(lock) => lock.amount = amount,
+            None => locks.push(NamedBalance { id, amount }),
+        }
+        Self::update_locks(&key, locks);
+    }
+
+    fn remove_lock(id: LockIdentifier, token_symbol: TokenSymbol, who: &T::AccountId) {
+        let key = (token_symbol, who.clone());
+        let mut locks = <Locks<T>>::get(&key);
+        locks.retain(|l| l.id != id);
+        Self::update_locks(&key, locks);
+
+        Self::reap_account_asset(token_symbol, who);
+    }
+
+    fn reserve(
+        id: LockIdentifier,
+        token_symbol: TokenSymbol,
+        who: &T::AccountId,
+        amount: T::Balance,
+    ) -> Result<(), Self::Error> {
+        let key = (token_symbol, who.clone());
+        ensure!(
+            <AccountAssets<T>>::get(&key).available >= amount,
+            Error::<T>::InvalidBalanceForTransaction
+        );
+        Self::ensure_not_frozen(token_symbol, who)?;
+
+        <Reserves<T>>::mutate(&key, |reserves| {
+            match reserves.iter_mut().find(|r| r.id == id) {
+                Some(reserve) => reserve.amount = reserve.amount.saturating_add(amount),
+                None => reserves.push(NamedBalance { id, amount }),
+            }
+        });
+        <AccountAssets<T>>::mutate(&key, |asset| {
+            asset.reserved = asset.reserved.saturating_add(amount);
+            asset.update_available();
         });
+
+        Ok(())
     }
 
-    fn unlock_asset(who: &T::AccountId, token_symbol: TokenSymbol, locked: T::Balance) {
-        let target_asset = (token_symbol, who);
-        <AccountAssets<T>>::mutate(target_asset, |asset| {
-            asset.balance = asset.balance.saturating_sub(locked);
-            asset.locked = 0.into();
-            asset.available = asset.balance;
+    fn unreserve(
+        id: LockIdentifier,
+        token_symbol: TokenSymbol,
+        who: &T::AccountId,
+        amount: T::Balance,
+    ) -> T::Balance {
+        let key = (token_symbol, who.clone());
+        let unreserved = Self::take_reserve(&key, id, amount);
+        <AccountAssets<T>>::mutate(&key, |asset| {
+            asset.reserved = asset.reserved.saturating_sub(unreserved);
+            asset.update_available();
         });
+
+        Self::reap_account_asset(token_symbol, who);
+
+        unreserved
+    }
+
+    fn slash_reserved(
+        id: LockIdentifier,
+        token_symbol: TokenSymbol,
+        who: &T::AccountId,
+        amount: T::Balance,
+    ) -> T::Balance {
+        let key = (token_symbol, who.clone());
+        let slashed = Self::take_reserve(&key, id, amount);
+        <AccountAssets<T>>::mutate(&key, |asset| {
+            asset.reserved = asset.reserved.saturating_sub(slashed);
+            asset.balance = asset.balance.saturating_sub(slashed);
+            asset.update_available();
+        });
+        <Tokens<T>>::mutate(token_symbol, |token| {
+            token.total_supply = token.total_supply.saturating_sub(slashed);
+        });
+
+        Self::reap_account_asset(token_symbol, who);
+
+        slashed
+    }
+
+    fn reserved_balance(id: LockIdentifier, token_symbol: TokenSymbol, who: &T::AccountId) -> T::Balance {
+        <Reserves<T>>::get((token_symbol, who))
+            .into_iter()
+            .find(|r| r.id == id)
+            .map(|r| r.amount)
+            .unwrap_or_else(Zero::zero)
+    }
+
+    fn asset_transfer_from(
+        token_symbol: TokenSymbol,
+        owner: &T::AccountId,
+        spender: &T::AccountId,
+        target: &T::AccountId,
+        amount: T::Balance,
+    ) -> Result<(), Self::Error> {
+        let key = (token_symbol, owner.clone());
+        let mut allowance =
+            <Allowances<T>>::get(&key, spender).ok_or(Error::<T>::AllowanceExceeded)?;
+        ensure!(!Self::allowance_expired(&allowance), Error::<T>::AllowanceExpired);
+        ensure!(allowance.amount >= amount, Error::<T>::AllowanceExceeded);
+        ensure!(
+            <AccountAssets<T>>::get(&key).available >= amount,
+            Error::<T>::InvalidBalanceForTransaction
+        );
+        Self::ensure_not_frozen(token_symbol, owner)?;
+        Self::ensure_not_frozen(token_symbol, target)?;
+        Self::ensure_above_min_balance(token_symbol, target, amount)?;
+
+        allowance.amount -= amount;
+        if allowance.amount.is_zero() {
+            <Allowances<T>>::remove(&key, spender);
+        } else {
+            <Allowances<T>>::insert(&key, spender, allowance);
+        }
+
+        Self::asset_transfer(token_symbol, owner.clone(), target.clone(), amount);
+
+        Ok(())
+    }
+
+    fn is_frozen(token_symbol: TokenSymbol, who: &T::AccountId) -> bool {
+        <FrozenTokens>::get(token_symbol) || <FrozenAccounts<T>>::get((token_symbol, who))
     }
 }
 
 impl<T: Trait> TokenPriceHandler<T::Price> for Module<T> {
     fn set_token_price(symbol: Vec<u8>, price: T::Price) {
-        match TOKEN_LIST.iter().position(|s| s.0 == symbol) {
-            Some(id) => {
-                let token_symbol = TokenSymbol::from(id as u32 + 1); // skip aUSD
-                <Prices<T>>::mutate(token_symbol, |p| *p = price);
-            }
-            _ => {}
+        if let Some(token_symbol) = <TokenSymbolIds>::get(symbol) {
+            <Prices<T>>::mutate(token_symbol, |p| *p = price);
         }
     }
 }
@@ -451,26 +1049,210 @@ impl<T: Trait> Module<T> {
         to: T::AccountId,
         amount: T::Balance,
     ) {
+        Self::move_balance(token_symbol, &from, &to, amount);
+
+        Self::reap_account_asset(token_symbol, &from);
+
+        T::TransferHandler::handle_transfer(token_symbol, &from, &to, amount);
+    }
+
+    /// Move some balance of `from` to `to`, without any check.
+    fn move_balance(
+        token_symbol: TokenSymbol,
+        from: &T::AccountId,
+        to: &T::AccountId,
+        amount: T::Balance,
+    ) {
+        // the sender realizes the value of the transferred assets, and the receiver pays for them
+        let value = Self::asset_value(token_symbol, amount);
+
         let from_asset = (token_symbol, from);
-        <AccountAssets<T>>::mutate(&from_asset, |asset| {
+        <AccountAssets<T>>::mutate(from_asset, |asset| {
             asset.balance = asset.balance.saturating_sub(amount);
+            asset.update_available();
+            asset.income = asset.income.saturating_add(value.into());
         });
 
-        let to_asset = (token_symbol, &to);
+        let to_asset = (token_symbol, to);
         <AccountAssets<T>>::mutate(to_asset, |asset| {
             asset.balance = asset.balance.saturating_add(amount);
+            asset.update_available();
+            asset.cost = asset.cost.saturating_add(value.into());
         });
 
         // save asset id for this account
-        if <AccountAssetIds<T>>::contains_key(&to) {
-            <AccountAssetIds<T>>::mutate(&to, |ids| {
+        if <AccountAssetIds<T>>::contains_key(to) {
+            <AccountAssetIds<T>>::mutate(to, |ids| {
                 // do not push a duplicated asset id to list
                 if !ids.contains(&token_symbol) {
                     ids.push(token_symbol);
                 }
             });
         } else {
-            <AccountAssetIds<T>>::insert(&to, vec![token_symbol]);
+            <AccountAssetIds<T>>::insert(to, vec![token_symbol]);
+        }
+        <TokenHolders<T>>::insert(token_symbol, to, ());
+    }
+
+    /// Value of some amount of a token at its current price, with the precision of prices.
+    /// Prices are quoted for one whole token, so the amount is scaled down by the precision of
+    /// the token first.
+    fn asset_value(token_symbol: TokenSymbol, amount: T::Balance) -> T::Balance {
+        let price: u128 = <Prices<T>>::get(token_symbol).saturated_into();
+        let unit = 10u128.saturating_pow(<Tokens<T>>::get(token_symbol).precision.into());
+        multiply_by_rational(amount.saturated_into(), price, unit)
+            .unwrap_or(u128::max_value())
+            .saturated_into()
+    }
+
+    /// Check and move some available assets of `from` to `to`.
+    fn do_transfer(
+        token_symbol: TokenSymbol,
+        from: &T::AccountId,
+        to: &T::AccountId,
+        amount: T::Balance,
+    ) -> Result<(), Error<T>> {
+        let from_balance = <AccountAssets<T>>::get((token_symbol, from)).available;
+
+        ensure!(!amount.is_zero(), Error::<T>::ZeroAmountOfBalance);
+        ensure!(from_balance >= amount, Error::<T>::InvalidBalanceForTransaction);
+        Self::ensure_not_frozen(token_symbol, from)?;
+        Self::ensure_not_frozen(token_symbol, to)?;
+        Self::ensure_above_min_balance(token_symbol, to, amount)?;
+
+        Self::asset_transfer(token_symbol, from.clone(), to.clone(), amount);
+
+        Ok(())
+    }
+
+    /// Store the locks of an account asset and freeze the biggest of them.
+    fn update_locks(key: &(TokenSymbol, T::AccountId), locks: Vec<NamedBalance<T::Balance>>) {
+        let frozen = locks
+            .iter()
+            .fold(Zero::zero(), |frozen: T::Balance, l| frozen.max(l.amount));
+        <AccountAssets<T>>::mutate(key, |asset| {
+            asset.frozen = frozen;
+            asset.update_available();
+        });
+
+        if locks.is_empty() {
+            <Locks<T>>::remove(key);
+        } else {
+            <Locks<T>>::insert(key, locks);
+        }
+    }
+
+    /// Take at most `amount` from the reserve `id`, returns the amount taken.
+    fn take_reserve(
+        key: &(TokenSymbol, T::AccountId),
+        id: LockIdentifier,
+        amount: T::Balance,
+    ) -> T::Balance {
+        let mut reserves = <Reserves<T>>::get(key);
+        let taken = match reserves.iter_mut().find(|r| r.id == id) {
+            Some(reserve) => {
+                let taken = reserve.amount.min(amount);
+                reserve.amount -= taken;
+                taken
+            }
+            None => Zero::zero(),
+        };
+        reserves.retain(|r| !r.amount.is_zero());
+
+        if reserves.is_empty() {
+            <Reserves<T>>::remove(key);
+        } else {
+            <Reserves<T>>::insert(key, reserves);
+        }
+
+        taken
+    }
+
+    /// Ensure the account asset of `who` is not below the minimum balance after receiving
+    /// `amount`.
+    fn ensure_above_min_balance(
+        token_symbol: TokenSymbol,
+        who: &T::AccountId,
+        amount: T::Balance,
+    ) -> Result<(), Error<T>> {
+        let balance = <AccountAssets<T>>::get((token_symbol, who)).balance;
+        let min_balance = <Tokens<T>>::get(token_symbol).min_balance;
+        ensure!(
+            balance.saturating_add(amount) >= min_balance,
+            Error::<T>::BelowMinimumBalance
+        );
+
+        Ok(())
+    }
+
+    /// Ensure the origin is root or holds the role of the token.
+    fn ensure_role<F>(origin: T::Origin, token_symbol: TokenSymbol, role: F) -> DispatchResult
+    where
+        F: Fn(&TokenRoles<T::AccountId>) -> &T::AccountId,
+    {
+        if ensure_root(origin.clone()).is_ok() {
+            return Ok(());
+        }
+
+        let who = ensure_signed(origin)?;
+        let roles = <Roles<T>>::get(token_symbol).ok_or(Error::<T>::NoPermission)?;
+        ensure!(*role(&roles) == who, Error::<T>::NoPermission);
+
+        Ok(())
+    }
+
+    /// Ensure neither the token nor the account asset of `who` is frozen.
+    fn ensure_not_frozen(token_symbol: TokenSymbol, who: &T::AccountId) -> Result<(), Error<T>> {
+        ensure!(!<FrozenTokens>::get(token_symbol), Error::<T>::TokenFrozen);
+        ensure!(!<FrozenAccounts<T>>::get((token_symbol, who)), Error::<T>::AccountFrozen);
+
+        Ok(())
+    }
+
+    /// Whether the allowance cannot be used anymore at the current block.
+    fn allowance_expired(allowance: &Allowance<T::Balance, T::BlockNumber>) -> bool {
+        allowance
+            .expiry
+            .map_or(false, |expiry| expiry < <system::Module<T>>::block_number())
+    }
+
+    /// Remove the account asset if nothing is reserved or frozen and its balance is zero or
+    /// below the minimum balance of the token. A non-zero remaining balance is handled as dust,
+    /// so only burnable tokens are reaped below the minimum balance.
+    fn reap_account_asset(token_symbol: TokenSymbol, who: &T::AccountId) {
+        let asset = <AccountAssets<T>>::get((token_symbol, who));
+        let min_balance = <Tokens<T>>::get(token_symbol).min_balance;
+        if !asset.reserved.is_zero()
+            || !asset.frozen.is_zero()
+            || (!asset.balance.is_zero()
+                && (asset.balance >= min_balance || !T::TransferHandler::is_burnable(token_symbol)))
+        {
+            return;
+        }
+
+        <AccountAssets<T>>::remove((token_symbol, who));
+        <TokenHolders<T>>::remove(token_symbol, who);
+        <AccountAssetIds<T>>::mutate_exists(who, |maybe_ids| {
+            let is_empty = match maybe_ids {
+                Some(ids) => {
+                    ids.retain(|id| *id != token_symbol);
+                    ids.is_empty()
+                }
+                None => false,
+            };
+            if is_empty {
+                *maybe_ids = None;
+            }
+        });
+
+        let dust = asset.balance;
+        if !dust.is_zero() {
+            <Tokens<T>>::mutate(token_symbol, |token| {
+                token.total_supply = token.total_supply.saturating_sub(dust);
+            });
+            T::DustHandler::handle_dust(token_symbol, who, dust);
+
+            Self::deposit_event(RawEvent::DustRemoved(token_symbol, who.clone(), dust));
         }
     }
 
@@ -489,4 +1271,108 @@ impl<T: Trait> Module<T> {
     pub fn asset_tokens(target: T::AccountId) -> Vec<TokenSymbol> {
         <AccountAssetIds<T>>::get(target)
     }
+
+    pub fn all_account_assets(
+        target: T::AccountId,
+    ) -> Vec<(TokenSymbol, AccountAsset<T::Balance, T::Cost, T::Income>)> {
+        <AccountAssetIds<T>>::get(&target)
+            .into_iter()
+            .map(|token_symbol| (token_symbol, <AccountAssets<T>>::get((token_symbol, &target))))
+            .collect()
+    }
+
+    pub fn token_info(token_symbol: TokenSymbol) -> Option<Token<T::Balance>> {
+        if <Tokens<T>>::contains_key(token_symbol) {
+            Some(<Tokens<T>>::get(token_symbol))
+        } else {
+            None
+        }
+    }
+
+    pub fn total_issuance(token_symbol: TokenSymbol) -> T::Balance {
+        <Tokens<T>>::get(token_symbol).total_supply
+    }
+
+    /// A page of the holders of a token, at most `count` of them following the holder `start`,
+    /// or from the first holder without it. Holders are in the order of their storage keys, the
+    /// last one of a page is the start of the next page.
+    pub fn token_holders(
+        token_symbol: TokenSymbol,
+        start: Option<T::AccountId>,
+        count: u32,
+    ) -> Vec<(T::AccountId, AccountAsset<T::Balance, T::Cost, T::Income>)> {
+        let count = count.min(MAX_HOLDERS_PAGE_SIZE) as usize;
+        let prefix = <TokenHolders<T>>::storage_double_map_final_key1(token_symbol);
+        let mut key = match start {
+            Some(who) => <TokenHolders<T>>::storage_double_map_final_key(token_symbol, who),
+            None => prefix.clone(),
+        };
+
+        let mut holders = Vec::with_capacity(count);
+        while holders.len() < count {
+            key = match sp_io::storage::next_key(&key) {
+                Some(next) if next.starts_with(&prefix) => next,
+                _ => break,
+            };
+            // the account follows the 16 bytes of its blake2_128 hash at the end of the key
+            let who = match key
+                .get(prefix.len() + 16..)
+                .and_then(|mut raw| T::AccountId::decode(&mut raw).ok())
+            {
+                Some(who) => who,
+                None => continue,
+            };
+            let asset = <AccountAssets<T>>::get((token_symbol, &who));
+            holders.push((who, asset));
+        }
+        holders
+    }
+
+    /// The accounting of an account asset, (cost, income, value of the balance at the current
+    /// price). The accounting starts over once the account asset is reaped.
+    pub fn asset_pnl(token_symbol: TokenSymbol, target: T::AccountId) -> (u128, u128, u128) {
+        let asset = <AccountAssets<T>>::get((token_symbol, target));
+        let value = Self::asset_value(token_symbol, asset.balance);
+
+        (
+            asset.cost.saturated_into(),
+            asset.income.saturated_into(),
+            value.saturated_into(),
+        )
+    }
+}
+
+mod weight_for {
+    use super::Trait;
+    use frame_support::{traits::Get, weights::Weight};
+    use node_primitives::TransferHandler;
+
+    /// Execution of a call besides its storage accesses, the origin and the events.
+    const CALL_BASE: Weight = 20_000_000;
+    /// Execution of a transfer besides its storage accesses, the checks and the value moved.
+    const TRANSFER_BASE: Weight = 10_000_000;
+
+    /// A transfer: the account assets of both holders, their frozen flags, the token and its
+    /// price, the asset ids of both holders, the reap of the sender and the transfer handler.
+    fn transfer_leg<T: Trait>() -> Weight {
+        T::DbWeight::get()
+            .reads_writes(9, 5)
+            .saturating_add(TRANSFER_BASE)
+            .saturating_add(T::TransferHandler::handle_transfer_weight())
+    }
+
+    /// transfer weight
+    pub(crate) fn transfer<T: Trait>() -> Weight {
+        CALL_BASE.saturating_add(transfer_leg::<T>())
+    }
+
+    /// transfer_batch weight, an empty batch costs the call only
+    pub(crate) fn transfer_batch<T: Trait>(count: usize) -> Weight {
+        CALL_BASE.saturating_add(transfer_leg::<T>().saturating_mul(count as Weight))
+    }
+
+    /// transfer_from weight, a transfer and the allowance
+    pub(crate) fn transfer_from<T: Trait>() -> Weight {
+        transfer::<T>().saturating_add(T::DbWeight::get().reads_writes(1, 1))
+    }
 }
@@ -0,0 +1,51 @@
+// Copyright 2020 Keysians Technologies.
+// This file is part of Keysians.
+
+// Keysians is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Keysians is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Keysians.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Storage migrations of the pallet, a module for each release whose layout changes.
+
+use crate::*;
+
+/// Migrate the storage from its version to the latest release step by step. Returns the weight
+/// consumed.
+pub(crate) fn migrate<T: Trait>() -> Weight {
+    let mut weight = T::DbWeight::get().reads(1);
+
+    if StorageVersion::get() == Releases::V1_0_0 {
+        weight = weight.saturating_add(v2::migrate::<T>());
+        StorageVersion::put(Releases::V2_0_0);
+        weight = weight.saturating_add(T::DbWeight::get().writes(1));
+    }
+
+    // the migration to a new release goes here, after the migrations to all the releases before it
+
+    weight
+}
+
+/// Account assets are indexed by their tokens in `TokenHolders`.
+pub(crate) mod v2 {
+    use super::*;
+
+    /// Index every account asset by its token. Returns the weight consumed.
+    pub(crate) fn migrate<T: Trait>() -> Weight {
+        let mut count: Weight = 0;
+        for ((token_symbol, who), _) in <AccountAssets<T>>::iter() {
+            <TokenHolders<T>>::insert(token_symbol, who, ());
+            count = count.saturating_add(1);
+        }
+
+        T::DbWeight::get().reads_writes(count, count)
+    }
+}
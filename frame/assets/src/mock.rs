@@ -66,6 +66,16 @@ impl system::Trait for Test {
 
 parameter_types! {
 	pub const SettlementPeriod: u64 = 24 * 60 * 10;
+	pub const PricePrecision: u64 = 1_000;
+	pub const MaxPriceUpdates: u32 = 2;
+}
+
+/// Every token is worth as many aUSD as its id.
+pub struct MockPriceProvider;
+impl PriceProvider<TokenSymbol, u64> for MockPriceProvider {
+	fn get_price(token_symbol: TokenSymbol, _quote: TokenSymbol, precision: u64) -> Option<u64> {
+		Some(u64::from(u32::from(token_symbol)) * precision)
+	}
 }
 
 impl Trait for Test {
@@ -78,6 +88,11 @@ impl Trait for Test {
 	type Convert = u128;
 	type AssetRedeem = ();
 	type FetchConvertPrice = ();
+	type DustHandler = ();
+	type PriceProvider = MockPriceProvider;
+	type PricePrecision = PricePrecision;
+	type MaxPriceUpdates = MaxPriceUpdates;
+	type TransferHandler = ();
 }
 
 mod assets {
@@ -20,36 +20,30 @@
 
 use super::*;
 use crate::mock::*;
-use frame_support::{assert_ok, assert_noop, dispatch::DispatchError};
+use frame_support::{
+	assert_noop, assert_ok, dispatch::DispatchError, traits::OnRuntimeUpgrade, weights::GetDispatchInfo,
+};
 use system::{EventRecord, Phase};
 
 #[test]
 fn create_asset_should_work() {
 	new_test_ext().execute_with(|| {
-		let token1 = Token {
-			symbol: vec![0x12, 0x34],
-			precision: 8,
-			total_supply: 0,
-		};
+		let token1 = Token::new(vec![0x12, 0x34], 8, 0);
 
 		let id1 = Assets::next_asset_id();
 		let token_type1 = TokenSymbol::from(id1);
 
 		System::set_block_number(1);
 
-		assert_ok!(Assets::create(Origin::root(), vec![0x12, 0x34], 8));
+		assert_ok!(Assets::create(Origin::root(), vec![0x12, 0x34], 8, None));
 		assert_eq!(Assets::next_asset_id(), id1 + 1);
 		assert_eq!(Assets::token_details(token_type1), token1);
 
-		let token2 = Token {
-			symbol: vec![0x56, 0x68, 0x90],
-			precision: 4,
-			total_supply: 0,
-		};
+		let token2 = Token::new(vec![0x56, 0x68, 0x90], 4, 0);
 
 		let id2 = Assets::next_asset_id();
 		let token_type2 = TokenSymbol::from(id2);
-		assert_ok!(Assets::create(Origin::root(), vec![0x56, 0x68, 0x90], 4)); // take 2 as asset id
+		assert_ok!(Assets::create(Origin::root(), vec![0x56, 0x68, 0x90], 4, None)); // take 2 as asset id
 		assert_eq!(Assets::next_asset_id(), id2 + 1);
 		assert_eq!(Assets::token_details(token_type2), token2);
 
@@ -75,15 +69,15 @@ fn issuing_asset_units_to_issuer_should_work() {
 
 		let ausd_id = Assets::next_asset_id();
 		let ausd_type = TokenSymbol::from(ausd_id);
-		assert_ok!(Assets::create(Origin::root(), b"aUSD".to_vec(), 18));
+		assert_ok!(Assets::create(Origin::root(), b"aUSD".to_vec(), 18, None));
 
 		let dot_id = Assets::next_asset_id();
 		let dot_type = TokenSymbol::from(dot_id);
-		assert_ok!(Assets::create(Origin::root(), b"DOT".to_vec(), 12));
+		assert_ok!(Assets::create(Origin::root(), b"DOT".to_vec(), 12, None));
 
 		let vdot_id = Assets::next_asset_id();
 		let vdot_type = TokenSymbol::from(vdot_id);
-		assert_ok!(Assets::create(Origin::root(), b"vDOT".to_vec(), 12));
+		assert_ok!(Assets::create(Origin::root(), b"vDOT".to_vec(), 12, None));
 
 		System::set_block_number(1);
 
@@ -104,7 +98,7 @@ fn issuing_asset_units_to_issuer_should_work() {
 
 		// creare a exsited token
 		assert_eq!(
-			Assets::create(Origin::root(), b"vDOT".to_vec(), 12),
+			Assets::create(Origin::root(), b"vDOT".to_vec(), 12, None),
 			Err(DispatchError::Module { index: 0, error: 0, message: Some("TokenExisted") })
 		);
 
@@ -134,7 +128,7 @@ fn transferring_amount_above_available_balance_should_work() {
 		let bob = 2;
 		let ausd_id = Assets::next_asset_id();
 		let ausd_type = TokenSymbol::from(ausd_id);
-		assert_ok!(Assets::create(Origin::root(), b"aUSD".to_vec(), 18));
+		assert_ok!(Assets::create(Origin::root(), b"aUSD".to_vec(), 18, None));
 
 		System::set_block_number(1);
 
@@ -157,7 +151,7 @@ fn transferring_amount_above_available_balance_should_work() {
 #[test]
 fn transferring_amount_less_than_available_balance_should_not_work() {
 	new_test_ext().execute_with(|| {
-		assert_ok!(Assets::create(Origin::root(), vec![0x12, 0x34], 8));
+		assert_ok!(Assets::create(Origin::root(), vec![0x12, 0x34], 8, None));
 		let (alice, bob) = (1, 2);
 		assert_noop!(
 			Assets::transfer(Origin::signed(alice), TokenSymbol::aUSD, bob, 1000),
@@ -169,7 +163,7 @@ fn transferring_amount_less_than_available_balance_should_not_work() {
 #[test]
 fn transferring_less_than_one_unit_should_not_work() {
 	new_test_ext().execute_with(|| {
-		assert_ok!(Assets::create(Origin::root(), vec![0x12, 0x34], 8));
+		assert_ok!(Assets::create(Origin::root(), vec![0x12, 0x34], 8, None));
 		let (alice, bob) = (1, 2);
 		assert_noop!(
 			Assets::transfer(Origin::signed(alice), TokenSymbol::aUSD, bob, 0),
@@ -186,7 +180,7 @@ fn destroying_asset_balance_with_positive_balance_should_work() {
 		let alice = 1;
 		let ausd_id = Assets::next_asset_id();
 		let ausd_type = TokenSymbol::from(ausd_id);
-		assert_ok!(Assets::create(Origin::root(), b"aUSD".to_vec(), 18));
+		assert_ok!(Assets::create(Origin::root(), b"aUSD".to_vec(), 18, None));
 
 		System::set_block_number(1);
 
@@ -198,7 +192,7 @@ fn destroying_asset_balance_with_positive_balance_should_work() {
 		assert_eq!(Assets::account_assets((ausd_type, alice)).balance, 9000);
 
 		assert_eq!(System::events(), vec![
-			EventRecord { phase: Phase::Initialization, event: TestEvent::assets(RawEvent::Created(0, Token { symbol: b"aUSD".to_vec(), precision: 18, total_supply: 0 })), topics: vec![] },
+			EventRecord { phase: Phase::Initialization, event: TestEvent::assets(RawEvent::Created(0, Token::new(b"aUSD".to_vec(), 18, 0))), topics: vec![] },
 			EventRecord { phase: Phase::Initialization, event: TestEvent::assets(RawEvent::Issued(ausd_type, 1, 10000)), topics: vec![] },
 			EventRecord { phase: Phase::Initialization, event: TestEvent::assets(RawEvent::Destroyed(ausd_type, 1, 1000)), topics: vec![] }
 		]);
@@ -208,7 +202,7 @@ fn destroying_asset_balance_with_positive_balance_should_work() {
 #[test]
 fn destroying_asset_balance_with_zero_balance_should_not_work() {
 	new_test_ext().execute_with(|| {
-		assert_ok!(Assets::create(Origin::root(), vec![0x12, 0x34], 8));
+		assert_ok!(Assets::create(Origin::root(), vec![0x12, 0x34], 8, None));
 		let alice = 1;
 		let id = Assets::next_asset_id();
 		let token_symbol = TokenSymbol::from(id - 1);
@@ -219,3 +213,539 @@ fn destroying_asset_balance_with_zero_balance_should_not_work() {
 		);
 	});
 }
+
+#[test]
+fn register_token_should_work() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+
+		assert_ok!(Assets::create(Origin::root(), b"IOST".to_vec(), 12, None));
+		let iost_type = TokenSymbol::from(Assets::next_asset_id() - 1);
+
+		assert_ok!(Assets::register_token(
+			Origin::root(),
+			b"vIOST".to_vec(),
+			12,
+			b"Keysians staked IOST".to_vec(),
+			b"https://keysians.com/tokens/viost.json".to_vec(),
+			Some(iost_type),
+		));
+		let viost_type = TokenSymbol::from(Assets::next_asset_id() - 1);
+
+		let token = Assets::token_details(viost_type);
+		assert_eq!(token.symbol, b"vIOST".to_vec());
+		assert_eq!(token.name, b"Keysians staked IOST".to_vec());
+		assert_eq!(token.metadata_uri, b"https://keysians.com/tokens/viost.json".to_vec());
+
+		// both sides of the pair can be looked up
+		assert_eq!(Assets::token_symbol_id(b"IOST".to_vec()), Some(iost_type));
+		assert_eq!(Assets::token_symbol_id(b"vIOST".to_vec()), Some(viost_type));
+		assert_eq!(Assets::get_token_pair(iost_type), Some((iost_type, viost_type)));
+		assert_eq!(Assets::get_token_pair(viost_type), Some((iost_type, viost_type)));
+
+		assert!(System::events().iter().any(|r| r.event == TestEvent::assets(RawEvent::TokenPaired(iost_type, viost_type))));
+	});
+}
+
+#[test]
+fn register_token_with_invalid_pair_should_not_work() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(
+			Assets::register_token(Origin::root(), b"vIOST".to_vec(), 12, vec![], vec![], Some(TokenSymbol::IOST)),
+			AssetsError::TokenNotExist
+		);
+
+		assert_ok!(Assets::create(Origin::root(), b"IOST".to_vec(), 12, None));
+		let iost_type = TokenSymbol::from(Assets::next_asset_id() - 1);
+		assert_ok!(Assets::register_token(Origin::root(), b"vIOST".to_vec(), 12, vec![], vec![], Some(iost_type)));
+
+		// a token can only be paired once
+		assert_noop!(
+			Assets::register_token(Origin::root(), b"xIOST".to_vec(), 12, vec![], vec![], Some(iost_type)),
+			AssetsError::TokenPairExisted
+		);
+		assert_noop!(
+			Assets::register_token(Origin::root(), b"vIOST".to_vec(), 12, vec![], vec![], None),
+			AssetsError::TokenExisted
+		);
+		assert_noop!(
+			Assets::register_token(Origin::root(), b"yIOST".to_vec(), 12, vec![0u8; 65], vec![], None),
+			AssetsError::TokenNameTooLong
+		);
+	});
+}
+
+#[test]
+fn destroying_all_balance_should_reap_account_asset() {
+	new_test_ext().execute_with(|| {
+		let alice = 1;
+		assert_ok!(Assets::create(Origin::root(), b"aUSD".to_vec(), 18, None));
+		let ausd_type = TokenSymbol::from(Assets::next_asset_id() - 1);
+
+		assert_ok!(Assets::issue(Origin::root(), ausd_type, alice, 100));
+		assert_eq!(Assets::account_asset_ids(alice), vec![ausd_type]);
+
+		assert_ok!(Assets::destroy(Origin::signed(alice), ausd_type, 100));
+		assert!(!<AccountAssets<Test>>::contains_key((ausd_type, alice)));
+		assert!(!<AccountAssetIds<Test>>::contains_key(alice));
+	});
+}
+
+#[test]
+fn transferring_with_min_balance_should_remove_dust() {
+	new_test_ext().execute_with(|| {
+		let (alice, bob, charlie) = (1, 2, 3);
+		assert_ok!(Assets::create(Origin::root(), b"aUSD".to_vec(), 18, None));
+		let ausd_type = TokenSymbol::from(Assets::next_asset_id() - 1);
+
+		assert_ok!(Assets::set_min_balance(Origin::root(), ausd_type, 10));
+		assert_eq!(Assets::token_details(ausd_type).min_balance, 10);
+
+		// cannot create an account asset below minimum balance
+		assert_noop!(Assets::issue(Origin::root(), ausd_type, alice, 9), AssetsError::BelowMinimumBalance);
+		assert_ok!(Assets::issue(Origin::root(), ausd_type, alice, 100));
+		assert_noop!(Assets::transfer(Origin::signed(alice), ausd_type, bob, 9), AssetsError::BelowMinimumBalance);
+
+		System::set_block_number(1);
+
+		// the 5 left to alice is dust
+		assert_ok!(Assets::transfer(Origin::signed(alice), ausd_type, bob, 95));
+		assert!(!<AccountAssets<Test>>::contains_key((ausd_type, alice)));
+		assert_eq!(Assets::account_assets((ausd_type, bob)).balance, 95);
+		assert_eq!(Assets::token_details(ausd_type).total_supply, 95);
+
+		// an existing account asset can receive less than minimum balance
+		assert_ok!(Assets::issue(Origin::root(), ausd_type, charlie, 10));
+		assert_ok!(Assets::transfer(Origin::signed(bob), ausd_type, charlie, 1));
+		assert_eq!(Assets::account_assets((ausd_type, charlie)).balance, 11);
+
+		assert_eq!(System::events(), vec![
+			EventRecord { phase: Phase::Initialization, event: TestEvent::assets(RawEvent::DustRemoved(ausd_type, alice, 5)), topics: vec![] },
+			EventRecord { phase: Phase::Initialization, event: TestEvent::assets(RawEvent::Transferred(ausd_type, alice, bob, 95)), topics: vec![] },
+			EventRecord { phase: Phase::Initialization, event: TestEvent::assets(RawEvent::Issued(ausd_type, charlie, 10)), topics: vec![] },
+			EventRecord { phase: Phase::Initialization, event: TestEvent::assets(RawEvent::Transferred(ausd_type, bob, charlie, 1)), topics: vec![] },
+		]);
+	});
+}
+
+#[test]
+fn named_locks_should_freeze_the_biggest_one() {
+	new_test_ext().execute_with(|| {
+		let (alice, bob) = (1, 2);
+		assert_ok!(Assets::create(Origin::root(), b"aUSD".to_vec(), 18, None));
+		let ausd_type = TokenSymbol::from(Assets::next_asset_id() - 1);
+		assert_ok!(Assets::issue(Origin::root(), ausd_type, alice, 100));
+
+		Assets::set_lock(*b"lock_one", ausd_type, &alice, 30);
+		Assets::set_lock(*b"lock_two", ausd_type, &alice, 60);
+		assert_eq!(Assets::account_assets((ausd_type, alice)).frozen, 60);
+		assert_eq!(Assets::account_assets((ausd_type, alice)).available, 40);
+		assert_noop!(
+			Assets::transfer(Origin::signed(alice), ausd_type, bob, 41),
+			AssetsError::InvalidBalanceForTransaction
+		);
+
+		// removing a lock does not release the other one
+		Assets::remove_lock(*b"lock_two", ausd_type, &alice);
+		assert_eq!(Assets::account_assets((ausd_type, alice)).frozen, 30);
+		assert_eq!(Assets::locks((ausd_type, alice)), vec![NamedBalance { id: *b"lock_one", amount: 30 }]);
+		assert_ok!(Assets::transfer(Origin::signed(alice), ausd_type, bob, 70));
+	});
+}
+
+#[test]
+fn named_reserves_should_be_separated() {
+	new_test_ext().execute_with(|| {
+		let alice = 1;
+		assert_ok!(Assets::create(Origin::root(), b"aUSD".to_vec(), 18, None));
+		let ausd_type = TokenSymbol::from(Assets::next_asset_id() - 1);
+		assert_ok!(Assets::issue(Origin::root(), ausd_type, alice, 100));
+
+		assert_ok!(Assets::reserve(*b"module_a", ausd_type, &alice, 40));
+		assert_ok!(Assets::reserve(*b"module_b", ausd_type, &alice, 50));
+		assert!(Assets::reserve(*b"module_a", ausd_type, &alice, 11).is_err());
+
+		let asset = Assets::account_assets((ausd_type, alice));
+		assert_eq!((asset.balance, asset.reserved, asset.free(), asset.available), (100, 90, 10, 10));
+
+		// one module can never release the reserve of another
+		assert_eq!(Assets::unreserve(*b"module_a", ausd_type, &alice, 50), 40);
+		assert_eq!(Assets::reserved_balance(*b"module_a", ausd_type, &alice), 0);
+		assert_eq!(Assets::reserved_balance(*b"module_b", ausd_type, &alice), 50);
+
+		assert_eq!(Assets::slash_reserved(*b"module_b", ausd_type, &alice, 20), 20);
+		let asset = Assets::account_assets((ausd_type, alice));
+		assert_eq!((asset.balance, asset.reserved, asset.available), (80, 30, 50));
+		assert_eq!(Assets::token_details(ausd_type).total_supply, 80);
+	});
+}
+
+#[test]
+fn transfer_from_should_work_within_allowance() {
+	new_test_ext().execute_with(|| {
+		let (alice, bob, charlie) = (1, 2, 3);
+		assert_ok!(Assets::create(Origin::root(), b"aUSD".to_vec(), 18, None));
+		let ausd_type = TokenSymbol::from(Assets::next_asset_id() - 1);
+		assert_ok!(Assets::issue(Origin::root(), ausd_type, alice, 100));
+
+		assert_noop!(
+			Assets::transfer_from(Origin::signed(bob), alice, charlie, ausd_type, 10),
+			AssetsError::AllowanceExceeded
+		);
+
+		assert_ok!(Assets::approve(Origin::signed(alice), bob, ausd_type, 50, None));
+		assert_ok!(Assets::increase_allowance(Origin::signed(alice), bob, ausd_type, 20));
+		assert_ok!(Assets::decrease_allowance(Origin::signed(alice), bob, ausd_type, 10));
+		assert_eq!(Assets::allowances((ausd_type, alice), bob), Some(Allowance { amount: 60, expiry: None }));
+		assert_noop!(
+			Assets::decrease_allowance(Origin::signed(alice), bob, ausd_type, 61),
+			AssetsError::AllowanceExceeded
+		);
+
+		assert_ok!(Assets::transfer_from(Origin::signed(bob), alice, charlie, ausd_type, 40));
+		assert_eq!(Assets::account_assets((ausd_type, alice)).balance, 60);
+		assert_eq!(Assets::account_assets((ausd_type, charlie)).balance, 40);
+		assert_eq!(Assets::allowances((ausd_type, alice), bob), Some(Allowance { amount: 20, expiry: None }));
+		assert_noop!(
+			Assets::transfer_from(Origin::signed(bob), alice, charlie, ausd_type, 21),
+			AssetsError::AllowanceExceeded
+		);
+
+		// the allowance is removed once it is used up
+		assert_ok!(Assets::transfer_from(Origin::signed(bob), alice, bob, ausd_type, 20));
+		assert_eq!(Assets::allowances((ausd_type, alice), bob), None);
+	});
+}
+
+#[test]
+fn expired_allowance_should_not_work() {
+	new_test_ext().execute_with(|| {
+		let (alice, bob) = (1, 2);
+		assert_ok!(Assets::create(Origin::root(), b"aUSD".to_vec(), 18, None));
+		let ausd_type = TokenSymbol::from(Assets::next_asset_id() - 1);
+		assert_ok!(Assets::issue(Origin::root(), ausd_type, alice, 100));
+
+		System::set_block_number(5);
+		assert_noop!(
+			Assets::approve(Origin::signed(alice), bob, ausd_type, 50, Some(4)),
+			AssetsError::AllowanceExpired
+		);
+		assert_ok!(Assets::approve(Origin::signed(alice), bob, ausd_type, 50, Some(10)));
+
+		System::set_block_number(10);
+		assert_ok!(Assets::transfer_from(Origin::signed(bob), alice, bob, ausd_type, 10));
+
+		System::set_block_number(11);
+		assert_noop!(
+			Assets::transfer_from(Origin::signed(bob), alice, bob, ausd_type, 10),
+			AssetsError::AllowanceExpired
+		);
+		assert_noop!(
+			Assets::increase_allowance(Origin::signed(alice), bob, ausd_type, 10),
+			AssetsError::AllowanceExpired
+		);
+	});
+}
+
+#[test]
+fn transfer_batch_should_work() {
+	new_test_ext().execute_with(|| {
+		let (alice, bob, charlie) = (1, 2, 3);
+		assert_ok!(Assets::create(Origin::root(), b"aUSD".to_vec(), 18, None));
+		let ausd_type = TokenSymbol::from(Assets::next_asset_id() - 1);
+		assert_ok!(Assets::create(Origin::root(), b"DOT".to_vec(), 12, None));
+		let dot_type = TokenSymbol::from(Assets::next_asset_id() - 1);
+		assert_ok!(Assets::issue(Origin::root(), ausd_type, alice, 100));
+		assert_ok!(Assets::issue(Origin::root(), dot_type, alice, 50));
+
+		System::set_block_number(1);
+
+		assert_ok!(Assets::transfer_batch(
+			Origin::signed(alice),
+			vec![(ausd_type, bob, 30), (ausd_type, charlie, 20), (dot_type, charlie, 50)]
+		));
+		assert_eq!(Assets::account_assets((ausd_type, alice)).balance, 50);
+		assert_eq!(Assets::account_assets((ausd_type, bob)).balance, 30);
+		assert_eq!(Assets::account_assets((ausd_type, charlie)).balance, 20);
+		assert_eq!(Assets::account_assets((dot_type, charlie)).balance, 50);
+		assert!(!<AccountAssets<Test>>::contains_key((dot_type, alice)));
+
+		let events = System::events();
+		assert_eq!(events.len(), 4);
+		assert_eq!(
+			events.last().unwrap().event,
+			TestEvent::assets(RawEvent::BatchTransferred(alice, 3))
+		);
+	});
+}
+
+#[test]
+fn transfer_weight_should_count_every_transfer() {
+	new_test_ext().execute_with(|| {
+		let (bob, dot_type) = (2, TokenSymbol::DOT);
+		let transfer = Call::<Test>::transfer(dot_type, bob, 10).get_dispatch_info().weight;
+		let batch = |count| Call::<Test>::transfer_batch(vec![(dot_type, bob, 10); count]).get_dispatch_info().weight;
+
+		// an empty batch still pays for the call
+		assert!(batch(0) > 0);
+		assert_eq!(batch(1), transfer);
+		assert!(batch(2) > transfer);
+		assert_eq!(batch(3) - batch(2), batch(1) - batch(0));
+	});
+}
+
+#[test]
+fn transfer_batch_should_be_all_or_nothing() {
+	new_test_ext().execute_with(|| {
+		let (alice, bob, charlie) = (1, 2, 3);
+		assert_ok!(Assets::create(Origin::root(), b"aUSD".to_vec(), 18, None));
+		let ausd_type = TokenSymbol::from(Assets::next_asset_id() - 1);
+		assert_ok!(Assets::issue(Origin::root(), ausd_type, alice, 100));
+
+		// the second transfer exceeds the remaining balance, so the first one is reverted too
+		assert_noop!(
+			Assets::transfer_batch(Origin::signed(alice), vec![(ausd_type, bob, 60), (ausd_type, charlie, 50)]),
+			AssetsError::InvalidBalanceForTransaction
+		);
+		assert_eq!(Assets::account_assets((ausd_type, alice)).balance, 100);
+		assert_eq!(Assets::account_assets((ausd_type, bob)).balance, 0);
+	});
+}
+
+#[test]
+fn token_roles_should_work() {
+	new_test_ext().execute_with(|| {
+		let (alice, bob, charlie) = (1, 2, 3);
+		assert_ok!(Assets::create(Origin::root(), b"aUSD".to_vec(), 18, Some(alice)));
+		let ausd_type = TokenSymbol::from(Assets::next_asset_id() - 1);
+		assert_eq!(
+			Assets::token_roles(ausd_type),
+			Some(TokenRoles { owner: alice, issuer: alice, freezer: alice, admin: alice })
+		);
+
+		assert_ok!(Assets::issue(Origin::signed(alice), ausd_type, alice, 100));
+		assert_noop!(Assets::issue(Origin::signed(bob), ausd_type, bob, 100), AssetsError::NoPermission);
+
+		assert_ok!(Assets::set_team(Origin::signed(alice), ausd_type, bob, charlie, charlie));
+		assert_noop!(Assets::issue(Origin::signed(alice), ausd_type, alice, 100), AssetsError::NoPermission);
+		assert_ok!(Assets::issue(Origin::signed(bob), ausd_type, bob, 100));
+		assert_noop!(
+			Assets::set_team(Origin::signed(bob), ausd_type, bob, bob, bob),
+			AssetsError::NoPermission
+		);
+
+		assert_ok!(Assets::transfer_ownership(Origin::signed(alice), ausd_type, bob));
+		assert_noop!(
+			Assets::transfer_ownership(Origin::signed(alice), ausd_type, alice),
+			AssetsError::NoPermission
+		);
+		assert_eq!(Assets::token_roles(ausd_type).unwrap().owner, bob);
+
+		// tokens without roles are managed by root only
+		assert_ok!(Assets::create(Origin::root(), b"DOT".to_vec(), 12, None));
+		let dot_type = TokenSymbol::from(Assets::next_asset_id() - 1);
+		assert_noop!(Assets::issue(Origin::signed(alice), dot_type, alice, 100), AssetsError::NoPermission);
+		assert_ok!(Assets::issue(Origin::root(), dot_type, alice, 100));
+	});
+}
+
+#[test]
+fn frozen_account_asset_should_not_be_moved() {
+	new_test_ext().execute_with(|| {
+		let (alice, bob, charlie) = (1, 2, 3);
+		assert_ok!(Assets::create(Origin::root(), b"aUSD".to_vec(), 18, Some(alice)));
+		let ausd_type = TokenSymbol::from(Assets::next_asset_id() - 1);
+		assert_ok!(Assets::issue(Origin::signed(alice), ausd_type, bob, 100));
+		assert_ok!(Assets::set_team(Origin::signed(alice), ausd_type, alice, charlie, alice));
+
+		assert_noop!(Assets::freeze(Origin::signed(alice), ausd_type, bob), AssetsError::NoPermission);
+		assert_ok!(Assets::freeze(Origin::signed(charlie), ausd_type, bob));
+		assert!(Assets::is_frozen(ausd_type, &bob));
+		assert_noop!(
+			Assets::transfer(Origin::signed(bob), ausd_type, alice, 10),
+			AssetsError::AccountFrozen
+		);
+		assert_noop!(
+			Assets::transfer_batch(Origin::signed(bob), vec![(ausd_type, alice, 10)]),
+			AssetsError::AccountFrozen
+		);
+		assert_noop!(Assets::destroy(Origin::signed(bob), ausd_type, 10), AssetsError::AccountFrozen);
+		assert!(Assets::reserve(*b"module_a", ausd_type, &bob, 10).is_err());
+
+		assert_ok!(Assets::approve(Origin::signed(bob), charlie, ausd_type, 10, None));
+		assert_noop!(
+			Assets::transfer_from(Origin::signed(charlie), bob, charlie, ausd_type, 10),
+			AssetsError::AccountFrozen
+		);
+
+		assert_noop!(Assets::thaw(Origin::signed(charlie), ausd_type, bob), AssetsError::NoPermission);
+		assert_ok!(Assets::thaw(Origin::signed(alice), ausd_type, bob));
+		assert_ok!(Assets::transfer(Origin::signed(bob), ausd_type, alice, 10));
+	});
+}
+
+#[test]
+fn frozen_token_should_not_be_moved() {
+	new_test_ext().execute_with(|| {
+		let (alice, bob) = (1, 2);
+		assert_ok!(Assets::create(Origin::root(), b"aUSD".to_vec(), 18, None));
+		let ausd_type = TokenSymbol::from(Assets::next_asset_id() - 1);
+		assert_ok!(Assets::issue(Origin::root(), ausd_type, alice, 100));
+
+		assert_noop!(Assets::freeze_token(Origin::signed(alice), ausd_type), AssetsError::NoPermission);
+		assert_ok!(Assets::freeze_token(Origin::root(), ausd_type));
+		assert!(Assets::is_frozen(ausd_type, &bob));
+		assert_noop!(
+			Assets::transfer(Origin::signed(alice), ausd_type, bob, 10),
+			AssetsError::TokenFrozen
+		);
+
+		assert_ok!(Assets::thaw_token(Origin::root(), ausd_type));
+		assert_ok!(Assets::transfer(Origin::signed(alice), ausd_type, bob, 10));
+	});
+}
+
+#[test]
+fn cost_and_income_should_be_accounted_at_current_price() {
+	new_test_ext().execute_with(|| {
+		let (alice, bob) = (1, 2);
+		let dot = 1_000_000_000_000;
+		assert_ok!(Assets::create(Origin::root(), b"DOT".to_vec(), 12, None));
+		let dot_type = TokenSymbol::from(Assets::next_asset_id() - 1);
+
+		Assets::set_token_price(b"DOT".to_vec(), 5);
+		assert_ok!(Assets::issue(Origin::root(), dot_type, alice, 100 * dot));
+		assert_eq!(Assets::account_assets((dot_type, alice)).cost, 500);
+
+		Assets::set_token_price(b"DOT".to_vec(), 8);
+		assert_ok!(Assets::transfer(Origin::signed(alice), dot_type, bob, 40 * dot));
+		assert_ok!(Assets::destroy(Origin::signed(alice), dot_type, 10 * dot));
+		let asset = Assets::account_assets((dot_type, alice));
+		assert_eq!((asset.cost, asset.income), (500, 400));
+		let asset = Assets::account_assets((dot_type, bob));
+		assert_eq!((asset.cost, asset.income), (320, 0));
+
+		// 400 realized and 50 * 8 unrealized, against a cost of 500
+		assert_eq!(Assets::asset_pnl(dot_type, alice), (500, 400, 400));
+	});
+}
+
+#[test]
+fn asset_value_should_be_scaled_by_precision() {
+	new_test_ext().execute_with(|| {
+		let alice = 1;
+		let eth = 1_000_000_000_000_000_000;
+		assert_ok!(Assets::create(Origin::root(), b"ETH".to_vec(), 18, None));
+		let eth_type = TokenSymbol::from(Assets::next_asset_id() - 1);
+
+		// a billion tokens at a price which overflows u128 if the amount is not scaled down
+		let price = 3_000_000_000_000;
+		Assets::set_token_price(b"ETH".to_vec(), price);
+		assert_ok!(Assets::issue(Origin::root(), eth_type, alice, 1_000_000_000 * eth));
+		let value = u128::from(price) * 1_000_000_000;
+		assert_eq!(Assets::account_assets((eth_type, alice)).cost, value);
+
+		// less than one whole token is still valued
+		assert_ok!(Assets::destroy(Origin::signed(alice), eth_type, eth / 2));
+		assert_eq!(Assets::account_assets((eth_type, alice)).income, u128::from(price) / 2);
+		assert_eq!(
+			Assets::asset_pnl(eth_type, alice),
+			(value, u128::from(price) / 2, value - u128::from(price) / 2),
+		);
+	});
+}
+
+#[test]
+fn token_holders_should_be_paged() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Assets::create(Origin::root(), b"aUSD".to_vec(), 18, None));
+		let ausd_type = TokenSymbol::from(Assets::next_asset_id() - 1);
+		assert_ok!(Assets::create(Origin::root(), b"DOT".to_vec(), 12, None));
+		let dot_type = TokenSymbol::from(Assets::next_asset_id() - 1);
+		for who in 1..=5 {
+			assert_ok!(Assets::issue(Origin::root(), ausd_type, who, 100 * who));
+		}
+		assert_ok!(Assets::issue(Origin::root(), dot_type, 1, 10));
+
+		// each page starts after the last holder of the page before it
+		let first = Assets::token_holders(ausd_type, None, 3);
+		let second = Assets::token_holders(ausd_type, first.last().map(|(who, _)| *who), 3);
+		assert_eq!((first.len(), second.len()), (3, 2));
+		let mut holders: Vec<(u64, u128)> = first
+			.iter()
+			.chain(second.iter())
+			.map(|(who, asset)| (*who, asset.balance))
+			.collect();
+		holders.sort();
+		assert_eq!(holders, vec![(1, 100), (2, 200), (3, 300), (4, 400), (5, 500)]);
+		assert!(Assets::token_holders(ausd_type, second.last().map(|(who, _)| *who), 3).is_empty());
+		assert_eq!(Assets::token_holders(dot_type, None, 3).len(), 1);
+
+		// a reaped account asset is no longer a holder, a new one is
+		assert_ok!(Assets::transfer(Origin::signed(5), ausd_type, 6, 500));
+		let mut holders: Vec<u64> = Assets::token_holders(ausd_type, None, 10)
+			.into_iter()
+			.map(|(who, _)| who)
+			.collect();
+		holders.sort();
+		assert_eq!(holders, vec![1, 2, 3, 4, 6]);
+
+		assert_eq!(Assets::total_issuance(ausd_type), 1500);
+		let assets: Vec<(TokenSymbol, u128)> = Assets::all_account_assets(1)
+			.into_iter()
+			.map(|(token_symbol, asset)| (token_symbol, asset.balance))
+			.collect();
+		assert_eq!(assets, vec![(ausd_type, 100), (dot_type, 10)]);
+		assert_eq!(Assets::token_info(TokenSymbol::from(100)), None);
+	});
+}
+
+#[test]
+fn token_holders_should_be_indexed_on_upgrade() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Assets::create(Origin::root(), b"aUSD".to_vec(), 18, None));
+		let ausd_type = TokenSymbol::from(Assets::next_asset_id() - 1);
+		for who in 1..=3 {
+			assert_ok!(Assets::issue(Origin::root(), ausd_type, who, 100));
+		}
+
+		// the holders of the release 1.0.0 are not indexed
+		<TokenHolders<Test>>::remove_prefix(ausd_type);
+		StorageVersion::put(Releases::V1_0_0);
+		assert!(Assets::token_holders(ausd_type, None, 10).is_empty());
+
+		Assets::on_runtime_upgrade();
+		assert_eq!(StorageVersion::get(), Releases::V2_0_0);
+		let mut holders: Vec<u64> = Assets::token_holders(ausd_type, None, 10)
+			.into_iter()
+			.map(|(who, _)| who)
+			.collect();
+		holders.sort();
+		assert_eq!(holders, vec![1, 2, 3]);
+	});
+}
+
+#[test]
+fn prices_should_follow_the_market_in_turn() {
+	new_test_ext().execute_with(|| {
+		for symbol in vec![b"aUSD".to_vec(), b"DOT".to_vec(), b"vDOT".to_vec(), b"KSM".to_vec()] {
+			assert_ok!(Assets::create(Origin::root(), symbol, 12, None));
+		}
+
+		// two tokens a block, aUSD is the quote itself
+		run_to_block(1);
+		assert_eq!(Assets::prices(TokenSymbol::aUSD), 0);
+		assert_eq!(Assets::prices(TokenSymbol::DOT), 1_000);
+		assert_eq!(Assets::prices(TokenSymbol::vDOT), 0);
+
+		run_to_block(2);
+		assert_eq!(Assets::prices(TokenSymbol::vDOT), 2_000);
+		assert_eq!(Assets::prices(TokenSymbol::KSM), 3_000);
+
+		// the cursor starts over from the first token
+		Assets::set_token_price(b"DOT".to_vec(), 7);
+		run_to_block(3);
+		assert_eq!(Assets::prices(TokenSymbol::DOT), 1_000);
+		assert_eq!(Assets::price_cursor(), 2);
+	});
+}
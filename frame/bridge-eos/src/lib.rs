@@ -42,7 +42,7 @@ use frame_system::{
 };
 use node_primitives::{
     AssetTrait, BlockchainType, BridgeAssetBalance, BridgeAssetFrom, BridgeAssetSymbol,
-    BridgeAssetTo, TokenSymbol,
+    BridgeAssetTo, LockIdentifier, TokenSymbol,
 };
 use sp_application_crypto::RuntimeAppPublic;
 use sp_core::offchain::StorageKind;
@@ -59,6 +59,9 @@ mod mock;
 mod tests;
 mod transaction;
 
+/// Identifier of the reserve holding assets on the way to EOS.
+const BRIDGE_EOS_ID: LockIdentifier = *b"bridgeos";
+
 lazy_static::lazy_static! {
     pub static ref ACTION_NAMES: [ActionName; 1] = {
         let name = ActionName::from_str("transfer").unwrap();
@@ -584,7 +587,7 @@ decl_module! {
             let symbol_code = token.symbol;
             let symbol_precise = token.precision;
 
-            let balance = T::AssetTrait::get_account_asset(token_symbol, &origin).balance;
+            let balance = T::AssetTrait::get_account_asset(token_symbol, &origin).available;
             ensure!(symbol_precise <= 12, "symbol precise cannot bigger than 12.");
             let amount = amount.div(T::Balance::from(10u32.pow(12u32 - symbol_precise as u32)));
             ensure!(balance >= amount, "amount should be less than or equal to origin balance");
@@ -598,14 +601,16 @@ decl_module! {
                 token_symbol
             };
 
+            // reserve balance until trade is verified
+            T::AssetTrait::reserve(BRIDGE_EOS_ID, token_symbol, &origin, amount)
+                .map_err(|_| Error::<T>::InsufficientBalance)?;
+
             if Self::bridge_asset_to(to, bridge_asset).is_ok() {
                 debug::info!("sent transaction to EOS node.");
-                // locked balance until trade is verified
-                T::AssetTrait::lock_asset(&origin, token_symbol, amount);
-
                 Self::deposit_event(RawEvent::SendTransactionSuccess);
             } else {
                 debug::warn!("failed to send transaction to EOS node.");
+                T::AssetTrait::unreserve(BRIDGE_EOS_ID, token_symbol, &origin, amount);
                 Self::deposit_event(RawEvent::SendTransactionFailure);
             }
         }
@@ -785,8 +790,7 @@ impl<T: Trait> Module<T> {
         let symbol_code = symbol.code().to_string().into_bytes();
         let symbol_precision = symbol.precision() as u16;
         // ensure symbol and precision matched
-        let (_token_symbol, _) = token_symbol.paired_token();
-        let existed_token_symbol = T::AssetTrait::get_token(_token_symbol);
+        let existed_token_symbol = T::AssetTrait::get_token(token_symbol);
         ensure!(
             existed_token_symbol.symbol == symbol_code
                 && existed_token_symbol.precision == symbol_precision,
@@ -820,18 +824,18 @@ impl<T: Trait> Module<T> {
                     let token_symbol = multi_sig_tx.token_symbol;
 
                     let all_vtoken_balances =
-                        T::AssetTrait::get_account_asset(token_symbol, &target).balance;
+                        T::AssetTrait::reserved_balance(BRIDGE_EOS_ID, token_symbol, &target);
                     let token_balances = action_transfer.quantity.amount as usize;
                     let vtoken_balances = T::Balance::try_from(token_balances)
                         .map_err(|_| Error::<T>::ConvertBalanceError)?;
 
                     if all_vtoken_balances.lt(&vtoken_balances) {
-                        debug::warn!("origin account balance must be greater than or equal to the transfer amount.");
+                        debug::warn!("reserved balance must be greater than or equal to the transfer amount.");
                         return Err(Error::<T>::InsufficientBalance);
                     }
 
-                    // the trade is verified, unlock asset
-                    T::AssetTrait::unlock_asset(&target, token_symbol, vtoken_balances);
+                    // the trade is verified, the reserved asset has left to EOS
+                    T::AssetTrait::slash_reserved(BRIDGE_EOS_ID, token_symbol, &target, vtoken_balances);
 
                     return Ok(target.clone());
                 }
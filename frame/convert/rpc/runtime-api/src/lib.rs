@@ -26,5 +26,8 @@ decl_runtime_apis! {
 	{
 		/// get current convert rate
 		fn get_convert_rate(token_symbol: TokenSymbol) -> ConvertPrice;
+
+		/// get market rate of the vtoken in its token
+		fn get_market_rate(token_symbol: TokenSymbol) -> Option<ConvertPrice>;
 	}
 }
@@ -45,6 +45,10 @@ pub trait ConvertPriceApi<BlockHash, TokenSymbol, ConvertPrice> {
 	/// rpc method for getting current convert rate
 	#[rpc(name = "convert_getConvert")]
 	fn get_convert_rate(&self, token_symbol: TokenSymbol, at: Option<BlockHash>) -> JsonRpcResult<ConvertPrice>;
+
+	/// rpc method for getting market rate of the vtoken in its token, from the swap pools
+	#[rpc(name = "convert_getMarketRate")]
+	fn get_market_rate(&self, token_symbol: TokenSymbol, at: Option<BlockHash>) -> JsonRpcResult<Option<ConvertPrice>>;
 }
 
 impl<C, Block, TokenSymbol, ConvertPrice> ConvertPriceApi<<Block as BlockT>::Hash, TokenSymbol, ConvertPrice>
@@ -66,4 +70,15 @@ for Convert<C, Block>
 			data: Some(format!("{:?}", e).into()),
 		})
 	}
+
+	fn get_market_rate(&self, token_symbol: TokenSymbol, at: Option<<Block as BlockT>::Hash>) -> JsonRpcResult<Option<ConvertPrice>> {
+		let convert_rpc_api = self.client.runtime_api();
+		let at = BlockId::<Block>::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+
+		convert_rpc_api.get_market_rate(&at, token_symbol).map_err(|e| RpcError {
+			code: ErrorCode::InternalError,
+			message: "Failed to get market rate.".to_owned(),
+			data: Some(format!("{:?}", e).into()),
+		})
+	}
 }
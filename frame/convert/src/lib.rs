@@ -23,19 +23,22 @@ use alloc::vec::Vec;
 mod mock;
 mod tests;
 
+use codec::{Decode, Encode};
 use frame_support::traits::Get;
 use frame_support::weights::DispatchClass;
 use frame_support::{
     debug, decl_error, decl_event, decl_module, decl_storage, ensure, IterableStorageMap,
     Parameter, StorageValue,
 };
-use frame_system::{ensure_root, ensure_signed};
+use frame_system::{self as system, ensure_root, ensure_signed};
 use node_primitives::{
-    AssetReward, AssetTrait, ConvertPool, FetchConvertPrice, RewardHandler, TokenSymbol,
+    AssetReward, AssetTrait, ConvertPool, FetchConvertPrice, PriceProvider, RewardHandler,
+    SwapHandler, TokenSymbol,
 };
 use sp_runtime::traits::{AtLeast32Bit, MaybeSerializeDeserialize, Member, Saturating, Zero};
+use sp_runtime::{DispatchError, RuntimeDebug};
 
-pub trait Trait: frame_system::Trait {
+pub trait Trait: system::Trait {
     /// convert rate
     type ConvertPrice: Member
         + Parameter
@@ -81,12 +84,44 @@ pub trait Trait: frame_system::Trait {
     >;
 
     /// event
-    type Event: From<Event> + Into<<Self as frame_system::Trait>::Event>;
+    type Event: From<Event> + Into<<Self as system::Trait>::Event>;
 
     type ConvertDuration: Get<Self::BlockNumber>;
     type ConvertPricePrecision: Get<Self::ConvertPrice>;
+
+    /// Market prices of vtokens in their tokens
+    type PriceProvider: PriceProvider<TokenSymbol, Self::ConvertPrice>;
+
+    /// Maximum number of unbonding requests an account can have at once
+    type MaxUnbondingRequests: Get<u32>;
+
+    /// The identifier of swap pools
+    type PoolId: Member + Parameter + Copy;
+
+    /// Swap vtokens for their tokens at market price, when redeemed instantly
+    type SwapHandler: SwapHandler<
+        Self::PoolId,
+        TokenSymbol,
+        Self::AccountId,
+        Self::Balance,
+        Error = DispatchError,
+    >;
 }
 
+/// Tokens being unbonded for an account, after its vtokens are converted back.
+#[derive(Encode, Decode, Clone, Copy, Eq, PartialEq, RuntimeDebug)]
+pub struct UnbondingRequest<Balance, BlockNumber> {
+    /// The token being unbonded
+    pub token_symbol: TokenSymbol,
+    /// Amount of the token
+    pub amount: Balance,
+    /// The block from which the token can be withdrawn
+    pub unlock_at: BlockNumber,
+}
+
+pub type UnbondingRequestOf<T> =
+    UnbondingRequest<<T as Trait>::Balance, <T as system::Trait>::BlockNumber>;
+
 decl_event! {
     pub enum Event {
         UpdateConvertSuccess,
@@ -94,6 +129,9 @@ decl_event! {
         ConvertTokenToVTokenSuccess,
         ConvertVTokenToTokenSuccess,
         RedeemedPointsSuccess,
+        UpdateUnbondingPeriodSuccess,
+        WithdrawUnbondedSuccess,
+        InstantRedeemSuccess,
     }
 }
 
@@ -111,6 +149,14 @@ decl_error! {
         NotSupportaUSD,
         /// Cannot convert token with itself
         ConvertWithTheSameToken,
+        /// Token is not paired with a vtoken
+        TokenNotPaired,
+        /// The token or the account asset is frozen
+        AssetFrozen,
+        /// The account has too many tokens being unbonded
+        TooManyUnbondingRequests,
+        /// None of the tokens being unbonded can be withdrawn yet
+        NothingToWithdraw,
     }
 }
 
@@ -130,6 +176,11 @@ decl_storage! {
         AllReferrerChannels get(fn all_referer_channels): (BTreeMap<T::AccountId, T::Balance>, T::Balance);
         /// Convert pool
         Pool get(fn pool): map hasher(blake2_128_concat) TokenSymbol => ConvertPool<T::Balance>;
+        /// Blocks to wait for before the tokens converted back from vtokens can be withdrawn
+        UnbondingPeriod get(fn unbonding_period) config(): map hasher(blake2_128_concat) TokenSymbol => T::BlockNumber;
+        /// Tokens being unbonded of each account, in the order they are requested
+        UnbondingRequests get(fn unbonding_requests): map hasher(blake2_128_concat) T::AccountId =>
+            Vec<UnbondingRequestOf<T>>;
     }
     add_extra_genesis {
         build(|config: &GenesisConfig<T>| {
@@ -146,6 +197,7 @@ decl_module! {
 
         const ConvertDuration: T::BlockNumber = T::ConvertDuration::get();
         const ConvertPricePrecision: T::ConvertPrice = T::ConvertPricePrecision::get();
+        const MaxUnbondingRequests: u32 = T::MaxUnbondingRequests::get();
 
         fn deposit_event() = default;
 
@@ -193,13 +245,18 @@ decl_module! {
             ensure!(vtoken_symbol != TokenSymbol::aUSD, Error::<T>::NotSupportaUSD);
 
             // get paired tokens
-            let (token_symbol, _) = vtoken_symbol.paired_token();
+            let (token_symbol, paired_vtoken) = T::AssetTrait::get_token_pair(vtoken_symbol).ok_or(Error::<T>::TokenNotPaired)?;
             ensure!(token_symbol != vtoken_symbol, Error::<T>::ConvertWithTheSameToken);
+            ensure!(paired_vtoken == vtoken_symbol, Error::<T>::TokenNotPaired);
 
             // check asset_id exist or not
             ensure!(T::AssetTrait::token_exists(token_symbol), Error::<T>::TokenNotExist);
+            ensure!(
+                !T::AssetTrait::is_frozen(token_symbol, &converter) && !T::AssetTrait::is_frozen(vtoken_symbol, &converter),
+                Error::<T>::AssetFrozen
+            );
 
-            let token_balances = T::AssetTrait::get_account_asset(token_symbol, &converter).balance;
+            let token_balances = T::AssetTrait::get_account_asset(token_symbol, &converter).available;
             ensure!(token_balances >= token_amount, Error::<T>::InvalidBalanceForTransaction);
 
             // check convert price has been set
@@ -224,23 +281,34 @@ decl_module! {
         }
 
         #[weight = T::DbWeight::get().reads_writes(1, 1)]
-        fn to_token(
+        fn set_unbonding_period(
             origin,
             token_symbol: TokenSymbol,
-            #[compact] vtoken_amount: T::Balance,
+            unbonding_period: T::BlockNumber
         ) {
-            let converter = ensure_signed(origin)?;
+            ensure_root(origin)?;
 
             ensure!(token_symbol != TokenSymbol::aUSD, Error::<T>::NotSupportaUSD);
 
-            // get paired tokens
-            let (_, vtoken_symbol) = token_symbol.paired_token();
-            ensure!(token_symbol != vtoken_symbol, Error::<T>::ConvertWithTheSameToken);
+            ensure!(T::AssetTrait::token_exists(token_symbol), Error::<T>::TokenNotExist);
+            <UnbondingPeriod<T>>::insert(token_symbol, unbonding_period);
 
-            // check asset_id exist or not
-            ensure!(T::AssetTrait::token_exists(vtoken_symbol), Error::<T>::TokenNotExist);
+            Self::deposit_event(Event::UpdateUnbondingPeriodSuccess);
+        }
+
+        /// Convert vtokens back to the token at the convert price, the token is withdrawn by
+        /// `withdraw_unbonded` once the unbonding period of the token passes.
+        #[weight = T::DbWeight::get().reads_writes(4, 4)]
+        fn to_token(
+            origin,
+            token_symbol: TokenSymbol,
+            #[compact] vtoken_amount: T::Balance,
+        ) {
+            let converter = ensure_signed(origin)?;
+
+            let vtoken_symbol = Self::ensure_redeemable(token_symbol, &converter)?;
 
-            let vtoken_balances = T::AssetTrait::get_account_asset(vtoken_symbol, &converter).balance;
+            let vtoken_balances = T::AssetTrait::get_account_asset(vtoken_symbol, &converter).available;
             ensure!(vtoken_balances >= vtoken_amount, Error::<T>::InvalidBalanceForTransaction);
 
             // check convert price has been set
@@ -251,8 +319,16 @@ decl_module! {
             ensure!(!price.is_zero(), Error::<T>::InvalidConvertPrice);
             let tokens_buy = vtoken_amount.saturating_mul(price.into()) / T::ConvertPricePrecision::get().into();
 
+            let mut requests = <UnbondingRequests<T>>::get(&converter);
+            ensure!(
+                requests.len() < T::MaxUnbondingRequests::get() as usize,
+                Error::<T>::TooManyUnbondingRequests
+            );
+
             T::AssetTrait::asset_destroy(vtoken_symbol, &converter, vtoken_amount);
-            T::AssetTrait::asset_issue(token_symbol, &converter, tokens_buy);
+            let unlock_at = <system::Module<T>>::block_number().saturating_add(<UnbondingPeriod<T>>::get(token_symbol));
+            requests.push(UnbondingRequest { token_symbol, amount: tokens_buy, unlock_at });
+            <UnbondingRequests<T>>::insert(&converter, requests);
 
             // both are the same pool, but need to be updated together
             Self::decrease_pool(token_symbol, tokens_buy, vtoken_amount);
@@ -263,6 +339,58 @@ decl_module! {
             Self::deposit_event(Event::ConvertVTokenToTokenSuccess);
         }
 
+        /// Withdraw all the tokens of the caller whose unbonding period has passed.
+        #[weight = T::DbWeight::get().reads_writes(1, 1 + T::MaxUnbondingRequests::get() as u64)]
+        fn withdraw_unbonded(origin) {
+            let converter = ensure_signed(origin)?;
+
+            let now = <system::Module<T>>::block_number();
+            let (unbonded, requests): (Vec<_>, Vec<_>) = <UnbondingRequests<T>>::get(&converter)
+                .into_iter()
+                .partition(|request| request.unlock_at <= now);
+            ensure!(!unbonded.is_empty(), Error::<T>::NothingToWithdraw);
+
+            for request in unbonded {
+                T::AssetTrait::asset_issue(request.token_symbol, &converter, request.amount);
+            }
+            if requests.is_empty() {
+                <UnbondingRequests<T>>::remove(&converter);
+            } else {
+                <UnbondingRequests<T>>::insert(&converter, requests);
+            }
+
+            Self::deposit_event(Event::WithdrawUnbondedSuccess);
+        }
+
+        /// Redeem vtokens for the token right away, by selling them in the swap pool at market
+        /// price instead of waiting for the unbonding period.
+        #[weight = T::DbWeight::get().reads_writes(8, 6).saturating_add(T::SwapHandler::swap_exact_in_weight())]
+        fn instant_redeem(
+            origin,
+            token_symbol: TokenSymbol,
+            pool_id: T::PoolId,
+            #[compact] vtoken_amount: T::Balance,
+            #[compact] min_token_amount: T::Balance,
+        ) {
+            let converter = ensure_signed(origin)?;
+
+            let vtoken_symbol = Self::ensure_redeemable(token_symbol, &converter)?;
+
+            T::SwapHandler::swap_exact_in(
+                &converter,
+                pool_id,
+                vtoken_symbol,
+                vtoken_amount,
+                token_symbol,
+                min_token_amount,
+            )?;
+
+            // redeem income
+            Self::redeem_income(converter, vtoken_amount);
+
+            Self::deposit_event(Event::InstantRedeemSuccess);
+        }
+
         fn on_finalize(block_number: T::BlockNumber) {
             // calculate & update convert price
             for (token_id, _convert_pool) in <Pool<T>>::iter() {
@@ -307,6 +435,35 @@ impl<T: Trait> Module<T> {
         <ConvertPrice<T>>::get(token_symbol)
     }
 
+    /// Market price of the vtoken in its token, at the same precision as the convert price.
+    pub fn get_market_price(token_symbol: TokenSymbol) -> Option<T::ConvertPrice> {
+        let (token_symbol, vtoken_symbol) = T::AssetTrait::get_token_pair(token_symbol)?;
+        T::PriceProvider::get_price(vtoken_symbol, token_symbol, T::ConvertPricePrecision::get())
+    }
+
+    /// Check the token can be redeemed from its vtoken by `who`, returns the vtoken.
+    fn ensure_redeemable(
+        token_symbol: TokenSymbol,
+        who: &T::AccountId,
+    ) -> Result<TokenSymbol, Error<T>> {
+        ensure!(token_symbol != TokenSymbol::aUSD, Error::<T>::NotSupportaUSD);
+
+        // get paired tokens
+        let (paired_token, vtoken_symbol) =
+            T::AssetTrait::get_token_pair(token_symbol).ok_or(Error::<T>::TokenNotPaired)?;
+        ensure!(token_symbol != vtoken_symbol, Error::<T>::ConvertWithTheSameToken);
+        ensure!(paired_token == token_symbol, Error::<T>::TokenNotPaired);
+
+        // check asset_id exist or not
+        ensure!(T::AssetTrait::token_exists(vtoken_symbol), Error::<T>::TokenNotExist);
+        ensure!(
+            !T::AssetTrait::is_frozen(token_symbol, who) && !T::AssetTrait::is_frozen(vtoken_symbol, who),
+            Error::<T>::AssetFrozen
+        );
+
+        Ok(vtoken_symbol)
+    }
+
     fn increase_pool(
         token_symbol: TokenSymbol,
         token_amount: T::Balance,
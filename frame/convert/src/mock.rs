@@ -18,11 +18,19 @@
 
 #![cfg(test)]
 
-use frame_support::{impl_outer_origin, impl_outer_event, parameter_types, traits::{OnInitialize, OnFinalize}};
+use frame_support::{
+	impl_outer_origin, impl_outer_dispatch, impl_outer_event, parameter_types, traits::{OnInitialize, OnFinalize}
+};
 use sp_core::H256;
 use sp_runtime::{Perbill, traits::{BlakeTwo256, IdentityLookup}, testing::Header};
 use super::*;
 
+impl_outer_dispatch! {
+	pub enum Call for Test where origin: Origin {
+		swap::Swap,
+	}
+}
+
 #[derive(Clone, Eq, PartialEq)]
 pub struct Test;
 
@@ -35,6 +43,7 @@ impl_outer_event! {
 		system<T>,
 		pallet_convert,
 		assets<T>,
+		swap<T>,
 	}
 }
 
@@ -59,11 +68,16 @@ impl assets::Trait for Test {
 	type Convert = u64;
 	type AssetRedeem = ();
 	type FetchConvertPrice = ();
+	type DustHandler = ();
+	type PriceProvider = ();
+	type PricePrecision = ();
+	type MaxPriceUpdates = ();
+	type TransferHandler = ();
 }
 
 impl system::Trait for Test {
 	type Origin = Origin;
-	type Call = ();
+	type Call = Call;
 	type Index = u64;
 	type BlockNumber = u64;
 	type Hash = H256;
@@ -91,6 +105,7 @@ impl system::Trait for Test {
 parameter_types! {
 	pub const ConvertDuration: u64 = 24 * 60 * 10;
 	pub const ConvertPricePrecision: u64 = 4;
+	pub const MaxUnbondingRequests: u32 = 2;
 }
 
 impl crate::Trait for Test {
@@ -104,11 +119,50 @@ impl crate::Trait for Test {
 	type Income = u64;
 	type ConvertDuration = ConvertDuration;
 	type ConvertPricePrecision = ConvertPricePrecision;
+	type PriceProvider = ();
+	type MaxUnbondingRequests = MaxUnbondingRequests;
+	type PoolId = swap::PoolId;
+	type SwapHandler = Swap;
+}
+
+parameter_types! {
+	pub const InitPoolSupply: u64 = 1000;
+	pub const MaximumSwapInRatio: u64 = 2;
+	pub const MinimumBalance: u64 = 10;
+	pub const MaximumSwapFee: u64 = 10_000; // 10%
+	pub const MinimumSwapFee: u64 = 1; // 0.0001%
+	pub const FeePrecision: u64 = 100_000;
+	pub const MaximumPriceMovement: u64 = 400_000; // 400%
+	pub const TwapWindow: u64 = 5;
+}
+
+impl swap::Trait for Test {
+	type Fee = u64;
+	type Event = TestEvent;
+	type Call = Call;
+	type AssetTrait = Assets;
+	type Balance = u64;
+	type AssetId = u32;
+	type Cost = u64;
+	type Income = u64;
+	type InvariantValue = u64;
+	type PoolWeight = u64;
+	type InitPoolSupply = InitPoolSupply;
+	type MaximumSwapInRatio = MaximumSwapInRatio;
+	type MinimumBalance = MinimumBalance;
+	type MaximumSwapFee = MaximumSwapFee;
+	type MinimumSwapFee = MinimumSwapFee;
+	type FeePrecision = FeePrecision;
+	type MaximumPriceMovement = MaximumPriceMovement;
+	type TwapWindow = TwapWindow;
+	type FetchConvertPrice = ();
+	type ConvertPricePrecision = ConvertPricePrecision;
 }
 
 pub type Convert = crate::Module<Test>;
 pub type System = system::Module<Test>;
 pub type Assets = assets::Module<Test>;
+pub type Swap = swap::Module<Test>;
 
 pub(crate) fn run_to_block(n: u64) {
 	while System::block_number() < n {
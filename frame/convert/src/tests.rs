@@ -20,8 +20,9 @@
 use alloc::collections::btree_map::BTreeMap;
 use crate::*;
 use crate::mock::*;
-use frame_support::assert_ok;
-use node_primitives::{ConvertPool, TokenSymbol};
+use frame_support::{assert_noop, assert_ok};
+use node_primitives::{ConvertPool, Token, TokenSymbol};
+use sp_runtime::DispatchError;
 
 #[test]
 #[ignore]
@@ -31,7 +32,7 @@ fn update_rate_multiple_times() {
 		// issue a vtoken
 		let symbol = b"aUSD".to_vec();
 		let precise = 18;
-		assert_ok!(assets::Module::<Test>::create(Origin::root(), symbol, precise));
+		assert_ok!(assets::Module::<Test>::create(Origin::root(), symbol, precise, None));
 		let ausd_id = <assets::NextAssetId<Test>>::get() - 1;
 		let ausd_type = TokenSymbol::from(ausd_id);
 
@@ -55,7 +56,7 @@ fn update_rate_multiple_times_until_overflow() {
 		run_to_block(1);
 		let symbol = b"aUSD".to_vec();
 		let precise = 4;
-		assert_ok!(assets::Module::<Test>::create(Origin::root(), symbol, precise));
+		assert_ok!(assets::Module::<Test>::create(Origin::root(), symbol, precise, None));
 		let ausd_id = <assets::NextAssetId<Test>>::get() - 1;
 		let ausd_type = TokenSymbol::from(ausd_id);
 
@@ -92,13 +93,13 @@ fn to_vtoken_should_be_ok() {
 		let vdot_symbol = b"vDOT".to_vec();
 		let precise = 4;
 
-		assert_ok!(assets::Module::<Test>::create(Origin::root(), b"aUSD".to_vec(), precise)); // let asset id is start from 1
+		assert_ok!(assets::Module::<Test>::create(Origin::root(), b"aUSD".to_vec(), precise, None)); // let asset id is start from 1
 
-		assert_ok!(assets::Module::<Test>::create(Origin::root(), dot_symbol, precise));
+		assert_ok!(assets::Module::<Test>::create(Origin::root(), dot_symbol, precise, None));
 		let dot_id = <assets::NextAssetId<Test>>::get() - 1;
 		let dot_type = TokenSymbol::from(dot_id);
 
-		assert_ok!(assets::Module::<Test>::create(Origin::root(), vdot_symbol, precise));
+		assert_ok!(assets::Module::<Test>::register_token(Origin::root(), vdot_symbol, precise, vec![], vec![], Some(dot_type)));
 		let vdot_id = <assets::NextAssetId<Test>>::get() - 1;
 		let vdot_type = TokenSymbol::from(vdot_id);
 
@@ -134,13 +135,13 @@ fn to_token_should_be_ok() {
 		let vdot_symbol = b"vDOT".to_vec();
 		let precise = 4;
 
-		assert_ok!(assets::Module::<Test>::create(Origin::root(), b"aUSD".to_vec(), precise)); // let asset id is start from 1
+		assert_ok!(assets::Module::<Test>::create(Origin::root(), b"aUSD".to_vec(), precise, None)); // let asset id is start from 1
 
-		assert_ok!(assets::Module::<Test>::create(Origin::root(), dot_symbol, precise));
+		assert_ok!(assets::Module::<Test>::create(Origin::root(), dot_symbol, precise, None));
 		let dot_id = <assets::NextAssetId<Test>>::get() - 1;
 		let dot_type = TokenSymbol::from(dot_id);
 
-		assert_ok!(assets::Module::<Test>::create(Origin::root(), vdot_symbol, precise));
+		assert_ok!(assets::Module::<Test>::register_token(Origin::root(), vdot_symbol, precise, vec![], vec![], Some(dot_type)));
 		let vdot_id = <assets::NextAssetId<Test>>::get() - 1;
 		let vdot_type = TokenSymbol::from(vdot_id);
 
@@ -154,13 +155,28 @@ fn to_token_should_be_ok() {
 		let rate = 2;
 		assert_ok!(Convert::set_convert_price(Origin::root(), dot_type, rate));
 
+		// dot is unbonded in 10 blocks
+		let unbonding_period = 10;
+		assert_ok!(Convert::set_unbonding_period(Origin::root(), dot_type, unbonding_period));
+
 		// convert
 		let bob_vdot_convert = 10;
 		assert_ok!(Convert::to_token(Origin::signed(bob), dot_type, bob_vdot_convert));
 		assert_eq!(<assets::AccountAssets<Test>>::get((vdot_type, bob)).balance, bob_vdot_issued - bob_vdot_convert); // check bob's token change
-		assert_eq!(<assets::AccountAssets<Test>>::get((dot_type, bob)).balance, bob_dot_issued + bob_vdot_convert / rate); // check bob's token change
+		assert_eq!(<assets::AccountAssets<Test>>::get((dot_type, bob)).balance, bob_dot_issued); // dot is being unbonded
+		assert_eq!(
+			Convert::unbonding_requests(bob),
+			vec![UnbondingRequest { token_symbol: dot_type, amount: bob_vdot_convert / rate, unlock_at: 2 + unbonding_period }]
+		);
 
 		assert_eq!(Convert::pool(dot_type), ConvertPool::new(0, 0));
+
+		// withdraw after the unbonding period
+		assert_noop!(Convert::withdraw_unbonded(Origin::signed(bob)), Error::<Test>::NothingToWithdraw);
+		run_to_block(2 + unbonding_period);
+		assert_ok!(Convert::withdraw_unbonded(Origin::signed(bob)));
+		assert_eq!(<assets::AccountAssets<Test>>::get((dot_type, bob)).balance, bob_dot_issued + bob_vdot_convert / rate);
+		assert!(Convert::unbonding_requests(bob).is_empty());
 	});
 }
 
@@ -177,13 +193,13 @@ fn add_new_refer_channel_should_be_ok() {
 		let vdot_symbol = b"vDOT".to_vec();
 		let precise = 4;
 
-		assert_ok!(assets::Module::<Test>::create(Origin::root(), b"aUSD".to_vec(), precise)); // let asset id is start from 1
+		assert_ok!(assets::Module::<Test>::create(Origin::root(), b"aUSD".to_vec(), precise, None)); // let asset id is start from 1
 
-		assert_ok!(assets::Module::<Test>::create(Origin::root(), dot_symbol, precise));
+		assert_ok!(assets::Module::<Test>::create(Origin::root(), dot_symbol, precise, None));
 		let dot_id = <assets::NextAssetId<Test>>::get() - 1;
 		let dot_type = TokenSymbol::from(dot_id);
 
-		assert_ok!(assets::Module::<Test>::create(Origin::root(), vdot_symbol, precise));
+		assert_ok!(assets::Module::<Test>::register_token(Origin::root(), vdot_symbol, precise, vec![], vec![], Some(dot_type)));
 		let vdot_id = <assets::NextAssetId<Test>>::get() - 1;
 		let vdot_type = TokenSymbol::from(vdot_id);
 
@@ -308,3 +324,190 @@ fn add_new_refer_channel_should_be_ok() {
 		);
 	});
 }
+
+#[test]
+fn convert_unpaired_token_should_not_work() {
+	new_test_ext().execute_with(|| {
+		run_to_block(2);
+
+		let bob = 1u64;
+		let precise = 4;
+
+		assert_ok!(assets::Module::<Test>::create(Origin::root(), b"aUSD".to_vec(), precise, None));
+		assert_ok!(assets::Module::<Test>::create(Origin::root(), b"DOT".to_vec(), precise, None));
+		let dot_type = TokenSymbol::from(<assets::NextAssetId<Test>>::get() - 1);
+		assert_ok!(assets::Module::<Test>::create(Origin::root(), b"vDOT".to_vec(), precise, None));
+		let vdot_type = TokenSymbol::from(<assets::NextAssetId<Test>>::get() - 1);
+
+		assert_ok!(assets::Module::<Test>::issue(Origin::root(), dot_type, bob, 60));
+		assert_ok!(Convert::set_convert_price(Origin::root(), dot_type, 2));
+
+		// vDOT is created without pairing it with DOT
+		assert_noop!(Convert::to_vtoken(Origin::signed(bob), vdot_type, 10, None), Error::<Test>::TokenNotPaired);
+		assert_noop!(Convert::to_token(Origin::signed(bob), dot_type, 10), Error::<Test>::TokenNotPaired);
+	});
+}
+
+#[test]
+fn genesis_convert_price_should_work() {
+	let bob = 1u64;
+	let mut t = system::GenesisConfig::default().build_storage::<Test>().unwrap();
+	assets::GenesisConfig::<Test> {
+		account_assets: vec![],
+		next_asset_id: 0,
+		token_details: vec![
+			(TokenSymbol::DOT, Token::new(b"DOT".to_vec(), 12, 0)),
+			(TokenSymbol::vDOT, Token::new(b"vDOT".to_vec(), 12, 0)),
+		],
+		token_pairs: vec![(TokenSymbol::DOT, TokenSymbol::vDOT)],
+		prices: vec![],
+	}.assimilate_storage(&mut t).unwrap();
+	// the convert price is kept by the token, like the chain spec does
+	crate::GenesisConfig::<Test> {
+		convert_price: vec![(TokenSymbol::DOT, 4)], // 1:1
+		unbonding_period: vec![(TokenSymbol::DOT, 10)],
+	}.assimilate_storage(&mut t).unwrap();
+
+	sp_io::TestExternalities::from(t).execute_with(|| {
+		run_to_block(2);
+
+		assert_eq!(Convert::convert_price(TokenSymbol::DOT), 4);
+		assert_eq!(Convert::unbonding_period(TokenSymbol::DOT), 10);
+
+		assert_ok!(assets::Module::<Test>::issue(Origin::root(), TokenSymbol::DOT, bob, 100));
+		assert_ok!(Convert::to_vtoken(Origin::signed(bob), TokenSymbol::vDOT, 100, None));
+		assert_eq!(<assets::AccountAssets<Test>>::get((TokenSymbol::vDOT, bob)).balance, 100);
+
+		assert_ok!(Convert::to_token(Origin::signed(bob), TokenSymbol::DOT, 40));
+		assert_eq!(
+			Convert::unbonding_requests(bob),
+			vec![UnbondingRequest { token_symbol: TokenSymbol::DOT, amount: 40, unlock_at: 12 }]
+		);
+	});
+}
+
+// dot and vdot paired, 1 vdot is converted to 2 dot
+fn setup_pair() -> (TokenSymbol, TokenSymbol) {
+	let precise = 4;
+
+	assert_ok!(assets::Module::<Test>::create(Origin::root(), b"aUSD".to_vec(), precise, None)); // let asset id is start from 1
+	assert_ok!(assets::Module::<Test>::create(Origin::root(), b"DOT".to_vec(), precise, None));
+	let dot_type = TokenSymbol::from(<assets::NextAssetId<Test>>::get() - 1);
+	assert_ok!(assets::Module::<Test>::register_token(Origin::root(), b"vDOT".to_vec(), precise, vec![], vec![], Some(dot_type)));
+	let vdot_type = TokenSymbol::from(<assets::NextAssetId<Test>>::get() - 1);
+
+	assert_ok!(Convert::set_convert_price(Origin::root(), dot_type, 8));
+
+	(dot_type, vdot_type)
+}
+
+#[test]
+fn unbonding_requests_should_be_withdrawn_once_unbonded() {
+	new_test_ext().execute_with(|| {
+		run_to_block(2);
+
+		let bob = 1u64;
+		let (dot_type, vdot_type) = setup_pair();
+		assert_ok!(assets::Module::<Test>::issue(Origin::root(), vdot_type, bob, 100));
+
+		assert_noop!(
+			Convert::set_unbonding_period(Origin::root(), TokenSymbol::aUSD, 10),
+			Error::<Test>::NotSupportaUSD
+		);
+		assert_ok!(Convert::set_unbonding_period(Origin::root(), dot_type, 10));
+		assert_eq!(Convert::unbonding_period(dot_type), 10);
+
+		// two requests unbonded at different blocks
+		assert_ok!(Convert::to_token(Origin::signed(bob), dot_type, 10));
+		run_to_block(5);
+		assert_ok!(Convert::to_token(Origin::signed(bob), dot_type, 20));
+		assert_eq!(
+			Convert::unbonding_requests(bob),
+			vec![
+				UnbondingRequest { token_symbol: dot_type, amount: 20, unlock_at: 12 },
+				UnbondingRequest { token_symbol: dot_type, amount: 40, unlock_at: 15 },
+			]
+		);
+		assert_eq!(<assets::AccountAssets<Test>>::get((vdot_type, bob)).balance, 70);
+
+		// no more requests until one of them is withdrawn
+		assert_noop!(Convert::to_token(Origin::signed(bob), dot_type, 10), Error::<Test>::TooManyUnbondingRequests);
+
+		// only the first one is unbonded
+		run_to_block(12);
+		assert_ok!(Convert::withdraw_unbonded(Origin::signed(bob)));
+		assert_eq!(<assets::AccountAssets<Test>>::get((dot_type, bob)).balance, 20);
+		assert_eq!(
+			Convert::unbonding_requests(bob),
+			vec![UnbondingRequest { token_symbol: dot_type, amount: 40, unlock_at: 15 }]
+		);
+		assert_noop!(Convert::withdraw_unbonded(Origin::signed(bob)), Error::<Test>::NothingToWithdraw);
+		assert_ok!(Convert::to_token(Origin::signed(bob), dot_type, 10));
+
+		run_to_block(22);
+		assert_ok!(Convert::withdraw_unbonded(Origin::signed(bob)));
+		assert_eq!(<assets::AccountAssets<Test>>::get((dot_type, bob)).balance, 20 + 40 + 20);
+		assert!(Convert::unbonding_requests(bob).is_empty());
+	});
+}
+
+#[test]
+fn instant_redeem_should_swap_at_market_price() {
+	new_test_ext().execute_with(|| {
+		run_to_block(2);
+
+		let alice = 1u64;
+		let bob = 2u64;
+		let (dot_type, vdot_type) = setup_pair();
+		assert_ok!(assets::Module::<Test>::issue(Origin::root(), dot_type, alice, 1_000_000));
+		assert_ok!(assets::Module::<Test>::issue(Origin::root(), vdot_type, alice, 1_000_000));
+		assert_ok!(assets::Module::<Test>::issue(Origin::root(), vdot_type, bob, 10_000));
+		assert_ok!(Convert::set_unbonding_period(Origin::root(), dot_type, 10));
+
+		// a pool of 100_000 dot and 100_000 vdot, with 0.1% swap fee
+		assert_ok!(Swap::create_pool(Origin::signed(alice), vec![(dot_type, 100_000, 1), (vdot_type, 100_000, 1)], 100));
+		let pool_id = 0;
+
+		// 100_000 * (1 - 100_000 / (100_000 + 1_000 * 0.999)) = 989.1...
+		assert_noop!(
+			Convert::instant_redeem(Origin::signed(bob), dot_type, pool_id, 1_000, 990),
+			DispatchError::Module { index: 0, error: 14, message: Some("LessThanExpectedAmount") }
+		);
+		assert_ok!(Convert::instant_redeem(Origin::signed(bob), dot_type, pool_id, 1_000, 980));
+		assert_eq!(<assets::AccountAssets<Test>>::get((vdot_type, bob)).balance, 10_000 - 1_000);
+		assert_eq!(<assets::AccountAssets<Test>>::get((dot_type, bob)).balance, 989);
+
+		// nothing is unbonded, and the convert pool is untouched
+		assert!(Convert::unbonding_requests(bob).is_empty());
+		assert_eq!(Convert::pool(dot_type), ConvertPool::new(0, 0));
+
+		// the whole balance can be redeemed, a swap is only limited by the pool balance
+		// 99_011 * (1 - 101_000 / (101_000 + 9_000 * 0.999)) = 8_093.4...
+		assert_ok!(Convert::instant_redeem(Origin::signed(bob), dot_type, pool_id, 9_000, 8_000));
+		assert_eq!(<assets::AccountAssets<Test>>::get((vdot_type, bob)).balance, 0);
+		let bob_dot = <assets::AccountAssets<Test>>::get((dot_type, bob)).balance;
+		assert!(bob_dot >= 989 + 8_092 && bob_dot <= 989 + 8_094);
+	});
+}
+
+#[test]
+fn reserved_balance_should_not_be_converted() {
+	new_test_ext().execute_with(|| {
+		run_to_block(2);
+
+		let bob = 1u64;
+		let (dot_type, vdot_type) = setup_pair();
+		assert_ok!(assets::Module::<Test>::issue(Origin::root(), dot_type, bob, 100));
+		assert_ok!(assets::Module::<Test>::issue(Origin::root(), vdot_type, bob, 100));
+
+		// reserved by another module
+		assert_ok!(<Assets as AssetTrait<_, _, _, _, _>>::reserve(*b"reserved", dot_type, &bob, 60));
+		assert_ok!(<Assets as AssetTrait<_, _, _, _, _>>::reserve(*b"reserved", vdot_type, &bob, 60));
+
+		assert_noop!(Convert::to_vtoken(Origin::signed(bob), vdot_type, 50, None), Error::<Test>::InvalidBalanceForTransaction);
+		assert_noop!(Convert::to_token(Origin::signed(bob), dot_type, 50), Error::<Test>::InvalidBalanceForTransaction);
+
+		assert_ok!(Convert::to_vtoken(Origin::signed(bob), vdot_type, 40, None));
+		assert_ok!(Convert::to_token(Origin::signed(bob), dot_type, 40));
+	});
+}
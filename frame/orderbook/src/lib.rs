@@ -0,0 +1,422 @@
+// Copyright 2020 Keysians Technologies.
+// This file is part of Keysians.
+
+// Keysians is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Keysians is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Keysians.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Limit orders filled through the swap pools.
+//!
+//! An order sells an exact amount of a token once the pool pays at least the limit price for it.
+//! Open orders are linked in a queue and checked in turns on initialize, at most
+//! `MaxOrdersPerBlock` in a block.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+mod mock;
+mod tests;
+
+use codec::{Decode, Encode};
+use frame_support::traits::Get;
+use frame_support::weights::Weight;
+use frame_support::{decl_error, decl_event, decl_module, decl_storage, ensure, Parameter};
+use frame_system::{self as system, ensure_signed};
+use node_primitives::{AssetTrait, LockIdentifier, SwapHandler, TokenSymbol};
+use sp_runtime::helpers_128bit::multiply_by_rational;
+use sp_runtime::traits::{AtLeast32Bit, Member, SaturatedConversion, Zero};
+use sp_runtime::RuntimeDebug;
+use sp_std::prelude::*;
+
+/// Identifier of a limit order.
+pub type OrderId = u64;
+
+/// Identifier of the reserve holding the tokens sold by open orders.
+const ORDERBOOK_ID: LockIdentifier = *b"orderbok";
+
+/// Sell `token_amount_in` of `token_in` for `token_out` once a `token_in` is worth at least
+/// `limit_price` in the pool.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug)]
+pub struct Order<AccountId, PoolId, Balance, BlockNumber> {
+    /// The account selling the tokens.
+    pub owner: AccountId,
+    /// The pool to swap in.
+    pub pool_id: PoolId,
+    pub token_in: TokenSymbol,
+    pub token_amount_in: Balance,
+    pub token_out: TokenSymbol,
+    /// Minimum amount of `token_out` for a `token_in`, a price of 1 is `PricePrecision`.
+    pub limit_price: Balance,
+    /// The order is removed at this block if it's not filled.
+    pub expire_at: BlockNumber,
+}
+
+type OrderOf<T> = Order<
+    <T as system::Trait>::AccountId,
+    <T as Trait>::PoolId,
+    <T as Trait>::Balance,
+    <T as system::Trait>::BlockNumber,
+>;
+
+pub trait Trait: system::Trait {
+    /// event
+    type Event: From<Event<Self>> + Into<<Self as system::Trait>::Event>;
+    /// The units in which we record balances.
+    type Balance: Member + Parameter + AtLeast32Bit + Default + Copy;
+    /// The arithmetic type of asset identifier.
+    type AssetId: Member + Parameter + AtLeast32Bit + Default + Copy;
+    /// The units in which we record costs.
+    type Cost: Member + Parameter + AtLeast32Bit + Default + Copy;
+    /// The units in which we record incomes.
+    type Income: Member + Parameter + AtLeast32Bit + Default + Copy;
+    /// Asset handler
+    type AssetTrait: AssetTrait<
+        Self::AssetId,
+        Self::AccountId,
+        Self::Balance,
+        Self::Cost,
+        Self::Income,
+    >;
+    /// Identifier of a swap pool.
+    type PoolId: Member + Parameter + Copy;
+    /// Swap handler filling the orders
+    type SwapHandler: SwapHandler<Self::PoolId, TokenSymbol, Self::AccountId, Self::Balance>;
+    /// A limit price of 1.
+    type PricePrecision: Get<Self::Balance>;
+    /// Maximum number of orders checked in a block.
+    type MaxOrdersPerBlock: Get<u32>;
+    /// Maximum number of open orders.
+    type MaxOpenOrders: Get<u32>;
+    /// Maximum number of open orders of an account.
+    type MaxOrdersPerAccount: Get<u32>;
+}
+
+decl_event! {
+    pub enum Event<T> where
+        <T as Trait>::Balance,
+        <T as system::Trait>::AccountId,
+    {
+        /// An order has been placed.
+        OrderPlaced(OrderId, AccountId),
+        /// An order has been filled, (order, owner, amount sold, amount bought).
+        OrderFilled(OrderId, AccountId, Balance, Balance),
+        /// An order has been cancelled by the owner, or its tokens can't be reserved any more.
+        OrderCancelled(OrderId, AccountId),
+        /// An order has expired before being filled.
+        OrderExpired(OrderId, AccountId),
+    }
+}
+
+decl_error! {
+    pub enum Error for Module<T: Trait> {
+        /// Cannot sell a token for itself.
+        SameTokenOrder,
+        /// The amount to sell or the limit price is zero.
+        InvalidOrder,
+        /// The order expires before the next block.
+        InvalidExpiry,
+        /// The available balance is not enough for the order.
+        NotEnoughBalance,
+        /// Too many open orders.
+        TooManyOrders,
+        /// The order doesn't exist.
+        OrderNotExist,
+        /// Only the owner can cancel the order.
+        NotOrderOwner,
+        /// The pool doesn't exist or doesn't have the tokens of the order.
+        TokenNotInPool,
+        /// The order sells more than a swap of the pool pays, it can never be filled.
+        ExceedMaximumSwapInRatio,
+    }
+}
+
+decl_storage! {
+    trait Store for Module<T: Trait> as Orderbook {
+        /// Identifier of the next order.
+        NextOrderId get(fn next_order_id): OrderId;
+        /// Open orders.
+        Orders get(fn orders): map hasher(blake2_128_concat) OrderId => Option<OrderOf<T>>;
+        /// The (previous, next) open orders of an open order, open orders are linked in
+        /// ascending order.
+        OrderLinks get(fn order_links): map hasher(blake2_128_concat) OrderId => (Option<OrderId>, Option<OrderId>);
+        /// The (first, last) open orders.
+        OrderQueue get(fn order_queue): Option<(OrderId, OrderId)>;
+        /// Number of open orders.
+        OpenOrderCount get(fn open_order_count): u32;
+        /// Number of open orders of an account.
+        OrderCount get(fn order_count): map hasher(blake2_128_concat) T::AccountId => u32;
+        /// The next order to check, checking starts over from the first open order if it's none.
+        NextCheckedOrder get(fn next_checked_order): Option<OrderId>;
+    }
+}
+
+decl_module! {
+    pub struct Module<T: Trait> for enum Call where origin: T::Origin {
+        type Error = Error<T>;
+
+        const PricePrecision: T::Balance = T::PricePrecision::get();
+        const MaxOrdersPerBlock: u32 = T::MaxOrdersPerBlock::get();
+        const MaxOpenOrders: u32 = T::MaxOpenOrders::get();
+        const MaxOrdersPerAccount: u32 = T::MaxOrdersPerAccount::get();
+
+        fn deposit_event() = default;
+
+        fn on_initialize(now: T::BlockNumber) -> Weight {
+            let (checked, closed, swapped) = Self::check_orders(now);
+
+            // a checked order reads its links, itself and the pool, a closed order is unlinked and
+            // its tokens are released, and a swapped order costs a swap
+            T::DbWeight::get().reads_writes(3, 1)
+                .saturating_add(T::DbWeight::get().reads(3).saturating_mul(checked as Weight))
+                .saturating_add(T::DbWeight::get().reads_writes(4, 7).saturating_mul(closed as Weight))
+                .saturating_add(T::SwapHandler::swap_exact_in_weight().saturating_mul(swapped as Weight))
+        }
+
+        /// Sell `token_amount_in` of `token_in` for `token_out` in the pool once a `token_in` is
+        /// worth at least `limit_price`. The tokens are reserved until the order is closed.
+        #[weight = T::DbWeight::get().reads_writes(6, 7)]
+        fn place_order(
+            origin,
+            pool_id: T::PoolId,
+            token_in: TokenSymbol,
+            #[compact] token_amount_in: T::Balance,
+            token_out: TokenSymbol,
+            #[compact] limit_price: T::Balance,
+            expire_at: T::BlockNumber
+        ) {
+            let owner = ensure_signed(origin)?;
+
+            ensure!(token_in != token_out, Error::<T>::SameTokenOrder);
+            ensure!(!token_amount_in.is_zero() && !limit_price.is_zero(), Error::<T>::InvalidOrder);
+            ensure!(expire_at > <system::Module<T>>::block_number(), Error::<T>::InvalidExpiry);
+
+            let max_amount_in = T::SwapHandler::max_amount_in(pool_id, token_in).ok_or(Error::<T>::TokenNotInPool)?;
+            ensure!(T::SwapHandler::max_amount_in(pool_id, token_out).is_some(), Error::<T>::TokenNotInPool);
+            ensure!(token_amount_in <= max_amount_in, Error::<T>::ExceedMaximumSwapInRatio);
+
+            ensure!(Self::open_order_count() < T::MaxOpenOrders::get(), Error::<T>::TooManyOrders);
+            let order_count = Self::order_count(&owner);
+            ensure!(order_count < T::MaxOrdersPerAccount::get(), Error::<T>::TooManyOrders);
+
+            T::AssetTrait::reserve(ORDERBOOK_ID, token_in, &owner, token_amount_in)
+                .map_err(|_| Error::<T>::NotEnoughBalance)?;
+
+            let order_id = Self::next_order_id();
+            let order = Order {
+                owner: owner.clone(),
+                pool_id,
+                token_in,
+                token_amount_in,
+                token_out,
+                limit_price,
+                expire_at,
+            };
+            <Orders<T>>::insert(order_id, order);
+            Self::link_order(order_id);
+            <OrderCount<T>>::insert(&owner, order_count + 1);
+            NextOrderId::put(order_id + 1);
+
+            Self::deposit_event(RawEvent::OrderPlaced(order_id, owner));
+        }
+
+        /// Cancel an open order, the tokens reserved are released.
+        #[weight = T::DbWeight::get().reads_writes(6, 9)]
+        fn cancel_order(origin, order_id: OrderId) {
+            let who = ensure_signed(origin)?;
+
+            let order = Self::orders(order_id).ok_or(Error::<T>::OrderNotExist)?;
+            ensure!(order.owner == who, Error::<T>::NotOrderOwner);
+
+            Self::close_order(order_id, &order);
+
+            Self::deposit_event(RawEvent::OrderCancelled(order_id, who));
+        }
+    }
+}
+
+impl<T: Trait> Module<T> {
+    /// Identifiers of the open orders, in ascending order. It reads all of them, so it's only
+    /// for queries.
+    pub fn open_orders() -> Vec<OrderId> {
+        let mut open_orders = Vec::with_capacity(Self::open_order_count() as usize);
+        let mut next = Self::order_queue().map(|(first, _)| first);
+        while let Some(order_id) = next {
+            open_orders.push(order_id);
+            next = Self::order_links(order_id).1;
+        }
+
+        open_orders
+    }
+
+    /// Check at most `MaxOrdersPerBlock` open orders from the next one to check, fill the orders
+    /// whose limit price is reached and remove the expired ones. Returns the number of orders
+    /// checked, closed and swapped.
+    fn check_orders(now: T::BlockNumber) -> (u32, u32, u32) {
+        let count = Self::open_order_count().min(T::MaxOrdersPerBlock::get());
+        if count == 0 {
+            return (0, 0, 0);
+        }
+
+        let (mut closed, mut swapped) = (0, 0);
+        let mut next = Self::next_checked_order();
+        for _ in 0..count {
+            // wrap around after the last one
+            let order_id = match next.or_else(|| Self::order_queue().map(|(first, _)| first)) {
+                Some(order_id) => order_id,
+                None => break,
+            };
+            next = Self::order_links(order_id).1;
+
+            let order = match Self::orders(order_id) {
+                Some(order) => order,
+                None => {
+                    Self::unlink_order(order_id);
+                    closed += 1;
+                    continue;
+                }
+            };
+
+            if now >= order.expire_at {
+                Self::close_order(order_id, &order);
+                Self::deposit_event(RawEvent::OrderExpired(order_id, order.owner));
+                closed += 1;
+            } else if let Some(filled) = Self::try_fill(order_id, &order) {
+                swapped += 1;
+                if filled {
+                    closed += 1;
+                }
+            }
+        }
+
+        match next {
+            Some(order_id) => NextCheckedOrder::put(order_id),
+            None => NextCheckedOrder::kill(),
+        }
+
+        (count, closed, swapped)
+    }
+
+    /// Fill the order if the pool pays at least the limit price. Returns whether the order is
+    /// closed if it's swapped, or none if the limit price is not reached or the pool is too small
+    /// for the order at the moment.
+    fn try_fill(order_id: OrderId, order: &OrderOf<T>) -> Option<bool> {
+        match T::SwapHandler::max_amount_in(order.pool_id, order.token_in) {
+            Some(max_amount_in) if order.token_amount_in <= max_amount_in => {}
+            _ => return None,
+        }
+
+        let precision = T::PricePrecision::get();
+        match T::SwapHandler::spot_price(order.pool_id, order.token_in, order.token_out, precision) {
+            Some(price) if price >= order.limit_price => {}
+            _ => return None,
+        }
+
+        // the average price of the fill should reach the limit price as well
+        let min_token_amount_out = match multiply_by_rational(
+            order.token_amount_in.saturated_into::<u128>(),
+            order.limit_price.saturated_into::<u128>(),
+            precision.saturated_into::<u128>(),
+        ) {
+            Ok(amount) => amount.saturated_into::<T::Balance>(),
+            Err(_) => return None,
+        };
+
+        T::AssetTrait::unreserve(ORDERBOOK_ID, order.token_in, &order.owner, order.token_amount_in);
+        match T::SwapHandler::swap_exact_in(
+            &order.owner,
+            order.pool_id,
+            order.token_in,
+            order.token_amount_in,
+            order.token_out,
+            min_token_amount_out,
+        ) {
+            Ok(token_amount_out) => {
+                Self::remove_order(order_id, &order.owner);
+                Self::deposit_event(RawEvent::OrderFilled(
+                    order_id,
+                    order.owner.clone(),
+                    order.token_amount_in,
+                    token_amount_out,
+                ));
+                Some(true)
+            }
+            Err(_) => {
+                // keep the order open unless its tokens can't be reserved again
+                if T::AssetTrait::reserve(ORDERBOOK_ID, order.token_in, &order.owner, order.token_amount_in).is_ok() {
+                    return Some(false);
+                }
+                Self::remove_order(order_id, &order.owner);
+                Self::deposit_event(RawEvent::OrderCancelled(order_id, order.owner.clone()));
+                Some(true)
+            }
+        }
+    }
+
+    /// Release the tokens of the order and remove it.
+    fn close_order(order_id: OrderId, order: &OrderOf<T>) {
+        T::AssetTrait::unreserve(ORDERBOOK_ID, order.token_in, &order.owner, order.token_amount_in);
+        Self::remove_order(order_id, &order.owner);
+    }
+
+    fn remove_order(order_id: OrderId, owner: &T::AccountId) {
+        <Orders<T>>::remove(order_id);
+        Self::unlink_order(order_id);
+        let order_count = Self::order_count(owner).saturating_sub(1);
+        if order_count == 0 {
+            <OrderCount<T>>::remove(owner);
+        } else {
+            <OrderCount<T>>::insert(owner, order_count);
+        }
+    }
+
+    /// Append an order to the open orders.
+    fn link_order(order_id: OrderId) {
+        match Self::order_queue() {
+            Some((first, last)) => {
+                OrderLinks::mutate(last, |links| links.1 = Some(order_id));
+                OrderLinks::insert(order_id, (Some(last), None::<OrderId>));
+                OrderQueue::put((first, order_id));
+            }
+            None => {
+                OrderLinks::insert(order_id, (None::<OrderId>, None::<OrderId>));
+                OrderQueue::put((order_id, order_id));
+            }
+        }
+        OpenOrderCount::mutate(|count| *count += 1);
+    }
+
+    /// Remove an order from the open orders, checking continues from the order after it.
+    fn unlink_order(order_id: OrderId) {
+        if !OrderLinks::contains_key(order_id) {
+            return;
+        }
+        let (prev, next) = OrderLinks::take(order_id);
+        if let Some(prev) = prev {
+            OrderLinks::mutate(prev, |links| links.1 = next);
+        }
+        if let Some(next) = next {
+            OrderLinks::mutate(next, |links| links.0 = prev);
+        }
+        match (Self::order_queue(), prev, next) {
+            (_, None, None) => OrderQueue::kill(),
+            (Some((_, last)), None, Some(next)) => OrderQueue::put((next, last)),
+            (Some((first, _)), Some(prev), None) => OrderQueue::put((first, prev)),
+            _ => {}
+        }
+        if Self::next_checked_order() == Some(order_id) {
+            match next {
+                Some(next) => NextCheckedOrder::put(next),
+                None => NextCheckedOrder::kill(),
+            }
+        }
+        OpenOrderCount::mutate(|count| *count = count.saturating_sub(1));
+    }
+}
@@ -0,0 +1,180 @@
+// Copyright 2020 Keysians Technologies.
+// This file is part of Keysians.
+
+// Keysians is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Keysians is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Keysians.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Test utilities
+
+#![cfg(test)]
+
+use frame_support::{
+	impl_outer_origin, impl_outer_dispatch, impl_outer_event, parameter_types, traits::{OnInitialize, OnFinalize}
+};
+use sp_core::H256;
+use sp_runtime::{Perbill, testing::Header, traits::{BlakeTwo256, IdentityLookup}};
+use super::*;
+
+impl_outer_dispatch! {
+	pub enum Call for Test where origin: Origin {
+		swap::Swap,
+	}
+}
+
+#[derive(Clone, Eq, PartialEq)]
+pub struct Test;
+
+impl_outer_origin! {
+	pub enum Origin for Test {}
+}
+
+impl_outer_event! {
+	pub enum TestEvent for Test {
+		system<T>,
+		pallet_orderbook<T>,
+		assets<T>,
+		swap<T>,
+	}
+}
+
+mod pallet_orderbook {
+	pub use crate::Event;
+}
+
+parameter_types! {
+	pub const BlockHashCount: u64 = 250;
+	pub const MaximumBlockWeight: u32 = 4 * 1024 * 1024;
+	pub const MaximumBlockLength: u32 = 4 * 1024 * 1024;
+	pub const AvailableBlockRatio: Perbill = Perbill::from_percent(75);
+}
+
+impl system::Trait for Test {
+	type Origin = Origin;
+	type Call = Call;
+	type Index = u64;
+	type BlockNumber = u64;
+	type Hash = H256;
+	type Hashing = BlakeTwo256;
+	type AccountId = u64;
+	type Lookup = IdentityLookup<Self::AccountId>;
+	type Header = Header;
+	type Event = TestEvent;
+	type ModuleToIndex = ();
+	type BlockHashCount = BlockHashCount;
+	type MaximumBlockWeight = MaximumBlockWeight;
+	type MaximumBlockLength = MaximumBlockLength;
+	type AvailableBlockRatio = AvailableBlockRatio;
+	type Version = ();
+	type AccountData = ();
+	type OnNewAccount = ();
+	type OnKilledAccount = ();
+	type DbWeight = ();
+	type BlockExecutionWeight = ();
+	type ExtrinsicBaseWeight = ();
+	type BaseCallFilter = ();
+	type MaximumExtrinsicWeight = MaximumBlockWeight;
+}
+
+impl assets::Trait for Test {
+	type Event = TestEvent;
+	type Balance = u64;
+	type AssetId = u32;
+	type Price = u64;
+	type Cost = u64;
+	type Income = u64;
+	type Convert = u64;
+	type AssetRedeem = ();
+	type FetchConvertPrice = ();
+	type DustHandler = ();
+	type PriceProvider = ();
+	type PricePrecision = ();
+	type MaxPriceUpdates = ();
+	type TransferHandler = ();
+}
+
+parameter_types! {
+	pub const InitPoolSupply: u64 = 1000;
+	pub const MaximumSwapInRatio: u64 = 2;
+	pub const MinimumBalance: u64 = 10;
+	pub const MaximumSwapFee: u64 = 10_000; // 10%
+	pub const MinimumSwapFee: u64 = 1; // 0.0001%
+	pub const FeePrecision: u64 = 100_000;
+	pub const MaximumPriceMovement: u64 = 400_000; // 400%
+	pub const TwapWindow: u64 = 5;
+	pub const ConvertPricePrecision: u64 = 10_000;
+}
+
+impl swap::Trait for Test {
+	type Fee = u64;
+	type Event = TestEvent;
+	type Call = Call;
+	type AssetTrait = Assets;
+	type Balance = u64;
+	type AssetId = u32;
+	type Cost = u64;
+	type Income = u64;
+	type InvariantValue = u64;
+	type PoolWeight = u64;
+	type InitPoolSupply = InitPoolSupply;
+	type MaximumSwapInRatio = MaximumSwapInRatio;
+	type MinimumBalance = MinimumBalance;
+	type MaximumSwapFee = MaximumSwapFee;
+	type MinimumSwapFee = MinimumSwapFee;
+	type FeePrecision = FeePrecision;
+	type MaximumPriceMovement = MaximumPriceMovement;
+	type TwapWindow = TwapWindow;
+	type FetchConvertPrice = ();
+	type ConvertPricePrecision = ConvertPricePrecision;
+}
+
+parameter_types! {
+	pub const PricePrecision: u64 = 10_000;
+	pub const MaxOrdersPerBlock: u32 = 2;
+	pub const MaxOpenOrders: u32 = 4;
+	pub const MaxOrdersPerAccount: u32 = 3;
+}
+
+impl crate::Trait for Test {
+	type Event = TestEvent;
+	type Balance = u64;
+	type AssetId = u32;
+	type Cost = u64;
+	type Income = u64;
+	type AssetTrait = Assets;
+	type PoolId = swap::PoolId;
+	type SwapHandler = Swap;
+	type PricePrecision = PricePrecision;
+	type MaxOrdersPerBlock = MaxOrdersPerBlock;
+	type MaxOpenOrders = MaxOpenOrders;
+	type MaxOrdersPerAccount = MaxOrdersPerAccount;
+}
+
+pub type Orderbook = crate::Module<Test>;
+pub type System = system::Module<Test>;
+pub type Assets = assets::Module<Test>;
+pub type Swap = swap::Module<Test>;
+
+pub(crate) fn run_to_block(n: u64) {
+	while System::block_number() < n {
+		Swap::on_finalize(System::block_number());
+		System::on_finalize(System::block_number());
+		System::set_block_number(System::block_number() + 1);
+		System::on_initialize(System::block_number());
+		Swap::on_initialize(System::block_number());
+		Orderbook::on_initialize(System::block_number());
+	}
+}
+
+pub(crate) fn new_test_ext() -> sp_io::TestExternalities {
+	system::GenesisConfig::default().build_storage::<Test>().unwrap().into()
+}
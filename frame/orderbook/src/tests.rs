@@ -0,0 +1,256 @@
+// Copyright 2020 Keysians Technologies.
+// This file is part of Keysians.
+
+// Keysians is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Keysians is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Keysians.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Tests for the module.
+#![cfg(test)]
+
+use crate::*;
+use crate::mock::*;
+use frame_support::{assert_noop, assert_ok};
+use sp_runtime::DispatchError;
+
+const ALICE: u64 = 1;
+const BOB: u64 = 2;
+const CAROL: u64 = 3;
+const DAVE: u64 = 4;
+
+// a pool of 100_000 DOT and 100_000 KSM, with 0.1% swap fee
+fn setup_pool() -> (TokenSymbol, TokenSymbol) {
+	assert_ok!(Assets::create(Origin::root(), b"aUSD".to_vec(), 18, None)); // let dot start from 1
+	assert_ok!(Assets::create(Origin::root(), b"DOT".to_vec(), 12, None));
+	let dot_type = TokenSymbol::from(Assets::next_asset_id() - 1);
+	assert_ok!(Assets::create(Origin::root(), b"KSM".to_vec(), 12, None));
+	let ksm_type = TokenSymbol::from(Assets::next_asset_id() - 1);
+
+	assert_ok!(Assets::issue(Origin::root(), dot_type, ALICE, 1_000_000));
+	assert_ok!(Assets::issue(Origin::root(), ksm_type, ALICE, 1_000_000));
+	assert_ok!(Assets::issue(Origin::root(), dot_type, BOB, 100_000));
+	assert_ok!(Assets::issue(Origin::root(), ksm_type, CAROL, 10_000));
+	assert_ok!(Assets::issue(Origin::root(), ksm_type, DAVE, 10_000));
+
+	assert_ok!(Swap::create_pool(Origin::signed(ALICE), vec![(dot_type, 100_000, 1), (ksm_type, 100_000, 1)], 100));
+
+	(dot_type, ksm_type)
+}
+
+#[test]
+fn order_should_be_filled_once_limit_price_is_reached() {
+	new_test_ext().execute_with(|| {
+		run_to_block(2);
+
+		let (dot_type, ksm_type) = setup_pool();
+		let pool_id = 0;
+
+		// a KSM is worth 0.999 DOT now, sell 1_000 KSM at 1.1 DOT at least
+		let price = <Swap as SwapHandler<_, _, _, _>>::spot_price(pool_id, ksm_type, dot_type, 10_000).unwrap();
+		assert!(price >= 9_989 && price <= 9_990);
+		assert_ok!(Orderbook::place_order(Origin::signed(CAROL), pool_id, ksm_type, 1_000, dot_type, 11_000, 100));
+		assert_eq!(
+			Orderbook::orders(0),
+			Some(Order {
+				owner: CAROL,
+				pool_id,
+				token_in: ksm_type,
+				token_amount_in: 1_000,
+				token_out: dot_type,
+				limit_price: 11_000,
+				expire_at: 100,
+			})
+		);
+		assert_eq!(Orderbook::open_orders(), vec![0]);
+		assert_eq!(Orderbook::order_count(CAROL), 1);
+		let carol_ksm = <assets::AccountAssets<Test>>::get((ksm_type, CAROL));
+		assert_eq!((carol_ksm.balance, carol_ksm.reserved, carol_ksm.available), (10_000, 1_000, 9_000));
+
+		run_to_block(3);
+		assert_eq!(Orderbook::open_orders(), vec![0]);
+
+		// KSM is bought up
+		assert_ok!(Swap::swap(Origin::signed(BOB), pool_id, dot_type, 20_000, None, ksm_type, None, None));
+		assert!(<Swap as SwapHandler<_, _, _, _>>::spot_price(pool_id, ksm_type, dot_type, 10_000).unwrap() > 11_000);
+
+		run_to_block(4);
+		assert_eq!(Orderbook::orders(0), None);
+		assert!(Orderbook::open_orders().is_empty());
+		assert_eq!(Orderbook::order_count(CAROL), 0);
+		let carol_ksm = <assets::AccountAssets<Test>>::get((ksm_type, CAROL));
+		assert_eq!((carol_ksm.balance, carol_ksm.reserved, carol_ksm.available), (9_000, 0, 9_000));
+		let carol_dot = <assets::AccountAssets<Test>>::get((dot_type, CAROL)).balance;
+		assert!(carol_dot >= 1_100);
+		assert!(System::events().iter().any(|record| {
+			record.event == TestEvent::pallet_orderbook(RawEvent::OrderFilled(0, CAROL, 1_000, carol_dot))
+		}));
+	});
+}
+
+#[test]
+fn place_and_cancel_order_should_work() {
+	new_test_ext().execute_with(|| {
+		run_to_block(2);
+
+		let (dot_type, ksm_type) = setup_pool();
+		let pool_id = 0;
+
+		assert_noop!(
+			Orderbook::place_order(Origin::signed(CAROL), pool_id, ksm_type, 1_000, ksm_type, 11_000, 100),
+			DispatchError::Module { index: 0, error: 0, message: Some("SameTokenOrder") }
+		);
+		assert_noop!(
+			Orderbook::place_order(Origin::signed(CAROL), pool_id, ksm_type, 0, dot_type, 11_000, 100),
+			DispatchError::Module { index: 0, error: 1, message: Some("InvalidOrder") }
+		);
+		assert_noop!(
+			Orderbook::place_order(Origin::signed(CAROL), pool_id, ksm_type, 1_000, dot_type, 11_000, 2),
+			DispatchError::Module { index: 0, error: 2, message: Some("InvalidExpiry") }
+		);
+		assert_noop!(
+			Orderbook::place_order(Origin::signed(CAROL), pool_id, ksm_type, 10_001, dot_type, 11_000, 100),
+			DispatchError::Module { index: 0, error: 3, message: Some("NotEnoughBalance") }
+		);
+
+		for _ in 0..3 {
+			assert_ok!(Orderbook::place_order(Origin::signed(CAROL), pool_id, ksm_type, 1_000, dot_type, 11_000, 100));
+		}
+		assert_noop!(
+			Orderbook::place_order(Origin::signed(CAROL), pool_id, ksm_type, 1_000, dot_type, 11_000, 100),
+			DispatchError::Module { index: 0, error: 4, message: Some("TooManyOrders") }
+		);
+		assert_ok!(Orderbook::place_order(Origin::signed(DAVE), pool_id, ksm_type, 1_000, dot_type, 11_000, 100));
+		assert_noop!(
+			Orderbook::place_order(Origin::signed(DAVE), pool_id, ksm_type, 1_000, dot_type, 11_000, 100),
+			DispatchError::Module { index: 0, error: 4, message: Some("TooManyOrders") }
+		);
+		assert_eq!(Orderbook::open_orders(), vec![0, 1, 2, 3]);
+		assert_eq!(Assets::reserved_balance(*b"orderbok", ksm_type, &CAROL), 3_000);
+
+		assert_noop!(
+			Orderbook::cancel_order(Origin::signed(CAROL), 4),
+			DispatchError::Module { index: 0, error: 5, message: Some("OrderNotExist") }
+		);
+		assert_noop!(
+			Orderbook::cancel_order(Origin::signed(CAROL), 3),
+			DispatchError::Module { index: 0, error: 6, message: Some("NotOrderOwner") }
+		);
+		assert_ok!(Orderbook::cancel_order(Origin::signed(CAROL), 1));
+		assert_eq!(Orderbook::open_orders(), vec![0, 2, 3]);
+		assert_eq!(Orderbook::order_count(CAROL), 2);
+		assert_eq!(Assets::reserved_balance(*b"orderbok", ksm_type, &CAROL), 2_000);
+		assert_eq!(<assets::AccountAssets<Test>>::get((ksm_type, CAROL)).available, 8_000);
+	});
+}
+
+#[test]
+fn orders_should_be_checked_in_turns() {
+	new_test_ext().execute_with(|| {
+		run_to_block(2);
+
+		let (dot_type, ksm_type) = setup_pool();
+		let pool_id = 0;
+
+		// two orders are checked in a block
+		assert_ok!(Orderbook::place_order(Origin::signed(CAROL), pool_id, ksm_type, 1_000, dot_type, 11_000, 4));
+		assert_ok!(Orderbook::place_order(Origin::signed(CAROL), pool_id, ksm_type, 1_000, dot_type, 11_000, 100));
+		assert_ok!(Orderbook::place_order(Origin::signed(DAVE), pool_id, ksm_type, 1_000, dot_type, 5_000, 100));
+		assert_ok!(Orderbook::place_order(Origin::signed(DAVE), pool_id, ksm_type, 1_000, dot_type, 5_000, 100));
+
+		run_to_block(3);
+		assert_eq!(Orderbook::open_orders(), vec![0, 1, 2, 3]);
+		assert_eq!(Orderbook::next_checked_order(), Some(2));
+
+		// orders of dave are filled at last
+		run_to_block(4);
+		assert_eq!(Orderbook::open_orders(), vec![0, 1]);
+		assert_eq!(Orderbook::next_checked_order(), None);
+		assert_eq!(<assets::AccountAssets<Test>>::get((ksm_type, DAVE)).balance, 8_000);
+		assert!(<assets::AccountAssets<Test>>::get((dot_type, DAVE)).balance >= 1_000);
+
+		// the first order of carol expires
+		run_to_block(5);
+		assert_eq!(Orderbook::open_orders(), vec![1]);
+		assert_eq!(Orderbook::orders(0), None);
+		assert_eq!(Orderbook::order_count(CAROL), 1);
+		assert_eq!(Assets::reserved_balance(*b"orderbok", ksm_type, &CAROL), 1_000);
+		assert!(System::events().iter().any(|record| {
+			record.event == TestEvent::pallet_orderbook(RawEvent::OrderExpired(0, CAROL))
+		}));
+	});
+}
+
+#[test]
+fn cancelled_orders_should_be_unlinked() {
+	new_test_ext().execute_with(|| {
+		run_to_block(2);
+
+		let (dot_type, ksm_type) = setup_pool();
+		let pool_id = 0;
+
+		for _ in 0..3 {
+			assert_ok!(Orderbook::place_order(Origin::signed(CAROL), pool_id, ksm_type, 1_000, dot_type, 11_000, 100));
+		}
+		assert_eq!(Orderbook::order_queue(), Some((0, 2)));
+		assert_eq!(Orderbook::order_links(1), (Some(0), Some(2)));
+
+		run_to_block(3);
+		assert_eq!(Orderbook::next_checked_order(), Some(2));
+
+		// checking continues from the order after a cancelled one
+		assert_ok!(Orderbook::cancel_order(Origin::signed(CAROL), 2));
+		assert_eq!(Orderbook::next_checked_order(), None);
+		assert_ok!(Orderbook::cancel_order(Origin::signed(CAROL), 0));
+		assert_eq!(Orderbook::order_queue(), Some((1, 1)));
+		assert_eq!(Orderbook::order_links(1), (None, None));
+		assert_eq!(Orderbook::open_orders(), vec![1]);
+		assert_eq!(Orderbook::open_order_count(), 1);
+
+		assert_ok!(Orderbook::cancel_order(Origin::signed(CAROL), 1));
+		assert_eq!(Orderbook::order_queue(), None);
+		assert_eq!(Orderbook::open_order_count(), 0);
+		assert!(!OrderLinks::contains_key(1));
+	});
+}
+
+#[test]
+fn order_should_not_swap_more_than_maximum_ratio() {
+	new_test_ext().execute_with(|| {
+		run_to_block(2);
+
+		let (dot_type, ksm_type) = setup_pool();
+		let pool_id = 0;
+
+		// a swap pays at most half of the 100_000 KSM in the pool, so the order can never be filled
+		assert_eq!(<Swap as SwapHandler<_, _, _, _>>::max_amount_in(pool_id, ksm_type), Some(50_000));
+		assert_noop!(
+			Orderbook::place_order(Origin::signed(ALICE), pool_id, ksm_type, 50_001, dot_type, 5_000, 100),
+			DispatchError::Module { index: 0, error: 8, message: Some("ExceedMaximumSwapInRatio") }
+		);
+		// the pool doesn't exist
+		assert_noop!(
+			Orderbook::place_order(Origin::signed(ALICE), 1, ksm_type, 1_000, dot_type, 5_000, 100),
+			DispatchError::Module { index: 0, error: 7, message: Some("TokenNotInPool") }
+		);
+
+		// more than half of the ksm of alice is fine, only the pool balance counts
+		assert_ok!(Orderbook::place_order(Origin::signed(ALICE), pool_id, ksm_type, 50_000, dot_type, 5_000, 100));
+		run_to_block(3);
+
+		// 100_000 * (1 - 100_000 / (100_000 + 50_000 * (1 - 0.1%))) = 33_311.1...
+		assert_eq!(Orderbook::open_orders(), Vec::<OrderId>::new());
+		assert_eq!(Assets::reserved_balance(*b"orderbok", ksm_type, &ALICE), 0);
+		assert_eq!(<assets::AccountAssets<Test>>::get((ksm_type, ALICE)).balance, 850_000);
+		let alice_dot = <assets::AccountAssets<Test>>::get((dot_type, ALICE)).balance;
+		assert!(alice_dot >= 900_000 + 33_310 && alice_dot <= 900_000 + 33_312);
+	});
+}
@@ -25,14 +25,17 @@ use core::ops::Div;
 use frame_support::storage::{IterableStorageDoubleMap, StorageMap};
 use frame_support::traits::Get;
 use frame_support::{debug, decl_error, decl_event, decl_module, decl_storage, ensure, Parameter};
-use frame_system::{ensure_root, ensure_signed};
-use node_primitives::{AssetTrait, BridgeAssetTo, RewardHandler, TokenSymbol};
+use frame_system::{self as system, ensure_root, ensure_signed};
+use node_primitives::{AssetTrait, BridgeAssetTo, LockIdentifier, RewardHandler, TokenSymbol};
 use sp_runtime::traits::{AtLeast32Bit, Member, Saturating, Zero};
 use sp_runtime::RuntimeDebug;
 use sp_std::prelude::*;
 
 pub type ValidatorAddress = Vec<u8>;
 
+/// Identifier of the reserve holding deposits of proxy validators.
+const PROXY_VALIDATOR_ID: LockIdentifier = *b"proxyval";
+
 #[derive(Encode, Decode, Clone, Default, PartialEq, Eq, RuntimeDebug)]
 pub struct AssetConfig<BlockNumber, Balance> {
     redeem_duration: BlockNumber,
@@ -71,9 +74,9 @@ impl<Balance: Default, BlockNumber: Default> ProxyValidatorRegister<Balance, Blo
     }
 }
 
-pub trait Trait: frame_system::Trait {
+pub trait Trait: system::Trait {
     /// event
-    type Event: From<Event<Self>> + Into<<Self as frame_system::Trait>::Event>;
+    type Event: From<Event<Self>> + Into<<Self as system::Trait>::Event>;
     /// The units in which we record balances.
     type Balance: Member + Parameter + AtLeast32Bit + Default + Copy + From<Self::BlockNumber>;
     /// The arithmetic type of asset identifier.
@@ -101,8 +104,8 @@ pub trait Trait: frame_system::Trait {
 decl_event! {
     pub enum Event<T> where
         <T as Trait>::Balance,
-        <T as frame_system::Trait>::AccountId,
-        <T as frame_system::Trait>::BlockNumber,
+        <T as system::Trait>::AccountId,
+        <T as system::Trait>::BlockNumber,
     {
         /// A new asset config has been set.
         AssetConfigSet(TokenSymbol, AssetConfig<BlockNumber, Balance>),
@@ -161,8 +164,6 @@ decl_storage! {
         /// The proxy validators registered from cross chain.
         ProxyValidators get(fn validators): double_map hasher(blake2_128_concat) TokenSymbol, hasher(blake2_128_concat) T::AccountId
             => ProxyValidatorRegister<T::Balance, T::BlockNumber>;
-        /// The locked amount of asset of account for staking.
-        LockedBalances get(fn locked_balances): map hasher(blake2_128_concat) T::AccountId => T::Balance;
     }
 }
 
@@ -379,20 +380,9 @@ impl<T: Trait> Module<T> {
         token_symbol: TokenSymbol,
         amount: T::Balance,
     ) -> Result<(), Error<T>> {
-        // check if has enough balance
-        let account_asset = T::AssetTrait::get_account_asset(token_symbol, &account_id);
-        ensure!(
-            account_asset.balance >= amount,
-            Error::<T>::FreeBalanceNotEnough
-        );
-
-        // lock asset to this module
-        LockedBalances::<T>::mutate(&account_id, |locked_balance| {
-            *locked_balance = locked_balance.saturating_add(amount)
-        });
-
-        // destroy asset in assets module
-        T::AssetTrait::asset_destroy(token_symbol, &account_id, amount);
+        // reserve asset to this module, fail if not enough free balance
+        T::AssetTrait::reserve(PROXY_VALIDATOR_ID, token_symbol, &account_id, amount)
+            .map_err(|_| Error::<T>::FreeBalanceNotEnough)?;
 
         Ok(())
     }
@@ -404,21 +394,12 @@ impl<T: Trait> Module<T> {
     ) -> Result<(), Error<T>> {
         // check if has enough locked_balance
         ensure!(
-            LockedBalances::<T>::contains_key(&account_id),
+            T::AssetTrait::reserved_balance(PROXY_VALIDATOR_ID, token_symbol, &account_id) >= amount,
             Error::<T>::LockedBalanceNotEnough
         );
-        ensure!(
-            LockedBalances::<T>::get(&account_id) >= amount,
-            Error::<T>::LockedBalanceNotEnough
-        );
-
-        // unlock asset to this module
-        LockedBalances::<T>::mutate(&account_id, |locked_balance| {
-            *locked_balance = locked_balance.saturating_sub(amount)
-        });
 
-        // issue asset in assets module
-        T::AssetTrait::asset_issue(token_symbol, &account_id, amount);
+        // unreserve asset from this module
+        T::AssetTrait::unreserve(PROXY_VALIDATOR_ID, token_symbol, &account_id, amount);
 
         Ok(())
     }
@@ -453,6 +434,8 @@ impl<T: Trait> Module<T> {
             }
 
             if reward > Zero::zero() {
+                // the deposit paid for reward leaves the validator
+                T::AssetTrait::slash_reserved(PROXY_VALIDATOR_ID, token_symbol, &account_id, reward);
                 T::RewardHandler::send_reward(token_symbol, reward);
             }
 
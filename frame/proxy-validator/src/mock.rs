@@ -96,6 +96,11 @@ impl assets::Trait for Test {
 	type Convert = u64;
 	type AssetRedeem = ();
 	type FetchConvertPrice = ();
+	type DustHandler = ();
+	type PriceProvider = ();
+	type PricePrecision = ();
+	type MaxPriceUpdates = ();
+	type TransferHandler = ();
 }
 
 impl crate::Trait for Test {
@@ -32,7 +32,7 @@ fn set_global_asset(token_symbol: TokenSymbol) {
 }
 
 fn asset_issue(account_id: u64, symbol: Vec<u8>, token_symbol: TokenSymbol, amount: u64) {
-	assert_ok!(Assets::create(Origin::root(), symbol, 18));
+	assert_ok!(Assets::create(Origin::root(), symbol, 18, None));
 	assert_ok!(Assets::issue(Origin::root(), token_symbol, account_id, amount));
 }
 
@@ -361,9 +361,9 @@ fn deposit_should_work() {
 		let origin = Origin::signed(origin_id);
 		let precision = 8;
 
-		assert_ok!(Assets::create(Origin::root(), b"aUSD".to_vec(), 18)); // let dot start from 1
+		assert_ok!(Assets::create(Origin::root(), b"aUSD".to_vec(), 18, None)); // let dot start from 1
 
-		assert_ok!(Assets::create(Origin::root(), b"DOT".to_vec(), precision));
+		assert_ok!(Assets::create(Origin::root(), b"DOT".to_vec(), precision, None));
 		let dot_id = Assets::next_asset_id() - 1;
 		let dot_type = TokenSymbol::from(dot_id);
 		assert_ok!(Assets::issue(Origin::root(), dot_type, origin_id, 10000));
@@ -378,13 +378,15 @@ fn deposit_should_work() {
 		assert_ok!(ProxyValidator::deposit(origin.clone(), dot_type, deposit_amount));
 		let validator = ProxyValidator::validators(dot_type, origin_id);
 		assert_eq!(validator.deposit, 100);
-		assert_eq!(Assets::token_details(dot_type), Token::new(b"DOT".to_vec(), 8, 9900));
+		assert_eq!(Assets::account_assets((dot_type, origin_id)).reserved, 100);
+		assert_eq!(Assets::account_assets((dot_type, origin_id)).available, 9900);
 
 		let deposit_amount = 200;
 		assert_ok!(ProxyValidator::deposit(origin, dot_type, deposit_amount));
 		let validator = ProxyValidator::validators(dot_type, origin_id);
 		assert_eq!(validator.deposit, 300);
-		assert_eq!(Assets::token_details(dot_type), Token::new(b"DOT".to_vec(), 8, 9700));
+		assert_eq!(Assets::account_assets((dot_type, origin_id)).reserved, 300);
+		assert_eq!(Assets::token_details(dot_type), Token::new(b"DOT".to_vec(), 8, 10000));
 	});
 }
 
@@ -397,8 +399,8 @@ fn deposit_not_enough_free_balance_should_error() {
 		let origin = Origin::signed(origin_id);
 		let precision = 8;
 
-		assert_ok!(Assets::create(Origin::root(), b"aUSD".to_vec(), 18)); // let dot start from 1
-		assert_ok!(Assets::create(Origin::root(), b"DOT".to_vec(), precision));
+		assert_ok!(Assets::create(Origin::root(), b"aUSD".to_vec(), 18, None)); // let dot start from 1
+		assert_ok!(Assets::create(Origin::root(), b"DOT".to_vec(), precision, None));
 
 		let dot_id = Assets::next_asset_id() - 1;
 		let dot_type = TokenSymbol::from(dot_id);
@@ -443,8 +445,8 @@ fn withdraw_should_ok() {
 		let origin = Origin::signed(origin_id);
 		let precision = 8;
 
-		assert_ok!(Assets::create(Origin::root(), b"aUSD".to_vec(), 18)); // let dot start from 1
-		assert_ok!(Assets::create(Origin::root(), b"DOT".to_vec(), precision));
+		assert_ok!(Assets::create(Origin::root(), b"aUSD".to_vec(), 18, None)); // let dot start from 1
+		assert_ok!(Assets::create(Origin::root(), b"DOT".to_vec(), precision, None));
 
 		let dot_id = Assets::next_asset_id() - 1;
 		let dot_type = TokenSymbol::from(dot_id);
@@ -464,7 +466,8 @@ fn withdraw_should_ok() {
 		assert_ok!(ProxyValidator::withdraw(origin, dot_type, withdraw_amount));
 		let validator = ProxyValidator::validators(dot_type, origin_id);
 		assert_eq!(validator.deposit, 300);
-		assert_eq!(Assets::token_details(dot_type), Token::new(b"DOT".to_vec(), 8, 9700));
+		assert_eq!(Assets::account_assets((dot_type, origin_id)).reserved, 300);
+		assert_eq!(Assets::account_assets((dot_type, origin_id)).available, 9700);
 	});
 }
 
@@ -477,8 +480,8 @@ fn withdraw_not_enough_locked_balance_should_error() {
 		let origin = Origin::signed(origin_id);
 		let precision = 8;
 
-		assert_ok!(Assets::create(Origin::root(), b"aUSD".to_vec(), 18)); // let dot start from 1
-		assert_ok!(Assets::create(Origin::root(), b"DOT".to_vec(), precision));
+		assert_ok!(Assets::create(Origin::root(), b"aUSD".to_vec(), 18, None)); // let dot start from 1
+		assert_ok!(Assets::create(Origin::root(), b"DOT".to_vec(), precision, None));
 
 		let dot_id = Assets::next_asset_id() - 1;
 		let dot_type = TokenSymbol::from(dot_id);
@@ -543,5 +546,7 @@ fn validator_deduct_should_ok() {
 		let validator = ProxyValidator::validators(token_symbol, origin_id);
 		assert_eq!(validator.last_block, 3599);
 		assert_eq!(validator.deposit, 99_999_208_220_000);
+		// deducted reward has been slashed from the reserve
+		assert_eq!(Assets::account_assets((token_symbol, origin_id)).reserved, validator.deposit);
 	});
 }
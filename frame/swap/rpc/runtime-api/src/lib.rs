@@ -0,0 +1,174 @@
+// Copyright 2020 Keysians Technologies.
+// This file is part of Keysians.
+
+// Keysians is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Keysians is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Keysians.  If not, see <http://www.gnu.org/licenses/>.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+use alloc::vec::Vec;
+use codec::{Codec, Decode, Encode};
+#[cfg(feature = "std")]
+use serde::{Deserialize, Serialize};
+use sp_api::decl_runtime_apis;
+
+/// Prices and price impact are multiplied by this.
+const PRICE_PRECISION: u128 = 1_000_000_000_000;
+
+/// A route for swapping between two tokens, the amount is serialized as a string so that u128
+/// values are not truncated.
+#[derive(Encode, Decode, Default, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(Debug, Serialize, Deserialize))]
+#[cfg_attr(feature = "std", serde(rename_all = "camelCase"))]
+#[cfg_attr(feature = "std", serde(bound(serialize = "PoolId: Serialize, TokenSymbol: Serialize, Balance: std::fmt::Display")))]
+#[cfg_attr(feature = "std", serde(bound(deserialize = "PoolId: Deserialize<'de>, TokenSymbol: Deserialize<'de>, Balance: std::str::FromStr")))]
+pub struct SwapRoute<PoolId, TokenSymbol, Balance> {
+	/// Hops of the route, (pool to swap in, token out of the pool).
+	pub path: Vec<(PoolId, TokenSymbol)>,
+	/// Amount of the last token out of the route.
+	#[cfg_attr(feature = "std", serde(with = "serde_balance"))]
+	pub amount_out: Balance,
+}
+
+/// Quote of a hypothetical swap, prices are multiplied by `10^12` and include the swap fee, all
+/// numbers are serialized as strings so that u128 values are not truncated.
+#[derive(Encode, Decode, Default, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(Debug, Serialize, Deserialize))]
+#[cfg_attr(feature = "std", serde(rename_all = "camelCase"))]
+#[cfg_attr(feature = "std", serde(bound(serialize = "Balance: std::fmt::Display")))]
+#[cfg_attr(feature = "std", serde(bound(deserialize = "Balance: std::str::FromStr")))]
+pub struct SwapQuote<Balance> {
+	/// Amount of token out of the pool.
+	#[cfg_attr(feature = "std", serde(with = "serde_balance"))]
+	pub amount_out: Balance,
+	/// Spot price of the token out in the token in before the swap.
+	#[cfg_attr(feature = "std", serde(with = "serde_balance"))]
+	pub spot_price_before: u128,
+	/// Spot price of the token out in the token in after the swap.
+	#[cfg_attr(feature = "std", serde(with = "serde_balance"))]
+	pub spot_price_after: u128,
+	/// Swap fee charged from the token in.
+	#[cfg_attr(feature = "std", serde(with = "serde_balance"))]
+	pub fee: Balance,
+	/// How much the spot price rises after the swap, `10^12` is 100%.
+	#[cfg_attr(feature = "std", serde(with = "serde_balance"))]
+	pub price_impact: u128,
+}
+
+impl<Balance> SwapQuote<Balance> {
+	pub fn new(amount_out: Balance, fee: Balance, spot_price_before: u128, spot_price_after: u128) -> Self {
+		let price_impact = if spot_price_before == 0 {
+			0
+		} else {
+			spot_price_after.saturating_sub(spot_price_before).saturating_mul(PRICE_PRECISION) / spot_price_before
+		};
+
+		Self {
+			amount_out,
+			spot_price_before,
+			spot_price_after,
+			fee,
+			price_impact,
+		}
+	}
+}
+
+/// A broken invariant of a pool found by the integrity check, amounts are serialized as strings
+/// so that u128 values are not truncated.
+#[derive(Encode, Decode, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(Debug, Serialize, Deserialize))]
+#[cfg_attr(feature = "std", serde(rename_all = "camelCase"))]
+#[cfg_attr(feature = "std", serde(bound(serialize = "TokenSymbol: Serialize, Balance: std::fmt::Display")))]
+#[cfg_attr(feature = "std", serde(bound(deserialize = "TokenSymbol: Deserialize<'de>, Balance: std::str::FromStr")))]
+pub enum IntegrityViolation<TokenSymbol, Balance> {
+	/// A token of the pool doesn't exist in the assets.
+	TokenNotExist(TokenSymbol),
+	/// Total weight of the pool is not the sum of the token weights, (total weight, sum).
+	TotalWeightMismatch(
+		#[cfg_attr(feature = "std", serde(with = "serde_balance"))] Balance,
+		#[cfg_attr(feature = "std", serde(with = "serde_balance"))] Balance,
+	),
+	/// The pool has no pool token, or the pool token doesn't stand for the pool.
+	PoolTokenMismatch,
+	/// Pool supply is not the total issuance of the pool token, (pool supply, issuance).
+	PoolSupplyMismatch(
+		#[cfg_attr(feature = "std", serde(with = "serde_balance"))] Balance,
+		#[cfg_attr(feature = "std", serde(with = "serde_balance"))] Balance,
+	),
+	/// Liquidity mining shares don't add up to the pool supply, (total shares, pool supply).
+	MiningSharesMismatch(
+		#[cfg_attr(feature = "std", serde(with = "serde_balance"))] Balance,
+		#[cfg_attr(feature = "std", serde(with = "serde_balance"))] Balance,
+	),
+	/// Pool balance of a token is not held by the pool account, (token, pool balance, balance
+	/// of the pool account).
+	PoolBalanceMismatch(
+		TokenSymbol,
+		#[cfg_attr(feature = "std", serde(with = "serde_balance"))] Balance,
+		#[cfg_attr(feature = "std", serde(with = "serde_balance"))] Balance,
+	),
+}
+
+#[cfg(feature = "std")]
+mod serde_balance {
+	use serde::{Deserialize, Deserializer, Serializer};
+
+	pub fn serialize<S: Serializer, T: std::fmt::Display>(t: &T, serializer: S) -> Result<S::Ok, S::Error> {
+		serializer.serialize_str(&t.to_string())
+	}
+
+	pub fn deserialize<'de, D: Deserializer<'de>, T: std::str::FromStr>(deserializer: D) -> Result<T, D::Error> {
+		let s = String::deserialize(deserializer)?;
+		s.parse::<T>().map_err(|_| serde::de::Error::custom("Parse from string failed"))
+	}
+}
+
+decl_runtime_apis! {
+	pub trait SwapApi<PoolId, TokenSymbol, Balance> where
+		PoolId: Codec,
+		TokenSymbol: Codec,
+		Balance: Codec,
+	{
+		/// get the route with the most amount out for swapping from one token to another
+		fn best_route(
+			token_in: TokenSymbol,
+			token_amount_in: Balance,
+			token_out: TokenSymbol
+		) -> Option<SwapRoute<PoolId, TokenSymbol, Balance>>;
+
+		/// get time weighted average price of the base token in the quote token of a pool
+		/// over the last `window` blocks, a price of 1 is `10^12`
+		fn twap(pool_id: PoolId, base: TokenSymbol, quote: TokenSymbol, window: u32) -> Option<u128>;
+
+		/// get amount out, spot price before and after, fee charged and price impact for swapping
+		/// `token_amount_in` of `token_in` for `token_out` in a pool
+		fn quote_swap(
+			pool_id: PoolId,
+			token_in: TokenSymbol,
+			token_amount_in: Balance,
+			token_out: TokenSymbol
+		) -> Option<SwapQuote<Balance>>;
+
+		/// get how many pool tokens will be issued for adding a single token to a pool
+		fn quote_add_single_liquidity(pool_id: PoolId, token_symbol: TokenSymbol, token_amount_in: Balance) -> Option<Balance>;
+
+		/// get how many tokens, including the share of rewards, will be paid for removing pool
+		/// tokens from a pool as a single token
+		fn quote_remove_single_asset_liquidity(pool_id: PoolId, token_symbol: TokenSymbol, pool_token_in: Balance) -> Option<Balance>;
+
+		/// check the invariants of all the pools, get the broken ones of each pool
+		fn check_integrity() -> Vec<(PoolId, IntegrityViolation<TokenSymbol, Balance>)>;
+	}
+}
@@ -0,0 +1,229 @@
+// Copyright 2020 Keysians Technologies.
+// This file is part of Keysians.
+
+// Keysians is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Keysians is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Keysians.  If not, see <http://www.gnu.org/licenses/>.
+
+use codec::Codec;
+use jsonrpc_derive::rpc;
+use jsonrpc_core::{Error as RpcError, ErrorCode, Result as JsonRpcResult};
+use std::sync::Arc;
+use std::marker::PhantomData;
+use sp_api::ProvideRuntimeApi;
+use sp_blockchain::HeaderBackend;
+use sp_runtime::{generic::BlockId, traits::Block as BlockT};
+pub use self::gen_client::Client as SwapClient;
+pub use swap_rpc_runtime_api::{self as runtime_api, IntegrityViolation, SwapApi as SwapRuntimeApi, SwapQuote, SwapRoute};
+
+#[derive(Clone, Debug)]
+pub struct Swap<C, Block> {
+	client: Arc<C>,
+	_marker: PhantomData<Block>
+}
+
+impl<C, Block> Swap<C, Block> {
+	pub fn new(client: Arc<C>) -> Self {
+		Self {
+			client,
+			_marker: PhantomData
+		}
+	}
+}
+
+#[rpc]
+pub trait SwapApi<BlockHash, PoolId, TokenSymbol, Balance, Route, Quote, Violation> {
+	/// rpc method search the route with the most amount out for swapping from one token to another
+	/// useage: curl -H "Content-Type: application/json" -d '{"id":1, "jsonrpc":"2.0", "method": "swap_getBestRoute", "params": [2, 1000000, 0]}' http://localhost:9933/
+	#[rpc(name = "swap_getBestRoute")]
+	fn best_route(
+		&self,
+		token_in: TokenSymbol,
+		token_amount_in: Balance,
+		token_out: TokenSymbol,
+		at: Option<BlockHash>
+	) -> JsonRpcResult<Option<Route>>;
+
+	/// rpc method get time weighted average price of the base token in the quote token of a pool,
+	/// a price of 1 is 10^12, as a string
+	/// useage: curl -H "Content-Type: application/json" -d '{"id":1, "jsonrpc":"2.0", "method": "swap_getTwap", "params": [0, 2, 1, 600]}' http://localhost:9933/
+	#[rpc(name = "swap_getTwap")]
+	fn twap(
+		&self,
+		pool_id: PoolId,
+		base: TokenSymbol,
+		quote: TokenSymbol,
+		window: u32,
+		at: Option<BlockHash>
+	) -> JsonRpcResult<Option<String>>;
+
+	/// rpc method get amount out, spot price before and after, fee charged and price impact for
+	/// swapping tokens in a pool, prices are multiplied by 10^12
+	/// useage: curl -H "Content-Type: application/json" -d '{"id":1, "jsonrpc":"2.0", "method": "swap_quoteSwap", "params": [0, 2, 1000000, 1]}' http://localhost:9933/
+	#[rpc(name = "swap_quoteSwap")]
+	fn quote_swap(
+		&self,
+		pool_id: PoolId,
+		token_in: TokenSymbol,
+		token_amount_in: Balance,
+		token_out: TokenSymbol,
+		at: Option<BlockHash>
+	) -> JsonRpcResult<Option<Quote>>;
+
+	/// rpc method get how many pool tokens will be issued for adding a single token to a pool, as a string
+	/// useage: curl -H "Content-Type: application/json" -d '{"id":1, "jsonrpc":"2.0", "method": "swap_quoteAddSingleLiquidity", "params": [0, 2, 1000000]}' http://localhost:9933/
+	#[rpc(name = "swap_quoteAddSingleLiquidity")]
+	fn quote_add_single_liquidity(
+		&self,
+		pool_id: PoolId,
+		token_symbol: TokenSymbol,
+		token_amount_in: Balance,
+		at: Option<BlockHash>
+	) -> JsonRpcResult<Option<String>>;
+
+	/// rpc method get how many tokens will be paid for removing pool tokens from a pool as a single token, as a string
+	/// useage: curl -H "Content-Type: application/json" -d '{"id":1, "jsonrpc":"2.0", "method": "swap_quoteRemoveSingleAssetLiquidity", "params": [0, 2, 1000000]}' http://localhost:9933/
+	#[rpc(name = "swap_quoteRemoveSingleAssetLiquidity")]
+	fn quote_remove_single_asset_liquidity(
+		&self,
+		pool_id: PoolId,
+		token_symbol: TokenSymbol,
+		pool_token_in: Balance,
+		at: Option<BlockHash>
+	) -> JsonRpcResult<Option<String>>;
+
+	/// rpc method check the invariants of all the pools, get the broken ones of each pool, nothing is broken if it's empty
+	/// useage: curl -H "Content-Type: application/json" -d '{"id":1, "jsonrpc":"2.0", "method": "swap_checkIntegrity", "params": []}' http://localhost:9933/
+	#[rpc(name = "swap_checkIntegrity")]
+	fn check_integrity(&self, at: Option<BlockHash>) -> JsonRpcResult<Vec<(PoolId, Violation)>>;
+}
+
+impl<C, Block, PoolId, TokenSymbol, Balance>
+	SwapApi<
+		<Block as BlockT>::Hash,
+		PoolId,
+		TokenSymbol,
+		Balance,
+		SwapRoute<PoolId, TokenSymbol, Balance>,
+		SwapQuote<Balance>,
+		IntegrityViolation<TokenSymbol, Balance>,
+	>
+	for Swap<C, Block>
+where
+	Block: BlockT,
+	C: Send + Sync + 'static + ProvideRuntimeApi<Block> + HeaderBackend<Block>,
+	C::Api: SwapRuntimeApi<Block, PoolId, TokenSymbol, Balance>,
+	PoolId: Codec,
+	TokenSymbol: Codec,
+	Balance: Codec + ToString,
+{
+	fn best_route(
+		&self,
+		token_in: TokenSymbol,
+		token_amount_in: Balance,
+		token_out: TokenSymbol,
+		at: Option<<Block as BlockT>::Hash>
+	) -> JsonRpcResult<Option<SwapRoute<PoolId, TokenSymbol, Balance>>> {
+		let swap_rpc_api = self.client.runtime_api();
+		let at = BlockId::<Block>::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+
+		swap_rpc_api.best_route(&at, token_in, token_amount_in, token_out).map_err(|e| RpcError {
+			code: ErrorCode::InternalError,
+			message: "Failed to search a route for you requested tokens.".to_owned(),
+			data: Some(format!("{:?}", e).into()),
+		})
+	}
+
+	fn twap(
+		&self,
+		pool_id: PoolId,
+		base: TokenSymbol,
+		quote: TokenSymbol,
+		window: u32,
+		at: Option<<Block as BlockT>::Hash>
+	) -> JsonRpcResult<Option<String>> {
+		let swap_rpc_api = self.client.runtime_api();
+		let at = BlockId::<Block>::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+
+		swap_rpc_api.twap(&at, pool_id, base, quote, window).map(|price| price.map(|p| p.to_string())).map_err(|e| RpcError {
+			code: ErrorCode::InternalError,
+			message: "Failed to get average price for you requested pair.".to_owned(),
+			data: Some(format!("{:?}", e).into()),
+		})
+	}
+
+	fn quote_swap(
+		&self,
+		pool_id: PoolId,
+		token_in: TokenSymbol,
+		token_amount_in: Balance,
+		token_out: TokenSymbol,
+		at: Option<<Block as BlockT>::Hash>
+	) -> JsonRpcResult<Option<SwapQuote<Balance>>> {
+		let swap_rpc_api = self.client.runtime_api();
+		let at = BlockId::<Block>::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+
+		swap_rpc_api.quote_swap(&at, pool_id, token_in, token_amount_in, token_out).map_err(|e| RpcError {
+			code: ErrorCode::InternalError,
+			message: "Failed to quote the swap for you requested tokens.".to_owned(),
+			data: Some(format!("{:?}", e).into()),
+		})
+	}
+
+	fn quote_add_single_liquidity(
+		&self,
+		pool_id: PoolId,
+		token_symbol: TokenSymbol,
+		token_amount_in: Balance,
+		at: Option<<Block as BlockT>::Hash>
+	) -> JsonRpcResult<Option<String>> {
+		let swap_rpc_api = self.client.runtime_api();
+		let at = BlockId::<Block>::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+
+		swap_rpc_api.quote_add_single_liquidity(&at, pool_id, token_symbol, token_amount_in).map(|amount| amount.map(|a| a.to_string())).map_err(|e| RpcError {
+			code: ErrorCode::InternalError,
+			message: "Failed to quote adding liquidity for you requested token.".to_owned(),
+			data: Some(format!("{:?}", e).into()),
+		})
+	}
+
+	fn quote_remove_single_asset_liquidity(
+		&self,
+		pool_id: PoolId,
+		token_symbol: TokenSymbol,
+		pool_token_in: Balance,
+		at: Option<<Block as BlockT>::Hash>
+	) -> JsonRpcResult<Option<String>> {
+		let swap_rpc_api = self.client.runtime_api();
+		let at = BlockId::<Block>::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+
+		swap_rpc_api.quote_remove_single_asset_liquidity(&at, pool_id, token_symbol, pool_token_in).map(|amount| amount.map(|a| a.to_string())).map_err(|e| RpcError {
+			code: ErrorCode::InternalError,
+			message: "Failed to quote removing liquidity for you requested token.".to_owned(),
+			data: Some(format!("{:?}", e).into()),
+		})
+	}
+
+	fn check_integrity(
+		&self,
+		at: Option<<Block as BlockT>::Hash>
+	) -> JsonRpcResult<Vec<(PoolId, IntegrityViolation<TokenSymbol, Balance>)>> {
+		let swap_rpc_api = self.client.runtime_api();
+		let at = BlockId::<Block>::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+
+		swap_rpc_api.check_integrity(&at).map_err(|e| RpcError {
+			code: ErrorCode::InternalError,
+			message: "Failed to check the integrity of the pools.".to_owned(),
+			data: Some(format!("{:?}", e).into()),
+		})
+	}
+}
@@ -21,7 +21,8 @@
 
 extern crate alloc;
 
-use alloc::vec::Vec;
+use alloc::{boxed::Box, vec::Vec};
+use codec::{Decode, Encode};
 use core::convert::{From, Into, TryInto};
 use core::ops::Div;
 use fixed_point::{
@@ -32,19 +33,222 @@ use fixed_point::{
 };
 use frame_support::traits::Get;
 use frame_support::{
-    decl_error, decl_event, decl_module, decl_storage, dispatch::DispatchResult, ensure, Parameter,
-    StorageValue,
+    decl_error, decl_event, decl_module, decl_storage,
+    dispatch::{DispatchError, DispatchResult},
+    ensure, transactional,
+    weights::{GetDispatchInfo, PostDispatchInfo, Weight},
+    IterableStorageMap, Parameter, StorageValue,
+};
+use frame_system::{self as system, ensure_root, ensure_signed};
+use node_primitives::{
+    AssetTrait, FetchConvertPrice, PriceProvider, SwapHandler, TokenSymbol, TransferHandler,
+};
+use sp_runtime::{
+    helpers_128bit::multiply_by_rational,
+    traits::{
+        AtLeast32Bit, BlakeTwo256, Bounded, Dispatchable, Hash, MaybeSerializeDeserialize, Member, One, SaturatedConversion,
+        Saturating, Zero,
+    },
+    RuntimeDebug,
 };
-use frame_system::ensure_signed;
-use node_primitives::{AssetTrait, TokenSymbol};
-use sp_runtime::traits::{AtLeast32Bit, MaybeSerializeDeserialize, Member, Saturating, Zero};
 
+mod migrations;
 mod mock;
 mod tests;
 
-pub trait Trait: frame_system::Trait {
+/// Identifier of a swap pool.
+pub type PoolId = u32;
+
+/// Maximum number of tokens in a pool.
+const MAX_POOL_TOKENS: usize = 8;
+
+/// Maximum number of hops in a routed swap.
+const MAX_ROUTE_HOPS: usize = 3;
+
+/// A hop of a routed swap, (pool to swap in, token out of the pool).
+pub type Hop = (PoolId, TokenSymbol);
+
+/// Maximum number of token pairs whose prices are recorded.
+const MAX_ORACLE_PAIRS: usize = 16;
+
+/// Prices recorded by the oracle are multiplied by it.
+pub const PRICE_PRECISION: u128 = 1_000_000_000_000;
+
+/// A token pair whose price is recorded, (pool, base token, quote token).
+pub type OraclePair = (PoolId, TokenSymbol, TokenSymbol);
+
+/// Maximum number of pools whose weights are ramping at the same time.
+const MAX_WEIGHT_RAMPS: usize = 16;
+
+/// Maximum number of tokens emitted to the liquidity providers of a pool.
+const MAX_MINING_REWARD_TOKENS: usize = 4;
+
+/// Rewards per share of liquidity mining are multiplied by it.
+const REWARD_PRECISION: u128 = 1_000_000_000_000;
+
+/// Maximum amplification coefficient of a stable swap pool.
+const MAX_AMPLIFICATION: u32 = 1_000_000;
+
+/// Maximum iterations of the Newton's method in the stable swap math.
+const MAX_STABLE_SWAP_ITERATIONS: usize = 255;
+
+/// Precision of the pool tokens standing for the shares of the pools.
+const POOL_TOKEN_PRECISION: u16 = 12;
+
+/// Prefix of the accounts holding the balances of the pools.
+const POOL_ACCOUNT_PREFIX: &[u8] = b"keysians/swap/pool";
+
+/// Storage layout versions of the pallet, a new release comes with its migration in `migrations`.
+#[derive(Encode, Decode, Clone, Copy, PartialEq, Eq, RuntimeDebug)]
+pub enum Releases {
+    /// A single pool is kept in `GlobalPool`, its shares are recorded in `UserPool` and
+    /// `UserSinglePool` by the providers.
+    V1_0_0,
+    /// Pools are kept in `Pools`, and their shares are held as pool tokens in the assets.
+    V2_0_0,
+}
+
+impl Default for Releases {
+    fn default() -> Self {
+        Releases::V1_0_0
+    }
+}
+
+/// A broken invariant of a pool, found by `Module::check_integrity`.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug)]
+pub enum IntegrityError<Balance, PoolWeight> {
+    /// A token of the pool doesn't exist in the assets.
+    TokenNotExist(TokenSymbol),
+    /// Total weight of the pool is not the sum of the token weights, (total weight, sum).
+    TotalWeightMismatch(PoolWeight, PoolWeight),
+    /// The pool has no pool token, or the pool token doesn't stand for the pool.
+    PoolTokenMismatch,
+    /// Pool supply is not the total issuance of the pool token, (pool supply, issuance).
+    PoolSupplyMismatch(Balance, Balance),
+    /// Liquidity mining shares don't add up to the pool supply, (total shares, pool supply).
+    MiningSharesMismatch(Balance, Balance),
+    /// Pool balance of a token is not held by the pool account, (token, pool balance, balance
+    /// of the pool account).
+    PoolBalanceMismatch(TokenSymbol, Balance, Balance),
+}
+
+type IntegrityErrorOf<T> = IntegrityError<<T as Trait>::Balance, <T as Trait>::PoolWeight>;
+
+type MiningPoolOf<T> = MiningPool<<T as system::Trait>::BlockNumber, <T as Trait>::Balance>;
+
+type PoolOf<T> = Pool<
+    <T as system::Trait>::AccountId,
+    <T as Trait>::Balance,
+    <T as Trait>::PoolWeight,
+    <T as Trait>::Fee,
+>;
+
+/// A weighted pool of tokens.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug)]
+pub struct Pool<AccountId, Balance, PoolWeight, Fee> {
+    /// The account which created the pool.
+    pub owner: AccountId,
+    /// Tokens in the pool, (token, balance, weight).
+    pub tokens: Vec<(TokenSymbol, Balance, PoolWeight)>,
+    /// Sum of the weights of all tokens.
+    pub total_weight: PoolWeight,
+    /// Fee charged on swaps.
+    pub swap_fee: Fee,
+    /// Fee charged on removing single asset liquidity.
+    pub exit_fee: Fee,
+    /// Total supply of the pool token, which stands for shares of the pool.
+    pub pool_supply: Balance,
+    /// Swap fees shared by liquidity providers, (token, amount).
+    pub rewards: Vec<(TokenSymbol, Balance)>,
+}
+
+/// Linear change of the token weights of a pool over a block range.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug)]
+pub struct WeightRamp<BlockNumber, PoolWeight> {
+    /// Weights start changing after this block.
+    pub start_block: BlockNumber,
+    /// Weights reach the end weights at this block.
+    pub end_block: BlockNumber,
+    /// Weights at the start block, in the same order as the pool tokens.
+    pub start_weights: Vec<PoolWeight>,
+    /// Weights at the end block, in the same order as the pool tokens.
+    pub end_weights: Vec<PoolWeight>,
+}
+
+impl<BlockNumber: AtLeast32Bit + Copy, PoolWeight: AtLeast32Bit + Copy> WeightRamp<BlockNumber, PoolWeight> {
+    /// Weights of the pool tokens at block `now`.
+    pub fn weights_at(&self, now: BlockNumber) -> Vec<PoolWeight> {
+        if now <= self.start_block {
+            return self.start_weights.clone();
+        }
+        if now >= self.end_block {
+            return self.end_weights.clone();
+        }
+
+        let passed = (now - self.start_block).saturated_into::<u128>();
+        let duration = (self.end_block - self.start_block).saturated_into::<u128>();
+        self.start_weights
+            .iter()
+            .zip(self.end_weights.iter())
+            .map(|(start, end)| {
+                let start_weight = (*start).saturated_into::<u128>();
+                let end_weight = (*end).saturated_into::<u128>();
+                // weights may go up or down
+                let weight = if end_weight >= start_weight {
+                    let delta = multiply_by_rational(end_weight - start_weight, passed, duration).unwrap_or(0);
+                    start_weight.saturating_add(delta)
+                } else {
+                    let delta = multiply_by_rational(start_weight - end_weight, passed, duration).unwrap_or(0);
+                    start_weight.saturating_sub(delta)
+                };
+                weight.saturated_into::<PoolWeight>()
+            })
+            .collect()
+    }
+}
+
+/// Liquidity mining of a pool.
+#[derive(Encode, Decode, Default, Clone, PartialEq, Eq, RuntimeDebug)]
+pub struct MiningPool<BlockNumber, Balance> {
+    /// Tokens emitted to the liquidity providers every block, (token, amount per block).
+    pub emissions: Vec<(TokenSymbol, Balance)>,
+    /// Pool tokens held by the liquidity providers.
+    pub total_shares: Balance,
+    /// Rewards are accumulated until this block.
+    pub last_block: BlockNumber,
+    /// Accumulated rewards per share multiplied by `REWARD_PRECISION`, (token, rewards per share).
+    pub reward_per_share: Vec<(TokenSymbol, u128)>,
+}
+
+/// Liquidity mining of a liquidity provider.
+#[derive(Encode, Decode, Default, Clone, PartialEq, Eq, RuntimeDebug)]
+pub struct MiningPosition<Balance> {
+    /// Pool tokens held by the provider.
+    pub shares: Balance,
+    /// Rewards per share the provider has been credited up to, (token, rewards per share).
+    pub reward_per_share_paid: Vec<(TokenSymbol, u128)>,
+    /// Rewards credited but not claimed yet, (token, amount).
+    pub pending: Vec<(TokenSymbol, Balance)>,
+}
+
+impl<AccountId, Balance: Copy, PoolWeight: Copy, Fee> Pool<AccountId, Balance, PoolWeight, Fee> {
+    /// Balance and weight of a token in the pool.
+    pub fn token(&self, token_symbol: TokenSymbol) -> Option<(Balance, PoolWeight)> {
+        self.tokens
+            .iter()
+            .find(|t| t.0 == token_symbol)
+            .map(|t| (t.1, t.2))
+    }
+}
+
+pub trait Trait: system::Trait {
     /// event
-    type Event: From<Event<Self>> + Into<<Self as frame_system::Trait>::Event>;
+    type Event: From<Event<Self>> + Into<<Self as system::Trait>::Event>;
+
+    /// The call dispatched by a flash swap while the tokens are lent out
+    type Call: Parameter
+        + Dispatchable<Origin = Self::Origin, PostInfo = PostDispatchInfo>
+        + GetDispatchInfo;
 
     /// fee
     type Fee: Member
@@ -98,20 +302,59 @@ pub trait Trait: frame_system::Trait {
 
     /// Some limitations on Balancer protocol
     type InitPoolSupply: Get<Self::Balance>;
+    /// A swap pays at most `1 / MaximumSwapInRatio` of the pool balance of the token in, in every
+    /// pool it passes through.
     type MaximumSwapInRatio: Get<Self::Balance>;
     type MinimumBalance: Get<Self::Balance>;
     type MaximumSwapFee: Get<Self::Fee>;
     type MinimumSwapFee: Get<Self::Fee>;
     type FeePrecision: Get<Self::Balance>;
+
+    /// Cumulative price movement of a pool allowed in a block, in `FeePrecision`
+    type MaximumPriceMovement: Get<Self::Balance>;
+
+    /// Blocks of price history kept for the time weighted average prices
+    type TwapWindow: Get<Self::BlockNumber>;
+
+    /// Convert prices value vTokens in their paired tokens in stable swap pools
+    type FetchConvertPrice: FetchConvertPrice<TokenSymbol, Self::Balance>;
+    type ConvertPricePrecision: Get<Self::Balance>;
 }
 
 decl_event! {
-    pub enum Event<T> where <T as Trait>::Balance, {
+    pub enum Event<T> where
+        <T as system::Trait>::AccountId,
+        <T as system::Trait>::BlockNumber,
+        <T as Trait>::Balance,
+        <T as Trait>::Fee,
+    {
         AddLiquiditySuccess,
         RemoveLiquiditySuccess,
         AddSingleLiquiditySuccess,
         RemoveSingleLiquiditySuccess,
         SwapTokenSuccess(Balance, Balance),
+        /// A pool was created, (pool id, creator).
+        PoolCreated(PoolId, AccountId),
+        /// Swapped along a route, (swaper, pools, token in, amount in, token out, amount out).
+        RouteSwapped(AccountId, Vec<PoolId>, TokenSymbol, Balance, TokenSymbol, Balance),
+        /// Price of the pair is recorded from now on.
+        OraclePairAdded(OraclePair),
+        /// Price of the pair is not recorded anymore.
+        OraclePairRemoved(OraclePair),
+        /// Swap fee of a pool is changed, (pool id, new fee).
+        SwapFeeChanged(PoolId, Fee),
+        /// Exit fee of a pool is changed, (pool id, new fee).
+        ExitFeeChanged(PoolId, Fee),
+        /// Weights of a pool will ramp to new weights, (pool id, start block, end block).
+        WeightRampScheduled(PoolId, BlockNumber, BlockNumber),
+        /// Weights of a pool stop ramping and keep the current weights.
+        WeightRampStopped(PoolId),
+        /// Tokens emitted to the liquidity providers of a pool, (pool id, (token, amount per block)).
+        MiningRewardsSet(PoolId, Vec<(TokenSymbol, Balance)>),
+        /// Liquidity mining rewards are claimed, (provider, pool id, (token, amount)).
+        RewardsClaimed(AccountId, PoolId, Vec<(TokenSymbol, Balance)>),
+        /// Tokens are borrowed and paid back in a flash swap, (borrower, pool id, borrowed, paid back).
+        FlashSwapped(AccountId, PoolId, Vec<(TokenSymbol, Balance)>, Vec<(TokenSymbol, Balance)>),
     }
 }
 
@@ -139,7 +382,7 @@ decl_error! {
         ForbidSameTokenSwap,
         /// Error on fix point crate
         FixedPointError,
-        /// Exceed too many amount of token, it should be trade_amount / all_amount <= 1 / 2
+        /// Pay more than `1 / MaximumSwapInRatio` of the pool balance of the token in
         ExceedMaximumSwapInRatio,
         /// Less than expected price while trading
         LessThanExpectedPrice,
@@ -149,75 +392,113 @@ decl_error! {
         LessThanExpectedAmount,
         /// Bigger than expected price while trading
         BiggerThanExpectedAmount,
+        /// The token or the account asset is frozen
+        AssetFrozen,
+        /// Pool id doesn't exist
+        PoolNotExist,
+        /// The token is not in the pool
+        TokenNotInPool,
+        /// A pool needs at least two different tokens with non-zero weights
+        InvalidPoolTokens,
+        /// Swap fee is out of the allowed range
+        InvalidSwapFee,
+        /// A route needs 1 to 3 hops through different pools
+        InvalidRoute,
+        /// The call is executed after its deadline
+        DeadlineExceeded,
+        /// Too many pairs are recorded by the oracle
+        TooManyOraclePairs,
+        /// The pair is recorded by the oracle already
+        OraclePairExisted,
+        /// The pair is not recorded by the oracle
+        OraclePairNotExist,
+        /// Exit fee is bigger than the maximum swap fee
+        InvalidExitFee,
+        /// A weight ramp needs a block range in the future
+        InvalidWeightRamp,
+        /// Too many pools are ramping their weights
+        TooManyWeightRamps,
+        /// Weights of the pool are not ramping
+        WeightRampNotExist,
+        /// Too many tokens are emitted to the liquidity providers
+        TooManyRewardTokens,
+        /// The provider has no liquidity mining rewards
+        NoRewardsToClaim,
+        /// The value function of the pool drops after a flash swap
+        FlashSwapInvariantBroken,
+        /// The stable swap math overflows or doesn't converge
+        StableSwapFailure,
+        /// The operation is only supported by weighted pools
+        UnsupportedForStablePool,
+        /// Amplification coefficient is out of the allowed range
+        InvalidAmplification,
+        /// The pool has no pool token, or it cannot be created
+        PoolTokenNotExist,
+        /// Swaps move the price of the pool more than allowed in a block
+        ExceedMaximumPriceMovement,
+        /// The pool is lent out by a flash swap
+        PoolLocked,
     }
 }
 
 decl_storage! {
     trait Store for Module<T: Trait> as Swap {
-        /// Balancer pool token
-        BalancerPoolToken get(fn all_pool_token) config(): T::Balance; // set pool token as 1000 by default
+        /// All pools, pool's details, like tokens with balance and weight, fee and pool token supply
+        Pools get(fn pools): map hasher(blake2_128_concat) PoolId
+            => Option<Pool<T::AccountId, T::Balance, T::PoolWeight, T::Fee>>;
 
-        /// Global pool, pool's details, like asset type, balance, weight, and value of function
-        GlobalPool get(fn global_pool) config(): (Vec<(TokenSymbol, T::Balance, T::PoolWeight)>, T::InvariantValue);
+        /// The next pool identifier up for grabs.
+        NextPoolId get(fn next_pool_id): PoolId;
 
-        /// Each user details for pool
-        UserPool get(fn user_pool) config(): map hasher(blake2_128_concat) T::AccountId => (Vec<(TokenSymbol, T::Balance)>, T::Balance);
+        /// Pools lent out by a flash swap, they cannot be used until the loan is paid back.
+        FlashLocked get(fn flash_locked): map hasher(blake2_128_concat) PoolId => bool;
 
-        /// User may add a single asst to liquidity
-        UserSinglePool: map hasher(blake2_128_concat) (T::AccountId, TokenSymbol) => (T::Balance, T::Balance);
-        // (T::Balance, BalancerPoolToken)
+        /// Token standing for the shares of each pool, its total issuance is the pool supply
+        PoolTokens get(fn pool_token): map hasher(blake2_128_concat) PoolId => Option<TokenSymbol>;
 
-        /// Now only support 7 tokens
-        NumberOfSupportedTokens get(fn count_of_supported_tokens) config(): u8;
+        /// The pool whose shares a pool token stands for
+        SharePools get(fn share_pool): map hasher(blake2_128_concat) TokenSymbol => Option<PoolId>;
 
-        /// Total weights
-        TotalWeight get(fn get_total_weight) build(|config: &GenesisConfig<T>| {
-            config.total_weight.iter().fold(Zero::zero(), |acc: T::PoolWeight, w| acc + *w)
-        }): T::PoolWeight;
-
-        /// Each token's weight
-        TokenWeight get(fn token_weight): map hasher(blake2_128_concat) TokenSymbol => T::PoolWeight;
+        /// Storage layout version, new chains start with the latest one
+        StorageVersion build(|_| Releases::V2_0_0): Releases;
 
         /// Fee stuff
         LiquidityFee get(fn liquidity_fee): T::Fee = T::Fee::from(0); // now we don't charge fee on adding or removing liquidity
-        SwapFee get(fn swap_fee) config(): T::Fee;
-        ExitFee get(fn exit_fee) config(): T::Fee;
 
-        /// shared fee pool
-        SharedRewardPool get(fn shared_reward): Vec<(TokenSymbol, T::Balance)>;
+        /// Token pairs whose prices are recorded
+        OraclePairs get(fn oracle_pairs): Vec<OraclePair>;
+
+        /// Sum of the prices of a pair at the end of each block, until the block
+        PriceCumulative get(fn price_cumulative): double_map hasher(blake2_128_concat) OraclePair, hasher(twox_64_concat) T::BlockNumber => Option<u128>;
+
+        /// Pools whose weights are ramping
+        WeightRamps get(fn weight_ramps): Vec<(PoolId, WeightRamp<T::BlockNumber, T::PoolWeight>)>;
+
+        /// Amplification coefficient of stable swap pools, other pools use the weighted math
+        Amplification get(fn amplification): map hasher(blake2_128_concat) PoolId => Option<u32>;
+
+        /// Liquidity mining of each pool
+        MiningPools get(fn mining_pools): map hasher(blake2_128_concat) PoolId => MiningPool<T::BlockNumber, T::Balance>;
+
+        /// Liquidity mining of each provider in a pool
+        MiningPositions get(fn mining_positions): map hasher(blake2_128_concat) (PoolId, T::AccountId) => MiningPosition<T::Balance>;
+
+        /// Cumulative price movement of each pool in a block, (block, movement in `FeePrecision`)
+        PriceMovements get(fn price_movement): map hasher(blake2_128_concat) PoolId => (T::BlockNumber, T::Balance);
     }
     add_extra_genesis {
-        config(total_weight): Vec<T::PoolWeight>;
+        // (owner, tokens with their balance and weight, swap fee, pool token supply) of each pool.
+        config(pools): Vec<(T::AccountId, Vec<(TokenSymbol, T::Balance, T::PoolWeight)>, T::Fee, T::Balance)>;
         build(|config: &GenesisConfig<T>| {
-            // initialize pool token
-            <BalancerPoolToken<T>>::put(config.all_pool_token);
-            // initialize count of supported tokens
-            NumberOfSupportedTokens::put(config.count_of_supported_tokens);
-            // set fee
-            <SwapFee<T>>::put(config.swap_fee);
-            <ExitFee<T>>::put(config.exit_fee);
-            // set token weight
-            for p in config.global_pool.0.iter() {
-                <TokenWeight<T>>::insert(p.0, p.2);
-            }
-            // initialize a pool for user
-            for (who, pool) in config.user_pool.iter() {
-                <UserPool<T>>::insert(who, pool);
+            for (owner, tokens, swap_fee, pool_supply) in config.pools.iter() {
+                let pool_id = <Module<T>>::insert_pool(owner.clone(), tokens.clone(), *swap_fee, *pool_supply)
+                    .expect("pool tokens of the genesis pools are created; qed");
+                // the balances of the genesis pools are not deposited by anyone
+                let pool_account = <Module<T>>::pool_account(pool_id);
+                for (token_symbol, balance, _) in tokens.iter() {
+                    T::AssetTrait::asset_issue(*token_symbol, &pool_account, *balance);
+                }
             }
-            // initialize global pool
-            <GlobalPool<T>>::put(&config.global_pool);
-
-            // initialize reward pool
-            let reward = vec![
-                (TokenSymbol::aUSD, T::Balance::from(0)),
-                (TokenSymbol::DOT, T::Balance::from(0)),
-                (TokenSymbol::vDOT, T::Balance::from(0)),
-                (TokenSymbol::KSM, T::Balance::from(0)),
-                (TokenSymbol::vKSM, T::Balance::from(0)),
-                (TokenSymbol::EOS, T::Balance::from(0)),
-                (TokenSymbol::vEOS, T::Balance::from(0)),
-            ];
-            <SharedRewardPool::<T>>::put(reward);
         });
     }
 }
@@ -227,448 +508,1826 @@ decl_module! {
         type Error = Error<T>;
 
         const InitPoolSupply: T::Balance = T::InitPoolSupply::get();
-        // when in a trade, trade_amount / all_amount <= 1 / 2
+        // a swap pays at most 1 / MaximumSwapInRatio of the pool balance of the token in
         const MaximumSwapInRatio: T::Balance = T::MaximumSwapInRatio::get();
         // when add liquidity, deposit this amount at least
         const MinimumBalance: T::Balance = T::MinimumBalance::get();
         const MaximumSwapFee: T::Fee = T::MaximumSwapFee::get();
         const MinimumSwapFee: T::Fee = T::MinimumSwapFee::get();
         const FeePrecision: T::Balance = T::FeePrecision::get();
+        const TwapWindow: T::BlockNumber = T::TwapWindow::get();
+        const ConvertPricePrecision: T::Balance = T::ConvertPricePrecision::get();
 
         fn deposit_event() = default;
 
+        fn on_runtime_upgrade() -> Weight {
+            migrations::migrate::<T>()
+        }
+
+        fn on_initialize(now: T::BlockNumber) -> Weight {
+            let ramps = Self::apply_weight_ramps(now) as Weight;
+
+            // prices are recorded on finalize
+            let pairs = MAX_ORACLE_PAIRS as Weight;
+            T::DbWeight::get().reads_writes(1 + 2 * pairs, 2 * pairs)
+                .saturating_add(T::DbWeight::get().reads_writes(1 + ramps, 1 + ramps))
+        }
+
+        fn on_finalize(now: T::BlockNumber) {
+            let window = T::TwapWindow::get();
+            for pair in Self::oracle_pairs() {
+                let last_block = now.saturating_sub(One::one());
+                let price = match Self::oracle_price(pair) {
+                    Some(price) => price,
+                    None => {
+                        // prices are recorded in continuous blocks, start over after the pool has a price
+                        if PriceCumulative::<T>::contains_key(pair, last_block) {
+                            PriceCumulative::<T>::remove_prefix(pair);
+                        }
+                        continue;
+                    }
+                };
+                let cumulative = Self::price_cumulative(pair, last_block).unwrap_or(0);
+                PriceCumulative::<T>::insert(pair, now, cumulative.saturating_add(price));
+
+                // no average price needs the sum before the window
+                if now > window {
+                    PriceCumulative::<T>::remove(pair, now - window - One::one());
+                }
+            }
+        }
+
+        /// Record the price of the base token in the quote token of a pool at the end of every block.
+        #[weight = T::DbWeight::get().reads_writes(2, 1)]
+        fn add_oracle_pair(origin, pair: OraclePair) {
+            ensure_root(origin)?;
+
+            let (pool_id, base, quote) = pair;
+            let pool = Self::pools(pool_id).ok_or(Error::<T>::PoolNotExist)?;
+            ensure!(base != quote, Error::<T>::ForbidSameTokenSwap);
+            ensure!(pool.token(base).is_some() && pool.token(quote).is_some(), Error::<T>::TokenNotInPool);
+
+            let mut pairs = Self::oracle_pairs();
+            ensure!(!pairs.contains(&pair), Error::<T>::OraclePairExisted);
+            ensure!(pairs.len() < MAX_ORACLE_PAIRS, Error::<T>::TooManyOraclePairs);
+            pairs.push(pair);
+            OraclePairs::put(pairs);
+
+            Self::deposit_event(RawEvent::OraclePairAdded(pair));
+        }
+
+        /// Stop recording the price of a pair, and clear its history.
+        #[weight = T::DbWeight::get().reads_writes(1, 1).saturating_add(
+            T::DbWeight::get().writes(T::TwapWindow::get().saturated_into::<Weight>() + 1)
+        )]
+        fn remove_oracle_pair(origin, pair: OraclePair) {
+            ensure_root(origin)?;
+
+            let mut pairs = Self::oracle_pairs();
+            let index = pairs.iter().position(|p| *p == pair).ok_or(Error::<T>::OraclePairNotExist)?;
+            pairs.swap_remove(index);
+            OraclePairs::put(pairs);
+            PriceCumulative::<T>::remove_prefix(pair);
+
+            Self::deposit_event(RawEvent::OraclePairRemoved(pair));
+        }
+
+        /// Change the swap fee of a pool within `MinimumSwapFee..=MaximumSwapFee`.
+        #[weight = T::DbWeight::get().reads_writes(1, 1)]
+        fn set_swap_fee(origin, pool_id: PoolId, #[compact] swap_fee: T::Fee) {
+            ensure_root(origin)?;
+
+            ensure!(
+                swap_fee >= T::MinimumSwapFee::get() && swap_fee <= T::MaximumSwapFee::get(),
+                Error::<T>::InvalidSwapFee
+            );
+            let mut pool = Self::pools(pool_id).ok_or(Error::<T>::PoolNotExist)?;
+            pool.swap_fee = swap_fee;
+            Pools::<T>::insert(pool_id, pool);
+
+            Self::deposit_event(RawEvent::SwapFeeChanged(pool_id, swap_fee));
+        }
+
+        /// Change the exit fee of a pool, it cannot exceed `MaximumSwapFee`.
+        #[weight = T::DbWeight::get().reads_writes(1, 1)]
+        fn set_exit_fee(origin, pool_id: PoolId, #[compact] exit_fee: T::Fee) {
+            ensure_root(origin)?;
+
+            ensure!(exit_fee <= T::MaximumSwapFee::get(), Error::<T>::InvalidExitFee);
+            let mut pool = Self::pools(pool_id).ok_or(Error::<T>::PoolNotExist)?;
+            pool.exit_fee = exit_fee;
+            Pools::<T>::insert(pool_id, pool);
+
+            Self::deposit_event(RawEvent::ExitFeeChanged(pool_id, exit_fee));
+        }
+
+        /// Ramp the token weights of a pool linearly from the current weights to `end_weights`
+        /// from `start_block` to `end_block`. A ramp replaces the previous one of the pool.
+        #[weight = T::DbWeight::get().reads_writes(2, 1)]
+        fn update_weights_gradually(
+            origin,
+            pool_id: PoolId,
+            end_weights: Vec<(TokenSymbol, T::PoolWeight)>,
+            start_block: T::BlockNumber,
+            end_block: T::BlockNumber
+        ) {
+            ensure_root(origin)?;
+
+            let now = <system::Module<T>>::block_number();
+            ensure!(start_block > now && end_block > start_block, Error::<T>::InvalidWeightRamp);
+
+            let pool = Self::pools(pool_id).ok_or(Error::<T>::PoolNotExist)?;
+            ensure!(!Amplification::contains_key(pool_id), Error::<T>::UnsupportedForStablePool);
+            // every token of the pool needs a new weight
+            ensure!(end_weights.len() == pool.tokens.len(), Error::<T>::InvalidPoolTokens);
+            let end_weights = pool.tokens
+                .iter()
+                .map(|t| {
+                    end_weights.iter().find(|w| w.0 == t.0).map(|w| w.1).ok_or(Error::<T>::TokenNotInPool)
+                })
+                .collect::<Result<Vec<T::PoolWeight>, Error<T>>>()?;
+            ensure!(end_weights.iter().all(|w| !w.is_zero()), Error::<T>::InvalidPoolTokens);
+
+            let ramp = WeightRamp {
+                start_block,
+                end_block,
+                start_weights: pool.tokens.iter().map(|t| t.2).collect(),
+                end_weights,
+            };
+            let mut ramps = Self::weight_ramps();
+            match ramps.iter().position(|r| r.0 == pool_id) {
+                Some(index) => ramps[index] = (pool_id, ramp),
+                None => {
+                    ensure!(ramps.len() < MAX_WEIGHT_RAMPS, Error::<T>::TooManyWeightRamps);
+                    ramps.push((pool_id, ramp));
+                }
+            }
+            WeightRamps::<T>::put(ramps);
+
+            Self::deposit_event(RawEvent::WeightRampScheduled(pool_id, start_block, end_block));
+        }
+
+        /// Stop ramping the weights of a pool, the pool keeps its current weights.
+        #[weight = T::DbWeight::get().reads_writes(1, 1)]
+        fn stop_weight_ramp(origin, pool_id: PoolId) {
+            ensure_root(origin)?;
+
+            let mut ramps = Self::weight_ramps();
+            let index = ramps.iter().position(|r| r.0 == pool_id).ok_or(Error::<T>::WeightRampNotExist)?;
+            ramps.swap_remove(index);
+            WeightRamps::<T>::put(ramps);
+
+            Self::deposit_event(RawEvent::WeightRampStopped(pool_id));
+        }
+
+        /// Emit `emissions` to the liquidity providers of a pool every block, in proportion to
+        /// their pool tokens. An empty list stops the emissions.
+        #[weight = T::DbWeight::get().reads_writes(2, 1)]
+        fn set_mining_rewards(origin, pool_id: PoolId, emissions: Vec<(TokenSymbol, T::Balance)>) {
+            ensure_root(origin)?;
+
+            ensure!(Pools::<T>::contains_key(pool_id), Error::<T>::PoolNotExist);
+            ensure!(emissions.len() <= MAX_MINING_REWARD_TOKENS, Error::<T>::TooManyRewardTokens);
+            for (token_symbol, _) in emissions.iter() {
+                ensure!(T::AssetTrait::token_exists(*token_symbol), Error::<T>::TokenNotExist);
+            }
+
+            let mut mining = Self::mining_pools(pool_id);
+            // rewards until now are accumulated at the former rates
+            Self::accrue_mining_rewards(&mut mining, <system::Module<T>>::block_number());
+            mining.emissions = emissions.clone();
+            MiningPools::<T>::insert(pool_id, mining);
+
+            Self::deposit_event(RawEvent::MiningRewardsSet(pool_id, emissions));
+        }
+
+        /// Claim liquidity mining rewards of a pool without removing liquidity.
+        #[weight = T::DbWeight::get().reads_writes(2, 2 + MAX_MINING_REWARD_TOKENS as Weight)]
+        fn claim_rewards(origin, pool_id: PoolId) {
+            let claimer = ensure_signed(origin)?;
+
+            ensure!(Pools::<T>::contains_key(pool_id), Error::<T>::PoolNotExist);
+
+            let (mining, mut position) = Self::settle_mining(pool_id, &claimer);
+            let rewards: Vec<(TokenSymbol, T::Balance)> = position.pending
+                .drain(..)
+                .filter(|r| !r.1.is_zero())
+                .collect();
+            ensure!(!rewards.is_empty(), Error::<T>::NoRewardsToClaim);
+
+            for (token_symbol, amount) in rewards.iter() {
+                T::AssetTrait::asset_issue(*token_symbol, &claimer, *amount);
+            }
+            MiningPools::<T>::insert(pool_id, mining);
+            MiningPositions::<T>::insert((pool_id, &claimer), position);
+
+            Self::deposit_event(RawEvent::RewardsClaimed(claimer, pool_id, rewards));
+        }
+
+        /// Borrow `borrows` from a pool, dispatch `call` as the borrower, then pay `repays` back
+        /// to the pool. Repayments are charged the swap fee, and the value function of the pool
+        /// without the fee must not drop, otherwise the whole flash swap is reverted. The pool is
+        /// locked while `call` runs.
+        #[weight = call.get_dispatch_info().weight.saturating_add(
+            T::DbWeight::get().reads_writes(2, 2).saturating_mul(1 + 2 * MAX_POOL_TOKENS as Weight)
+        ).saturating_add(T::DbWeight::get().writes(2))]
+        #[transactional]
+        fn flash_swap(
+            origin,
+            pool_id: PoolId,
+            borrows: Vec<(TokenSymbol, T::Balance)>,
+            repays: Vec<(TokenSymbol, T::Balance)>,
+            call: Box<<T as Trait>::Call>,
+            deadline: Option<T::BlockNumber>
+        ) {
+            let borrower = ensure_signed(origin)?;
+
+            Self::ensure_before_deadline(deadline)?;
+
+            ensure!(!borrows.is_empty() && borrows.len() <= MAX_POOL_TOKENS, Error::<T>::InvalidPoolTokens);
+            ensure!(repays.len() <= MAX_POOL_TOKENS, Error::<T>::InvalidPoolTokens);
+
+            let mut pool = Self::unlocked_pool(pool_id)?;
+            let value_before = Self::pool_invariant(pool_id, &pool.tokens)?;
+
+            // lend the tokens out
+            for (token_symbol, amount) in borrows.iter() {
+                ensure!(!T::AssetTrait::is_frozen(*token_symbol, &borrower), Error::<T>::AssetFrozen);
+                let (token_balance, _) = pool.token(*token_symbol).ok_or(Error::<T>::TokenNotInPool)?;
+                // the pool cannot be drained
+                ensure!(*amount < token_balance, Error::<T>::NotEnoughBalance);
+
+                for p in pool.tokens.iter_mut() {
+                    if *token_symbol == p.0 {
+                        p.1 = p.1.saturating_sub(*amount);
+                    }
+                }
+                T::AssetTrait::asset_move(*token_symbol, &Self::pool_account(pool_id), &borrower, *amount);
+            }
+            Pools::<T>::insert(pool_id, pool);
+
+            // the call cannot touch the pool, or it could pay back with the liquidity it adds
+            FlashLocked::insert(pool_id, true);
+            call.dispatch(system::RawOrigin::Signed(borrower.clone()).into()).map_err(|e| e.error)?;
+            FlashLocked::remove(pool_id);
+
+            let mut pool = Self::pools(pool_id).ok_or(Error::<T>::PoolNotExist)?;
+            // pool balances without the fee charged on repayments
+            let mut balances_without_fee = pool.tokens.clone();
+            for (token_symbol, amount) in repays.iter() {
+                ensure!(pool.token(*token_symbol).is_some(), Error::<T>::TokenNotInPool);
+                ensure!(!T::AssetTrait::is_frozen(*token_symbol, &borrower), Error::<T>::AssetFrozen);
+                let balances = T::AssetTrait::get_account_asset(*token_symbol, &borrower).available;
+                ensure!(balances >= *amount, Error::<T>::NotEnoughBalance);
+
+                let charged_fee = Self::calculate_charged_swap_fee(*amount, pool.swap_fee);
+                for (p, b) in pool.tokens.iter_mut().zip(balances_without_fee.iter_mut()) {
+                    if *token_symbol == p.0 {
+                        p.1 = p.1.saturating_add(*amount);
+                        b.1 = b.1.saturating_add(amount.saturating_sub(charged_fee));
+                    }
+                }
+                for r in pool.rewards.iter_mut() {
+                    if *token_symbol == r.0 {
+                        r.1 = r.1.saturating_add(charged_fee);
+                    }
+                }
+                T::AssetTrait::asset_move(*token_symbol, &borrower, &Self::pool_account(pool_id), *amount);
+            }
+            ensure!(
+                Self::pool_invariant(pool_id, &balances_without_fee)? >= value_before,
+                Error::<T>::FlashSwapInvariantBroken
+            );
+            Pools::<T>::insert(pool_id, pool);
+
+            Self::deposit_event(RawEvent::FlashSwapped(borrower, pool_id, borrows, repays));
+        }
+
+        /// Create a weighted pool over some tokens, (token, initial deposit, weight) for each of
+        /// them. The creator deposits the initial balances and gets `InitPoolSupply` pool tokens.
+        #[weight = T::DbWeight::get().reads_writes(2, 3).saturating_mul(tokens.len() as Weight)]
+        fn create_pool(
+            origin,
+            tokens: Vec<(TokenSymbol, T::Balance, T::PoolWeight)>,
+            swap_fee: T::Fee
+        ) {
+            let creator = ensure_signed(origin)?;
+
+            Self::do_create_pool(creator, tokens, swap_fee)?;
+        }
+
+        /// Create a stable swap pool over pegged tokens, (token, initial deposit) for each of them.
+        /// A vToken is valued in its paired token by the convert price. The higher the
+        /// `amplification`, the lower the slippage around the peg.
+        #[weight = T::DbWeight::get().reads_writes(2, 3).saturating_mul(tokens.len() as Weight)]
+        fn create_stable_pool(
+            origin,
+            tokens: Vec<(TokenSymbol, T::Balance)>,
+            amplification: u32,
+            swap_fee: T::Fee
+        ) {
+            let creator = ensure_signed(origin)?;
+
+            ensure!(
+                amplification > 0 && amplification <= MAX_AMPLIFICATION,
+                Error::<T>::InvalidAmplification
+            );
+
+            // weights are not used by the stable swap math
+            let tokens = tokens.into_iter().map(|(token_symbol, amount)| (token_symbol, amount, One::one())).collect();
+            let pool_id = Self::do_create_pool(creator, tokens, swap_fee)?;
+            Amplification::insert(pool_id, amplification);
+        }
+
         #[weight = weight_for::add_liquidity::<T>()]
         fn add_liquidity(
             origin,
-            #[compact] new_pool_token: T::Balance
+            pool_id: PoolId,
+            #[compact] new_pool_token: T::Balance,
+            deadline: Option<T::BlockNumber>
         ) {
             let provider = ensure_signed(origin)?;
 
+            Self::ensure_before_deadline(deadline)?;
+
             // ensure new pool's balances bigger than MinimumBalance
             ensure!(new_pool_token >= T::MinimumBalance::get(), Error::<T>::LessThanMinimumBalance);
 
-            // two times db reading
-            let all_pool_tokens = BalancerPoolToken::<T>::get();
-            let gpool = GlobalPool::<T>::get();
+            let mut pool = Self::unlocked_pool(pool_id)?;
+            let pool_token = Self::pool_token(pool_id).ok_or(Error::<T>::PoolTokenNotExist)?;
+            let all_pool_tokens = pool.pool_supply;
 
             // ensure this user have all kind of tokens and enough balance to deposit
-            let mut new_user_pool = Vec::with_capacity(gpool.0.len());
-            for p in gpool.0.iter() {
+            let mut new_user_pool = Vec::with_capacity(pool.tokens.len());
+            for p in pool.tokens.iter() {
                 // ensure user have the token
                 ensure!(T::AssetTrait::token_exists(p.0), Error::<T>::TokenNotExist);
+                ensure!(!T::AssetTrait::is_frozen(p.0, &provider), Error::<T>::AssetFrozen);
 
-                let balances = T::AssetTrait::get_account_asset(p.0, &provider).balance;
+                let balances = T::AssetTrait::get_account_asset(p.0, &provider).available;
                 ensure!(balances.gt(&T::Balance::from(0)), Error::<T>::NotEnoughBalance);
                 // about the algorithm: https://balancer.finance/whitepaper/#all-asset-depositwithdrawal
-                let need_deposited = new_pool_token.saturating_mul(balances) / all_pool_tokens; // todo, div may lose precision
+                // the same share of every pool balance, round up, in favor of the pool
+                let need_deposited = Self::pool_share_round_up(new_pool_token, p.1, all_pool_tokens)?;
                 // ensure user have enough token to deposit to this pool
                 ensure!(balances >= need_deposited, Error::<T>::NotEnoughBalance);
                 new_user_pool.push((p.0, need_deposited));
             }
 
-            let provider_pool = UserPool::<T>::get(&provider);
-            // first time to add liquidity
-            if provider_pool.0.is_empty() || provider_pool.1 == 0.into() {
-                UserPool::<T>::mutate(&provider, |pool| {
-                    // update user's pool
-                    pool.0 = new_user_pool.clone();
-                    pool.1 = new_pool_token;
-                });
-            } else {
-                // add more liquidity
-                UserPool::<T>::mutate(&provider, |pool| {
-                    // update pool token
-                    pool.1 = pool.1.saturating_add(new_pool_token);
-
-                    // update user's pool
-                    for (p, n) in pool.0.iter_mut().zip(new_user_pool.iter()) {
-                        p.1 = p.1.saturating_add(n.1);
+            Self::issue_pool_token(pool_id, pool_token, &provider, new_pool_token);
+
+            // deposit to the pool account
+            let pool_account = Self::pool_account(pool_id);
+            for p in new_user_pool.iter() {
+                T::AssetTrait::asset_move(p.0, &provider, &pool_account, p.1);
+            }
+
+            // update whole pool token
+            pool.pool_supply = pool.pool_supply.saturating_add(new_pool_token);
+
+            // update the pool
+            for (p, n) in pool.tokens.iter_mut().zip(new_user_pool.iter()) {
+                p.1 = p.1.saturating_add(n.1);
+            }
+            Pools::<T>::insert(pool_id, pool);
+
+            Self::deposit_event(RawEvent::AddLiquiditySuccess);
+        }
+
+        #[weight = T::DbWeight::get().reads_writes(1, 1)]
+        fn add_single_liquidity(
+            origin,
+            pool_id: PoolId,
+            token_symbol: TokenSymbol,
+            #[compact] token_amount_in: T::Balance,
+            deadline: Option<T::BlockNumber>
+        ) -> DispatchResult {
+            let provider = ensure_signed(origin)?;
+
+            Self::ensure_before_deadline(deadline)?;
+
+            // ensure user have token
+            ensure!(T::AssetTrait::token_exists(token_symbol), Error::<T>::TokenNotExist);
+            ensure!(!T::AssetTrait::is_frozen(token_symbol, &provider), Error::<T>::AssetFrozen);
+
+            let balances = T::AssetTrait::get_account_asset(token_symbol, &provider).available;
+            // ensure this use have enough balanes to deposit
+            ensure!(balances.gt(&T::Balance::from(0)), Error::<T>::NotEnoughBalance);
+            ensure!(balances >= token_amount_in, Error::<T>::NotEnoughBalance);
+
+            let mut pool = Self::unlocked_pool(pool_id)?;
+            let pool_token = Self::pool_token(pool_id).ok_or(Error::<T>::PoolTokenNotExist)?;
+
+            // caculate how many pool token will be issued to user
+            let new_pool_token = Self::pool_out_given_single_in(pool_id, &pool, token_symbol, token_amount_in)?;
+
+            Self::issue_pool_token(pool_id, pool_token, &provider, new_pool_token);
+
+            // update whole pool token
+            pool.pool_supply = pool.pool_supply.saturating_add(new_pool_token);
+
+            // update the pool
+            for p in pool.tokens.iter_mut() {
+                if token_symbol == p.0 {
+                    p.1 = p.1.saturating_add(token_amount_in);
+                }
+            }
+            Pools::<T>::insert(pool_id, pool);
+
+            // deposit to the pool account
+            T::AssetTrait::asset_move(token_symbol, &provider, &Self::pool_account(pool_id), token_amount_in);
+
+            Self::deposit_event(RawEvent::AddSingleLiquiditySuccess);
+            Ok(())
+        }
+
+        #[weight = T::DbWeight::get().reads_writes(1, 1)]
+        fn remove_single_asset_liquidity(
+            origin,
+            pool_id: PoolId,
+            token_symbol: TokenSymbol,
+            #[compact] pool_token_in: T::Balance,
+            deadline: Option<T::BlockNumber>
+        ) -> DispatchResult {
+            let remover = ensure_signed(origin)?;
+
+            Self::ensure_before_deadline(deadline)?;
+
+            let mut pool = Self::unlocked_pool(pool_id)?;
+            let pool_token = Self::pool_token(pool_id).ok_or(Error::<T>::PoolTokenNotExist)?;
+
+            // ensure this user has the pool, and doesn't redeem exceed all he has
+            let shares = T::AssetTrait::get_account_asset(pool_token, &remover).available;
+            ensure!(!shares.is_zero(), Error::<T>::NotExistedCurrentSinglePool);
+            ensure!(shares >= pool_token_in, Error::<T>::NotEnoughBalance);
+            ensure!(
+                !T::AssetTrait::is_frozen(token_symbol, &remover) && !T::AssetTrait::is_frozen(pool_token, &remover),
+                Error::<T>::AssetFrozen
+            );
+
+            // calculate how many balance user will get
+            let (token_amount, redeemed_reward) = Self::single_out_given_pool_in(pool_id, &pool, token_symbol, pool_token_in)?;
+
+            for r in pool.rewards.iter_mut() {
+                if token_symbol == r.0 {
+                    r.1 = r.1.saturating_sub(redeemed_reward);
+                }
+            }
+
+            // update user asset, the shared rewards are issued on top of the pool balance
+            T::AssetTrait::asset_move(token_symbol, &Self::pool_account(pool_id), &remover, token_amount);
+            if !redeemed_reward.is_zero() {
+                T::AssetTrait::asset_issue(token_symbol, &remover, redeemed_reward);
+            }
+            Self::burn_pool_token(pool_id, pool_token, &remover, pool_token_in);
+            // update whole pool token
+            pool.pool_supply = pool.pool_supply.saturating_sub(pool_token_in);
+
+            // update the pool
+            for p in pool.tokens.iter_mut() {
+                if token_symbol == p.0 {
+                    p.1 = p.1.saturating_sub(token_amount);
+                }
+            }
+            Pools::<T>::insert(pool_id, pool);
+
+            Self::deposit_event(RawEvent::RemoveSingleLiquiditySuccess);
+
+            Ok(())
+        }
+
+        #[weight = T::DbWeight::get().reads_writes(1, 1)]
+        fn remove_assets_liquidity(
+            origin,
+            pool_id: PoolId,
+            #[compact] pool_amount_in: T::Balance,
+            deadline: Option<T::BlockNumber>
+        ) {
+            let remover = ensure_signed(origin)?;
+
+            Self::ensure_before_deadline(deadline)?;
+
+            let mut pool = Self::unlocked_pool(pool_id)?;
+            let pool_token = Self::pool_token(pool_id).ok_or(Error::<T>::PoolTokenNotExist)?;
+
+            // ensure this user have the pool, and doesn't redeem too many
+            let shares = T::AssetTrait::get_account_asset(pool_token, &remover).available;
+            ensure!(!shares.is_zero(), Error::<T>::NotExistedCurrentPool);
+            ensure!(shares >= pool_amount_in, Error::<T>::NotEnoughBalance);
+            ensure!(!T::AssetTrait::is_frozen(pool_token, &remover), Error::<T>::AssetFrozen);
+
+            let whole_pool = pool.pool_supply;
+            ensure!(whole_pool >= pool_amount_in, Error::<T>::NotEnoughBalance);
+
+            // redeem a share of every token in the pool
+            let mut redeemed_pool = Vec::with_capacity(pool.tokens.len());
+            for p in pool.tokens.iter() {
+                let to_redeem = p.1.saturating_mul(pool_amount_in) / whole_pool;
+                ensure!(!T::AssetTrait::is_frozen(p.0, &remover), Error::<T>::AssetFrozen);
+                redeemed_pool.push((p.0, to_redeem));
+            }
+
+            Self::burn_pool_token(pool_id, pool_token, &remover, pool_amount_in);
+
+            // update whole pool
+            pool.pool_supply = pool.pool_supply.saturating_sub(pool_amount_in);
+
+            // redeem assets
+            let mut redeemed_rewards = Vec::with_capacity(redeemed_pool.len());
+            for r in pool.rewards.iter_mut() {
+                let redeemed_reward = r.1.saturating_mul(pool_amount_in) / whole_pool;
+                redeemed_rewards.push((r.0, redeemed_reward));
+                r.1 = r.1.saturating_sub(redeemed_reward);
+            }
+
+            // the shared rewards are issued on top of the pool balances
+            let pool_account = Self::pool_account(pool_id);
+            for (p, r) in redeemed_pool.iter().zip(redeemed_rewards.iter()) {
+                T::AssetTrait::asset_move(p.0, &pool_account, &remover, p.1);
+                if !r.1.is_zero() {
+                    T::AssetTrait::asset_issue(r.0, &remover, r.1);
+                }
+            }
+
+            // update the pool
+            for (p, r) in pool.tokens.iter_mut().zip(redeemed_pool.iter()) {
+                p.1 = p.1.saturating_sub(r.1);
+            }
+            Pools::<T>::insert(pool_id, pool);
+
+            Self::deposit_event(RawEvent::RemoveLiquiditySuccess);
+        }
+
+        // consider maxPrice and minAmountOut
+        #[weight = weight_for::swap::<T>()]
+        fn swap(
+            origin,
+            pool_id: PoolId,
+            token_in_type: TokenSymbol,
+            #[compact]token_amount_in: T::Balance,
+            min_token_amount_out: Option<T::Balance>,
+            token_out_type: TokenSymbol,
+            max_price: Option<T::Balance>,
+            deadline: Option<T::BlockNumber>
+        ) -> DispatchResult {
+            let swaper = ensure_signed(origin)?;
+
+            Self::ensure_before_deadline(deadline)?;
+
+            // ensure token symbol is different
+            ensure!(token_in_type != token_out_type, Error::<T>::ForbidSameTokenSwap);
+            ensure!(
+                !T::AssetTrait::is_frozen(token_in_type, &swaper) && !T::AssetTrait::is_frozen(token_out_type, &swaper),
+                Error::<T>::AssetFrozen
+            );
+
+            let balances = T::AssetTrait::get_account_asset(token_in_type, &swaper).available;
+            // ensure this use have enough balanes to deposit
+            ensure!(balances.ge(&token_amount_in), Error::<T>::NotEnoughBalance);
+
+            let pool = Self::unlocked_pool(pool_id)?;
+            Self::ensure_swap_in_ratio(&pool, token_in_type, token_amount_in)?;
+            let swap_fee = pool.swap_fee;
+
+            let spot_price_before = {
+                let price = Self::pool_spot_price(pool_id, &pool.tokens, token_in_type, token_out_type, swap_fee)?;
+                Self::convert_float(price)?
+            };
+
+            // compare spot price before do a swap
+            if max_price.is_some() {
+                ensure!(Some(spot_price_before) <= max_price, Error::<T>::BiggerThanExpectedPrice);
+            }
+
+            // do a swap
+            let token_amount_out = Self::pool_out_given_in(pool_id, &pool.tokens, token_in_type, token_amount_in, token_out_type, swap_fee)?;
+
+            // ensure token_amount_in is bigger than you exepect
+            if min_token_amount_out.is_some() {
+                ensure!(Some(token_amount_out) >= min_token_amount_out, Error::<T>::LessThanExpectedAmount);
+            }
+
+            // spot price before do a swap
+            let spot_price_after = {
+                let tokens_after = Self::tokens_after_swap(&pool.tokens, token_in_type, token_amount_in, token_out_type, token_amount_out);
+                let price = Self::pool_spot_price(pool_id, &tokens_after, token_in_type, token_out_type, swap_fee)?;
+                Self::convert_float(price)?
+            };
+            if max_price.is_some() {
+                ensure!(Some(spot_price_after) >= max_price, Error::<T>::LessThanExpectedPrice);
+                ensure!(spot_price_before <= spot_price_after, "The Price should rise after trade");
+                ensure!(spot_price_before <= token_amount_in.div(token_amount_out), "todo, what does it means");
+            }
+
+            Self::settle_swap(swaper, pool_id, pool, token_in_type, token_amount_in, token_out_type, token_amount_out)
+        }
+
+        /// Swap for exact amount of `token_out_type`, spending `max_token_amount_in` at most.
+        #[weight = weight_for::swap::<T>()]
+        fn swap_exact_out(
+            origin,
+            pool_id: PoolId,
+            token_in_type: TokenSymbol,
+            #[compact] max_token_amount_in: T::Balance,
+            token_out_type: TokenSymbol,
+            #[compact] token_amount_out: T::Balance,
+            deadline: Option<T::BlockNumber>
+        ) -> DispatchResult {
+            let swaper = ensure_signed(origin)?;
+
+            Self::ensure_before_deadline(deadline)?;
+
+            // ensure token symbol is different
+            ensure!(token_in_type != token_out_type, Error::<T>::ForbidSameTokenSwap);
+            ensure!(
+                !T::AssetTrait::is_frozen(token_in_type, &swaper) && !T::AssetTrait::is_frozen(token_out_type, &swaper),
+                Error::<T>::AssetFrozen
+            );
+
+            let pool = Self::unlocked_pool(pool_id)?;
+            // round up, in favor of the pool
+            let token_amount_in = Self::pool_in_given_out(pool_id, &pool.tokens, token_in_type, token_out_type, token_amount_out, pool.swap_fee)?;
+
+            // ensure token_amount_in is less than you exepect
+            ensure!(token_amount_in <= max_token_amount_in, Error::<T>::BiggerThanExpectedAmount);
+
+            let balances = T::AssetTrait::get_account_asset(token_in_type, &swaper).available;
+            // ensure this use have enough balanes to pay
+            ensure!(balances.ge(&token_amount_in), Error::<T>::NotEnoughBalance);
+            Self::ensure_swap_in_ratio(&pool, token_in_type, token_amount_in)?;
+
+            Self::settle_swap(swaper, pool_id, pool, token_in_type, token_amount_in, token_out_type, token_amount_out)
+        }
+
+        /// Swap exact amount of `token_in` through the pools in `path`, each hop swaps the token out
+        /// of the previous hop to the token of the hop.
+        #[weight = T::DbWeight::get().reads_writes(2, 3).saturating_mul(path.len() as Weight)]
+        fn swap_exact_in_routed(
+            origin,
+            token_in: TokenSymbol,
+            #[compact] token_amount_in: T::Balance,
+            path: Vec<Hop>,
+            #[compact] min_token_amount_out: T::Balance,
+            deadline: Option<T::BlockNumber>
+        ) {
+            let swaper = ensure_signed(origin)?;
+
+            Self::ensure_before_deadline(deadline)?;
+            Self::ensure_route_not_frozen(&swaper, token_in, &path)?;
+
+            let balances = T::AssetTrait::get_account_asset(token_in, &swaper).available;
+            ensure!(balances >= token_amount_in, Error::<T>::NotEnoughBalance);
+
+            let (token_amount_out, pools) = Self::route_out_given_in(token_in, token_amount_in, &path)?;
+            ensure!(token_amount_out >= min_token_amount_out, Error::<T>::LessThanExpectedAmount);
+
+            let token_out = path[path.len() - 1].1;
+            Self::settle_route(swaper, token_in, token_amount_in, token_out, token_amount_out, &path, pools)?;
+        }
+
+        /// Swap `token_in` through the pools in `path` for exact amount of the last token of the path,
+        /// spending `max_token_amount_in` at most.
+        #[weight = T::DbWeight::get().reads_writes(2, 3).saturating_mul(path.len() as Weight)]
+        fn swap_exact_out_routed(
+            origin,
+            token_in: TokenSymbol,
+            path: Vec<Hop>,
+            #[compact] token_amount_out: T::Balance,
+            #[compact] max_token_amount_in: T::Balance,
+            deadline: Option<T::BlockNumber>
+        ) {
+            let swaper = ensure_signed(origin)?;
+
+            Self::ensure_before_deadline(deadline)?;
+            Self::ensure_route_not_frozen(&swaper, token_in, &path)?;
+
+            let token_amount_in = Self::route_in_given_out(token_in, &path, token_amount_out)?;
+            ensure!(token_amount_in <= max_token_amount_in, Error::<T>::BiggerThanExpectedAmount);
+
+            let balances = T::AssetTrait::get_account_asset(token_in, &swaper).available;
+            ensure!(balances >= token_amount_in, Error::<T>::NotEnoughBalance);
+
+            // swap forward, amount in is rounded up on every hop, so it never gets less than expected
+            let (actual_amount_out, pools) = Self::route_out_given_in(token_in, token_amount_in, &path)?;
+            ensure!(actual_amount_out >= token_amount_out, Error::<T>::LessThanExpectedAmount);
+
+            let token_out = path[path.len() - 1].1;
+            Self::settle_route(swaper, token_in, token_amount_in, token_out, actual_amount_out, &path, pools)?;
+        }
+    }
+}
+
+#[allow(dead_code)]
+impl<T: Trait> Module<T> {
+    /// Create a pool with initial deposits from the creator, who gets `InitPoolSupply` pool tokens.
+    fn do_create_pool(
+        creator: T::AccountId,
+        tokens: Vec<(TokenSymbol, T::Balance, T::PoolWeight)>,
+        swap_fee: T::Fee,
+    ) -> Result<PoolId, DispatchError> {
+        ensure!(tokens.len() >= 2, Error::<T>::InvalidPoolTokens);
+        ensure!(tokens.len() <= MAX_POOL_TOKENS, Error::<T>::TooManyTokensToPool);
+        ensure!(
+            swap_fee >= T::MinimumSwapFee::get() && swap_fee <= T::MaximumSwapFee::get(),
+            Error::<T>::InvalidSwapFee
+        );
+
+        for (i, (token_symbol, amount, weight)) in tokens.iter().enumerate() {
+            // each token is only once in a pool
+            ensure!(tokens[..i].iter().all(|t| t.0 != *token_symbol), Error::<T>::InvalidPoolTokens);
+            ensure!(!weight.is_zero(), Error::<T>::InvalidPoolTokens);
+            ensure!(T::AssetTrait::token_exists(*token_symbol), Error::<T>::TokenNotExist);
+            ensure!(!T::AssetTrait::is_frozen(*token_symbol, &creator), Error::<T>::AssetFrozen);
+            ensure!(*amount >= T::MinimumBalance::get(), Error::<T>::LessThanMinimumBalance);
+
+            let balances = T::AssetTrait::get_account_asset(*token_symbol, &creator).available;
+            ensure!(balances >= *amount, Error::<T>::NotEnoughBalance);
+        }
+
+        let deposits: Vec<(TokenSymbol, T::Balance)> = tokens.iter().map(|t| (t.0, t.1)).collect();
+        let pool_id = Self::insert_pool(creator.clone(), tokens, swap_fee, T::InitPoolSupply::get())?;
+
+        // deposit the initial balances to the pool account
+        let pool_account = Self::pool_account(pool_id);
+        for (token_symbol, amount) in deposits.iter() {
+            T::AssetTrait::asset_move(*token_symbol, &creator, &pool_account, *amount);
+        }
+
+        Self::deposit_event(RawEvent::PoolCreated(pool_id, creator));
+
+        Ok(pool_id)
+    }
+
+    /// Store a new pool with its pool token, the whole `pool_supply` is issued to the owner.
+    /// Returns the pool identifier.
+    pub(crate) fn insert_pool(
+        owner: T::AccountId,
+        tokens: Vec<(TokenSymbol, T::Balance, T::PoolWeight)>,
+        swap_fee: T::Fee,
+        pool_supply: T::Balance,
+    ) -> Result<PoolId, DispatchError> {
+        let total_weight = tokens
+            .iter()
+            .fold(Zero::zero(), |acc: T::PoolWeight, t| acc.saturating_add(t.2));
+        // initialize reward pool for every token in the pool
+        let rewards = tokens.iter().map(|t| (t.0, Zero::zero())).collect();
+
+        let pool_id = Self::next_pool_id();
+        let pool_token = Self::create_pool_token(pool_id)?;
+        NextPoolId::put(pool_id.saturating_add(1));
+        <Pools<T>>::insert(
+            pool_id,
+            Pool {
+                owner: owner.clone(),
+                tokens,
+                total_weight,
+                swap_fee,
+                exit_fee: Zero::zero(),
+                pool_supply,
+                rewards,
+            },
+        );
+        Self::issue_pool_token(pool_id, pool_token, &owner, pool_supply);
+
+        Ok(pool_id)
+    }
+
+    /// Create the token standing for the shares of a pool, its symbol is `BPT-<pool id>`.
+    fn create_pool_token(pool_id: PoolId) -> Result<TokenSymbol, DispatchError> {
+        let symbol = alloc::format!("BPT-{}", pool_id).into_bytes();
+        let (id, _) = T::AssetTrait::asset_create(symbol, POOL_TOKEN_PRECISION)
+            .map_err(|_| Error::<T>::PoolTokenNotExist)?;
+        let pool_token = TokenSymbol::from(id.saturated_into::<u32>());
+
+        PoolTokens::insert(pool_id, pool_token);
+        SharePools::insert(pool_token, pool_id);
+
+        Ok(pool_token)
+    }
+
+    /// The account holding the balances of a pool, nobody has its private key.
+    pub fn pool_account(pool_id: PoolId) -> T::AccountId {
+        let entropy = BlakeTwo256::hash_of(&(POOL_ACCOUNT_PREFIX, pool_id));
+        T::AccountId::decode(&mut entropy.as_ref()).unwrap_or_default()
+    }
+
+    /// Issue pool tokens to a provider, who gets the liquidity mining shares as well.
+    fn issue_pool_token(pool_id: PoolId, pool_token: TokenSymbol, who: &T::AccountId, amount: T::Balance) {
+        T::AssetTrait::asset_issue(pool_token, who, amount);
+        Self::update_mining_shares(pool_id, who, |shares| shares.saturating_add(amount));
+    }
+
+    /// Burn pool tokens of a provider, who loses the liquidity mining shares as well.
+    fn burn_pool_token(pool_id: PoolId, pool_token: TokenSymbol, who: &T::AccountId, amount: T::Balance) {
+        T::AssetTrait::asset_redeem(pool_token, who, amount);
+        Self::update_mining_shares(pool_id, who, |shares| shares.saturating_sub(amount));
+    }
+
+    /// Spot price of the base token in the quote token of the pair without fee, multiplied by
+    /// `PRICE_PRECISION`.
+    pub(crate) fn oracle_price((pool_id, base, quote): OraclePair) -> Option<u128> {
+        let pool = Self::pools(pool_id)?;
+        let (base_balance, base_weight) = pool.token(base)?;
+        let (quote_balance, quote_weight) = pool.token(quote)?;
+        // an empty pool has no price
+        if base_balance.is_zero() || quote_balance.is_zero() || base_weight.is_zero() || quote_weight.is_zero() {
+            return None;
+        }
+
+        // it costs how many quote tokens to buy a base token
+        let price = Self::pool_spot_price(pool_id, &pool.tokens, quote, base, Zero::zero()).ok()?;
+        Some(Self::precise_price(price))
+    }
+
+    /// Check the invariants of all the pools, returns the broken ones of each pool. The pool
+    /// account of each pool holds its balances. Shared swap fee rewards and liquidity mining
+    /// rewards are issued on top of the pool balances when they are paid, so the pool account
+    /// doesn't hold them.
+    pub fn check_integrity() -> Vec<(PoolId, IntegrityErrorOf<T>)> {
+        let mut errors = Vec::new();
+
+        for (pool_id, pool) in Pools::<T>::iter() {
+            let pool_account = Self::pool_account(pool_id);
+            for (token_symbol, balance, _) in pool.tokens.iter() {
+                if !T::AssetTrait::token_exists(*token_symbol) {
+                    errors.push((pool_id, IntegrityError::TokenNotExist(*token_symbol)));
+                    continue;
+                }
+
+                let held = T::AssetTrait::get_account_asset(*token_symbol, &pool_account).balance;
+                if *balance != held {
+                    errors.push((pool_id, IntegrityError::PoolBalanceMismatch(*token_symbol, *balance, held)));
+                }
+            }
+
+            let weights = Self::total_weight(&pool.tokens);
+            if pool.total_weight != weights {
+                errors.push((pool_id, IntegrityError::TotalWeightMismatch(pool.total_weight, weights)));
+            }
+
+            let pool_token = match Self::pool_token(pool_id) {
+                Some(pool_token) if Self::share_pool(pool_token) == Some(pool_id) => pool_token,
+                _ => {
+                    errors.push((pool_id, IntegrityError::PoolTokenMismatch));
+                    continue;
+                }
+            };
+
+            let issued = T::AssetTrait::get_token(pool_token).total_supply;
+            if pool.pool_supply != issued {
+                errors.push((pool_id, IntegrityError::PoolSupplyMismatch(pool.pool_supply, issued)));
+            }
+
+            let total_shares = Self::mining_pools(pool_id).total_shares;
+            if total_shares != pool.pool_supply {
+                errors.push((pool_id, IntegrityError::MiningSharesMismatch(total_shares, pool.pool_supply)));
+            }
+        }
+
+        errors
+    }
+
+    /// Multiply a price by `PRICE_PRECISION` so that the fraction is kept in an integer.
+    pub(crate) fn precise_price(price: I64F64) -> u128 {
+        u128::from_fixed(price.saturating_mul(I64F64::from_num(PRICE_PRECISION)))
+    }
+
+    /// Time weighted average price of the pair over the last `window` blocks, multiplied by
+    /// `PRICE_PRECISION`. None if the price is not recorded for the whole window.
+    pub fn twap(pair: OraclePair, window: T::BlockNumber) -> Option<u128> {
+        if window.is_zero() || window > T::TwapWindow::get() {
+            return None;
+        }
+
+        // the prices of current block are recorded on finalize
+        let now = <system::Module<T>>::block_number();
+        let end = if PriceCumulative::<T>::contains_key(pair, now) {
+            now
+        } else {
+            now.saturating_sub(One::one())
+        };
+        if end < window {
+            return None;
+        }
+
+        let cumulative_end = Self::price_cumulative(pair, end)?;
+        let cumulative_start = Self::price_cumulative(pair, end - window)?;
+        Some(cumulative_end.saturating_sub(cumulative_start) / window.saturated_into::<u128>())
+    }
+
+    /// Quote of swapping `token_amount_in` of `token_in` for `token_out` in a pool, returns
+    /// (amount out, fee charged, spot price before, spot price after), prices include the swap
+    /// fee and are multiplied by `PRICE_PRECISION`.
+    pub fn quote_swap(
+        pool_id: PoolId,
+        token_in: TokenSymbol,
+        token_amount_in: T::Balance,
+        token_out: TokenSymbol,
+    ) -> Result<(T::Balance, T::Balance, u128, u128), Error<T>> {
+        ensure!(token_in != token_out, Error::<T>::ForbidSameTokenSwap);
+
+        let pool = Self::pools(pool_id).ok_or(Error::<T>::PoolNotExist)?;
+        let swap_fee = pool.swap_fee;
+        let (token_balance_in, _) = pool.token(token_in).ok_or(Error::<T>::TokenNotInPool)?;
+        let (token_balance_out, _) = pool.token(token_out).ok_or(Error::<T>::TokenNotInPool)?;
+        ensure!(!token_balance_in.is_zero() && !token_balance_out.is_zero(), Error::<T>::NotEnoughBalance);
+
+        let spot_price_before = Self::pool_spot_price(pool_id, &pool.tokens, token_in, token_out, swap_fee)?;
+
+        let token_amount_out = Self::pool_out_given_in(pool_id, &pool.tokens, token_in, token_amount_in, token_out, swap_fee)?;
+        ensure!(token_amount_out < token_balance_out, Error::<T>::NotEnoughBalance);
+
+        let tokens_after = Self::tokens_after_swap(&pool.tokens, token_in, token_amount_in, token_out, token_amount_out);
+        let spot_price_after = Self::pool_spot_price(pool_id, &tokens_after, token_in, token_out, swap_fee)?;
+        let charged_fee = Self::calculate_charged_swap_fee(token_amount_in, swap_fee);
+
+        Ok((
+            token_amount_out,
+            charged_fee,
+            Self::precise_price(spot_price_before),
+            Self::precise_price(spot_price_after),
+        ))
+    }
+
+    /// Quote of how many pool tokens will be issued for adding `token_amount_in` of a single token
+    /// to a pool.
+    pub fn quote_add_single_liquidity(
+        pool_id: PoolId,
+        token_symbol: TokenSymbol,
+        token_amount_in: T::Balance,
+    ) -> Result<T::Balance, Error<T>> {
+        let pool = Self::pools(pool_id).ok_or(Error::<T>::PoolNotExist)?;
+        Self::pool_out_given_single_in(pool_id, &pool, token_symbol, token_amount_in)
+    }
+
+    /// Quote of how many tokens, including the share of rewards, will be paid for removing
+    /// `pool_token_in` pool tokens from a pool as a single token.
+    pub fn quote_remove_single_asset_liquidity(
+        pool_id: PoolId,
+        token_symbol: TokenSymbol,
+        pool_token_in: T::Balance,
+    ) -> Result<T::Balance, Error<T>> {
+        let pool = Self::pools(pool_id).ok_or(Error::<T>::PoolNotExist)?;
+        let (token_amount, reward) = Self::single_out_given_pool_in(pool_id, &pool, token_symbol, pool_token_in)?;
+        Ok(token_amount.saturating_add(reward))
+    }
+
+    /// Pool tokens issued for adding `token_amount_in` of a single token to the pool.
+    fn pool_out_given_single_in(
+        pool_id: PoolId,
+        pool: &PoolOf<T>,
+        token_symbol: TokenSymbol,
+        token_amount_in: T::Balance,
+    ) -> Result<T::Balance, Error<T>> {
+        // the math is for weighted pools
+        ensure!(!Amplification::contains_key(pool_id), Error::<T>::UnsupportedForStablePool);
+
+        // get current token balance and weight in the pool
+        let (token_balance_in, token_weight_in) = pool.token(token_symbol).ok_or(Error::<T>::TokenNotInPool)?;
+
+        let issued_pool_token = Self::calculate_pool_out_given_single_in(
+            token_balance_in,
+            token_weight_in,
+            token_amount_in,
+            pool.total_weight,
+            pool.pool_supply,
+            LiquidityFee::<T>::get(),
+        )?;
+        Self::convert_float(issued_pool_token)
+    }
+
+    /// Token amount and share of rewards paid for removing `pool_token_in` pool tokens from the
+    /// pool as a single token.
+    fn single_out_given_pool_in(
+        pool_id: PoolId,
+        pool: &PoolOf<T>,
+        token_symbol: TokenSymbol,
+        pool_token_in: T::Balance,
+    ) -> Result<(T::Balance, T::Balance), Error<T>> {
+        // the math is for weighted pools
+        ensure!(!Amplification::contains_key(pool_id), Error::<T>::UnsupportedForStablePool);
+
+        let whole_pool = pool.pool_supply;
+        ensure!(!whole_pool.is_zero() && whole_pool >= pool_token_in, Error::<T>::NotEnoughBalance);
+
+        // get token's weight
+        let (pool_token, token_weight) = pool.token(token_symbol).ok_or(Error::<T>::TokenNotInPool)?;
+
+        let token_amount = {
+            let token_amount_out = Self::calculate_single_out_given_pool_in(
+                token_weight,
+                pool_token_in,
+                pool.total_weight,
+                pool_token,
+                whole_pool,
+                LiquidityFee::<T>::get(),
+                pool.exit_fee,
+            )?;
+            Self::convert_float(token_amount_out)?
+        };
+
+        let reward = pool
+            .rewards
+            .iter()
+            .find(|r| r.0 == token_symbol)
+            .map(|r| r.1.saturating_mul(pool_token_in) / whole_pool)
+            .unwrap_or_else(Zero::zero);
+
+        Ok((token_amount, reward))
+    }
+
+    /// Set the weights of ramping pools at block `now`, and drop the finished ramps. Returns how
+    /// many pools are updated.
+    pub(crate) fn apply_weight_ramps(now: T::BlockNumber) -> usize {
+        let ramps = Self::weight_ramps();
+        if ramps.is_empty() {
+            return 0;
+        }
+
+        let mut updated = 0;
+        let mut remaining = Vec::with_capacity(ramps.len());
+        for (pool_id, ramp) in ramps {
+            if now <= ramp.start_block {
+                remaining.push((pool_id, ramp));
+                continue;
+            }
+
+            let weights = ramp.weights_at(now);
+            let exists = Pools::<T>::mutate(pool_id, |pool| {
+                pool.as_mut().map(|pool| {
+                    for (token, weight) in pool.tokens.iter_mut().zip(weights.into_iter()) {
+                        token.2 = weight;
                     }
-                });
+                    // the invariant depends on weight ratios, keep the total in sync
+                    pool.total_weight = pool
+                        .tokens
+                        .iter()
+                        .fold(Zero::zero(), |acc: T::PoolWeight, t| acc.saturating_add(t.2));
+                })
+            });
+            if exists.is_some() {
+                updated += 1;
+                if now < ramp.end_block {
+                    remaining.push((pool_id, ramp));
+                }
+            }
+        }
+        WeightRamps::<T>::put(remaining);
+
+        updated
+    }
+
+    /// Accumulate the emissions of a pool until block `now`. Rewards are accumulated per block, so
+    /// liquidity added and removed in the same block earns nothing.
+    fn accrue_mining_rewards(mining: &mut MiningPoolOf<T>, now: T::BlockNumber) {
+        if now <= mining.last_block {
+            return;
+        }
+        let blocks = (now - mining.last_block).saturated_into::<u128>();
+        mining.last_block = now;
+        // nobody earns the emissions
+        if mining.total_shares.is_zero() {
+            return;
+        }
+
+        let total_shares = mining.total_shares.saturated_into::<u128>();
+        for (token_symbol, emission) in mining.emissions.iter() {
+            let rewards = emission.saturated_into::<u128>().saturating_mul(blocks);
+            let per_share = multiply_by_rational(rewards, REWARD_PRECISION, total_shares).unwrap_or(0);
+            match mining.reward_per_share.iter_mut().find(|r| r.0 == *token_symbol) {
+                Some(r) => r.1 = r.1.saturating_add(per_share),
+                None => mining.reward_per_share.push((*token_symbol, per_share)),
+            }
+        }
+    }
+
+    /// Accumulate the emissions of a pool until now, and credit the rewards earned by a provider
+    /// since its last update. Returns the updated pool mining and position, which are not stored.
+    pub(crate) fn settle_mining(
+        pool_id: PoolId,
+        who: &T::AccountId,
+    ) -> (MiningPoolOf<T>, MiningPosition<T::Balance>) {
+        let mut mining = Self::mining_pools(pool_id);
+        Self::accrue_mining_rewards(&mut mining, <system::Module<T>>::block_number());
+
+        let mut position = Self::mining_positions((pool_id, who));
+        let shares = position.shares.saturated_into::<u128>();
+        for (token_symbol, per_share) in mining.reward_per_share.iter() {
+            let paid = position
+                .reward_per_share_paid
+                .iter()
+                .find(|p| p.0 == *token_symbol)
+                .map(|p| p.1)
+                .unwrap_or(0);
+            let earned = multiply_by_rational(shares, per_share.saturating_sub(paid), REWARD_PRECISION)
+                .unwrap_or(0)
+                .saturated_into::<T::Balance>();
+            if earned.is_zero() {
+                continue;
+            }
+            match position.pending.iter_mut().find(|p| p.0 == *token_symbol) {
+                Some(p) => p.1 = p.1.saturating_add(earned),
+                None => position.pending.push((*token_symbol, earned)),
+            }
+        }
+        position.reward_per_share_paid = mining.reward_per_share.clone();
+
+        (mining, position)
+    }
+
+    /// Update the liquidity mining shares of a provider after its pool tokens change.
+    pub(crate) fn update_mining_shares(
+        pool_id: PoolId,
+        who: &T::AccountId,
+        f: impl FnOnce(T::Balance) -> T::Balance,
+    ) {
+        let (mut mining, mut position) = Self::settle_mining(pool_id, who);
+
+        let shares = f(position.shares);
+        mining.total_shares = mining
+            .total_shares
+            .saturating_sub(position.shares)
+            .saturating_add(shares);
+        position.shares = shares;
+
+        MiningPools::<T>::insert(pool_id, mining);
+        MiningPositions::<T>::insert((pool_id, who), position);
+    }
+
+    /// Update the pool and the swaper's assets after a swap, fails if the swap moves the price
+    /// of the pool too much in the block.
+    fn settle_swap(
+        swaper: T::AccountId,
+        pool_id: PoolId,
+        mut pool: PoolOf<T>,
+        token_in_type: TokenSymbol,
+        token_amount_in: T::Balance,
+        token_out_type: TokenSymbol,
+        token_amount_out: T::Balance,
+    ) -> DispatchResult {
+        let tokens_after = Self::tokens_after_swap(&pool.tokens, token_in_type, token_amount_in, token_out_type, token_amount_out);
+        let movement = Self::price_movement_after(pool_id, &pool.tokens, &tokens_after, token_in_type, token_out_type)?;
+        PriceMovements::<T>::insert(pool_id, (<system::Module<T>>::block_number(), movement));
+
+        let charged_fee = Self::calculate_charged_swap_fee(token_amount_in, pool.swap_fee);
+
+        // update the pool
+        for p in pool.tokens.iter_mut() {
+            if token_in_type == p.0 {
+                p.1 = p.1.saturating_add(token_amount_in);
+            }
+            if token_out_type == p.0 {
+                p.1 = p.1.saturating_sub(token_amount_out);
             }
+        }
 
-            // destroy token from user's assets
-            for p in new_user_pool.iter() {
-                T::AssetTrait::asset_redeem(p.0, &provider, p.1);
+        // update reward pool
+        for r in pool.rewards.iter_mut() {
+            if token_in_type == r.0 {
+                r.1 = r.1.saturating_add(charged_fee);
             }
+        }
+        Pools::<T>::insert(pool_id, pool);
 
-            // update whole pool token
-            BalancerPoolToken::<T>::mutate(|pool_token| {
-                *pool_token = pool_token.saturating_add(new_pool_token);
-            });
-
-            // update global pool
-            GlobalPool::<T>::mutate(|pool| {
-                for (p, n) in pool.0.iter_mut().zip(new_user_pool.iter()) {
-                    p.1 = p.1.saturating_add(n.1);
-                }
-            });
+        // pay to the pool account
+        let pool_account = Self::pool_account(pool_id);
+        T::AssetTrait::asset_move(token_in_type, &swaper, &pool_account, token_amount_in);
+        // what you get
+        T::AssetTrait::asset_move(token_out_type, &pool_account, &swaper, token_amount_out);
 
-            Self::deposit_event(RawEvent::AddLiquiditySuccess);
-        }
+        Self::deposit_event(RawEvent::SwapTokenSuccess(token_amount_in, token_amount_out));
 
-        #[weight = T::DbWeight::get().reads_writes(1, 1)]
-        fn add_single_liquidity(
-            origin,
-            token_symbol: TokenSymbol,
-            #[compact] token_amount_in: T::Balance,
-        ) -> DispatchResult {
-            let provider = ensure_signed(origin)?;
+        Ok(())
+    }
 
-            // ensure user have token
-            ensure!(T::AssetTrait::token_exists(token_symbol), Error::<T>::TokenNotExist);
+    /// A pool which is not lent out by a flash swap.
+    fn unlocked_pool(pool_id: PoolId) -> Result<PoolOf<T>, Error<T>> {
+        ensure!(!FlashLocked::contains_key(pool_id), Error::<T>::PoolLocked);
+        Self::pools(pool_id).ok_or(Error::<T>::PoolNotExist)
+    }
 
-            let balances = T::AssetTrait::get_account_asset(token_symbol, &provider).balance;
-            // ensure this use have enough balanes to deposit
-            ensure!(balances.gt(&T::Balance::from(0)), Error::<T>::NotEnoughBalance);
-            ensure!(balances >= token_amount_in, Error::<T>::NotEnoughBalance);
+    /// Ensure a swap pays at most `1 / MaximumSwapInRatio` of the pool balance of `token_in`.
+    fn ensure_swap_in_ratio(pool: &PoolOf<T>, token_in: TokenSymbol, token_amount_in: T::Balance) -> Result<(), Error<T>> {
+        let (token_balance_in, _) = pool.token(token_in).ok_or(Error::<T>::TokenNotInPool)?;
+        ensure!(
+            token_amount_in.saturating_mul(T::MaximumSwapInRatio::get()) <= token_balance_in,
+            Error::<T>::ExceedMaximumSwapInRatio
+        );
 
-            // get current token balance and weight in the pool
-            let (token_balance_in, token_weight_in) = {
-                let whole_pool = GlobalPool::<T>::get();
-                let mut token_balance_in = 0.into();
-                let mut token_weight_in = 0.into();
-                for p in whole_pool.0.iter() {
-                    if token_symbol == p.0 {
-                        token_balance_in = p.1;
-                        token_weight_in = p.2;
-                        break;
-                    }
-                }
-                (token_balance_in, token_weight_in)
-            };
+        Ok(())
+    }
 
-            let pool_supply = BalancerPoolToken::<T>::get();
-            let total_weight = TotalWeight::<T>::get();
-            let swap_fee = LiquidityFee::<T>::get();
+    /// Ensure the call is executed before its deadline, if there is one.
+    fn ensure_before_deadline(deadline: Option<T::BlockNumber>) -> Result<(), Error<T>> {
+        if let Some(deadline) = deadline {
+            ensure!(<system::Module<T>>::block_number() <= deadline, Error::<T>::DeadlineExceeded);
+        }
 
-            // caculate how many pool token will be issued to user
-            let new_pool_token = {
-                let issued_pool_token = Self::calculate_pool_out_given_single_in(token_balance_in, token_weight_in, token_amount_in, total_weight, pool_supply, swap_fee)?;
-                let pool_token_issued = u128::from_fixed(issued_pool_token);
-                TryInto::<T::Balance>::try_into(pool_token_issued).map_err(|_| Error::<T>::ConvertFailure)?
-            };
+        Ok(())
+    }
 
-            // first time to add liquidity
-            if !UserSinglePool::<T>::contains_key((&provider, token_symbol)) {
-                // add it to user's single pool
-                UserSinglePool::<T>::insert(
-                    (&provider, token_symbol),
-                    (token_amount_in, new_pool_token)
-                );
-            } else {
-                // add more liquidity to current single pool
-                UserSinglePool::<T>::mutate((&provider, token_symbol), |pool| {
-                    pool.0 = pool.0.saturating_add(token_amount_in);
-                    pool.1 = pool.1.saturating_add(new_pool_token);
-                });
-            }
+    /// Cumulative price movement of a pool in the current block, after a trade changes the pool
+    /// from `tokens_before` to `tokens_after`. The movement of a trade is how much the spot price
+    /// without fee changes in `FeePrecision`. Swaps are paused in the pool for the rest of the
+    /// block once they would move the price more than `MaximumPriceMovement`.
+    pub(crate) fn price_movement_after(
+        pool_id: PoolId,
+        tokens_before: &[(TokenSymbol, T::Balance, T::PoolWeight)],
+        tokens_after: &[(TokenSymbol, T::Balance, T::PoolWeight)],
+        token_in: TokenSymbol,
+        token_out: TokenSymbol,
+    ) -> Result<T::Balance, Error<T>> {
+        let price_before = Self::precise_price(Self::pool_spot_price(pool_id, tokens_before, token_in, token_out, Zero::zero())?);
+        let price_after = Self::precise_price(Self::pool_spot_price(pool_id, tokens_after, token_in, token_out, Zero::zero())?);
+        let moved = multiply_by_rational(
+            price_after.max(price_before) - price_after.min(price_before),
+            T::FeePrecision::get().saturated_into::<u128>(),
+            price_before.max(1),
+        )
+        .unwrap_or(u128::max_value());
+
+        // the movements of previous blocks are not counted
+        let (block_number, movement) = Self::price_movement(pool_id);
+        let movement = if block_number == <system::Module<T>>::block_number() {
+            movement
+        } else {
+            Zero::zero()
+        };
+        let movement = movement.saturating_add(moved.saturated_into());
+        ensure!(movement <= T::MaximumPriceMovement::get(), Error::<T>::ExceedMaximumPriceMovement);
 
-            // update whole pool
-            BalancerPoolToken::<T>::mutate(|pool_token| {
-                *pool_token = pool_token.saturating_add(new_pool_token);
-            });
+        Ok(movement)
+    }
 
-            // update global pool
-            GlobalPool::<T>::mutate(|pool| {
-                for p in pool.0.iter_mut() {
-                    if token_symbol == p.0 {
-                        p.1 = p.1.saturating_add(token_amount_in);
-                    }
+    /// Swap `token_amount_in` of `token_in` along `path` on copies of the pools, returns the amount
+    /// out of the last hop and the pools after the swap.
+    pub(crate) fn route_out_given_in(
+        token_in: TokenSymbol,
+        token_amount_in: T::Balance,
+        path: &[Hop],
+    ) -> Result<(T::Balance, Vec<(PoolId, PoolOf<T>)>), Error<T>> {
+        Self::ensure_valid_route(path)?;
+
+        let mut pools = Vec::with_capacity(path.len());
+        let (mut token_in, mut token_amount_in) = (token_in, token_amount_in);
+        for (pool_id, token_out) in path.iter() {
+            ensure!(token_in != *token_out, Error::<T>::ForbidSameTokenSwap);
+
+            let mut pool = Self::unlocked_pool(*pool_id)?;
+            Self::ensure_swap_in_ratio(&pool, token_in, token_amount_in)?;
+            let (token_balance_out, _) = pool.token(*token_out).ok_or(Error::<T>::TokenNotInPool)?;
+
+            let token_amount_out = Self::pool_out_given_in(*pool_id, &pool.tokens, token_in, token_amount_in, *token_out, pool.swap_fee)?;
+            ensure!(token_amount_out < token_balance_out, Error::<T>::NotEnoughBalance);
+
+            let charged_fee = Self::calculate_charged_swap_fee(token_amount_in, pool.swap_fee);
+            for p in pool.tokens.iter_mut() {
+                if token_in == p.0 {
+                    p.1 = p.1.saturating_add(token_amount_in);
                 }
-            });
-
-            // destroy token from user
-            T::AssetTrait::asset_redeem(token_symbol, &provider, token_amount_in);
+                if *token_out == p.0 {
+                    p.1 = p.1.saturating_sub(token_amount_out);
+                }
+            }
+            for r in pool.rewards.iter_mut() {
+                if token_in == r.0 {
+                    r.1 = r.1.saturating_add(charged_fee);
+                }
+            }
+            pools.push((*pool_id, pool));
 
-            Self::deposit_event(RawEvent::AddSingleLiquiditySuccess);
-            Ok(())
+            token_in = *token_out;
+            token_amount_in = token_amount_out;
         }
 
-        #[weight = T::DbWeight::get().reads_writes(1, 1)]
-        fn remove_single_asset_liquidity(
-            origin,
-            token_symbol: TokenSymbol,
-            #[compact] pool_token_in: T::Balance
-        ) -> DispatchResult {
-            let remover = ensure_signed(origin)?;
+        Ok((token_amount_in, pools))
+    }
 
-            // ensure this user has the pool
-            ensure!(
-                UserSinglePool::<T>::contains_key((&remover, token_symbol)),
-                Error::<T>::NotExistedCurrentSinglePool
-            );
+    /// How many `token_in` is needed to get `token_amount_out` of the last token of `path`.
+    pub(crate) fn route_in_given_out(
+        token_in: TokenSymbol,
+        path: &[Hop],
+        token_amount_out: T::Balance,
+    ) -> Result<T::Balance, Error<T>> {
+        Self::ensure_valid_route(path)?;
+
+        // pools are different from each other, so every hop can be calculated backward on its pool
+        let mut token_amount_out = token_amount_out;
+        for (i, (pool_id, token_out)) in path.iter().enumerate().rev() {
+            let token_in = if i == 0 { token_in } else { path[i - 1].1 };
+            ensure!(token_in != *token_out, Error::<T>::ForbidSameTokenSwap);
+
+            let pool = Self::pools(pool_id).ok_or(Error::<T>::PoolNotExist)?;
+            // round up, in favor of the pool
+            token_amount_out = Self::pool_in_given_out(*pool_id, &pool.tokens, token_in, *token_out, token_amount_out, pool.swap_fee)?;
+        }
 
-            // ensure user doesn't redeem exceed all he has
-            let user_single_pool = UserSinglePool::<T>::get((&remover, token_symbol));
-            ensure!(user_single_pool.1 >= pool_token_in, Error::<T>::NotEnoughBalance);
-
-            let whole_pool = BalancerPoolToken::<T>::get();
-            ensure!(whole_pool >= pool_token_in, Error::<T>::NotEnoughBalance);
-
-            let total_weight = TotalWeight::<T>::get();
-            let swap_fee = LiquidityFee::<T>::get();
-            let exit_fee = ExitFee::<T>::get();
-
-            // get token's weight
-            let (token_weight, pool_token) = {
-                let mut weight = T::PoolWeight::from(0);
-                let mut pool_token = T::Balance::from(0);
-                for pool in GlobalPool::<T>::get().0.iter() {
-                    if token_symbol == pool.0 {
-                        weight = pool.2;
-                        pool_token = pool.1;
-                        break;
-                    }
+        Ok(token_amount_out)
+    }
+
+    /// The route with the most amount out from `token_in` to `token_out`, and the amount out.
+    pub fn best_route(
+        token_in: TokenSymbol,
+        token_amount_in: T::Balance,
+        token_out: TokenSymbol,
+    ) -> Option<(Vec<Hop>, T::Balance)> {
+        let pools: Vec<(PoolId, Vec<TokenSymbol>)> = Pools::<T>::iter()
+            .map(|(pool_id, pool)| (pool_id, pool.tokens.iter().map(|t| t.0).collect()))
+            .collect();
+
+        let mut best: Option<(Vec<Hop>, T::Balance)> = None;
+        Self::search_routes(&pools, token_in, token_out, &mut Vec::new(), &mut |path| {
+            if let Ok((amount_out, _)) = Self::route_out_given_in(token_in, token_amount_in, path) {
+                if best.as_ref().map_or(true, |b| amount_out > b.1) {
+                    best = Some((path.to_vec(), amount_out));
                 }
-                (weight, pool_token)
-            };
+            }
+        });
 
-            // calculate how many balance user will get
-            let token_amount = {
-                let pool_supply = BalancerPoolToken::<T>::get();
-                let token_amount_out = Self::calculate_single_out_given_pool_in(token_weight, pool_token_in, total_weight, pool_token, pool_supply, swap_fee, exit_fee)?;
-                let token_amount_out = u128::from_fixed(token_amount_out);
+        best
+    }
 
-                TryInto::<T::Balance>::try_into(token_amount_out).map_err(|_| Error::<T>::ConvertFailure)?
-            };
+    /// Depth first search for all routes from `token_in` to `token_out`, visits every pool and token once.
+    fn search_routes<F: FnMut(&[Hop])>(
+        pools: &[(PoolId, Vec<TokenSymbol>)],
+        token_in: TokenSymbol,
+        token_out: TokenSymbol,
+        path: &mut Vec<Hop>,
+        on_route: &mut F,
+    ) {
+        if path.len() >= MAX_ROUTE_HOPS {
+            return;
+        }
 
-            let mut redeemed_reward: T::Balance = 0.into();
-            SharedRewardPool::<T>::mutate(|reward| {
-                for r in reward.iter_mut() {
-                    if token_symbol == r.0 {
-                        redeemed_reward = r.1.saturating_mul(pool_token_in) / whole_pool;
-                        r.1 = r.1.saturating_sub(redeemed_reward);
-                    }
+        let current = path.last().map_or(token_in, |h| h.1);
+        for (pool_id, tokens) in pools.iter() {
+            if !tokens.contains(&current) || path.iter().any(|h| h.0 == *pool_id) {
+                continue;
+            }
+            for next in tokens.iter() {
+                if *next == token_in || path.iter().any(|h| h.1 == *next) {
+                    continue;
                 }
-            });
-
-            // update user asset
-            T::AssetTrait::asset_issue(token_symbol, &remover, token_amount.saturating_add(redeemed_reward));
-            // update user's pool
-            UserSinglePool::<T>::mutate((&remover, token_symbol), |pool| {
-                pool.0 = pool.0.saturating_sub(token_amount);
-                pool.1 = pool.1.saturating_sub(pool_token_in);
-            });
-            // update whole pool token
-            BalancerPoolToken::<T>::mutate(|pool| {
-                *pool = pool.saturating_sub(pool_token_in);
-            });
-
-            // update global pool
-            GlobalPool::<T>::mutate(|pool| {
-                for p in pool.0.iter_mut() {
-                    if token_symbol == p.0 {
-                        p.1 = p.1.saturating_sub(token_amount);
-                    }
+                path.push((*pool_id, *next));
+                if *next == token_out {
+                    on_route(path);
+                } else {
+                    Self::search_routes(pools, token_in, token_out, path, on_route);
                 }
-            });
-
-            Self::deposit_event(RawEvent::RemoveSingleLiquiditySuccess);
+                path.pop();
+            }
+        }
+    }
 
-            Ok(())
+    fn ensure_valid_route(path: &[Hop]) -> Result<(), Error<T>> {
+        ensure!(!path.is_empty() && path.len() <= MAX_ROUTE_HOPS, Error::<T>::InvalidRoute);
+        // a pool only can be passed once
+        for (i, hop) in path.iter().enumerate() {
+            ensure!(path[..i].iter().all(|h| h.0 != hop.0), Error::<T>::InvalidRoute);
         }
 
-        #[weight = T::DbWeight::get().reads_writes(1, 1)]
-        fn remove_assets_liquidity(
-            origin,
-            #[compact] pool_amount_in: T::Balance
-        ) {
-            let remover = ensure_signed(origin)?;
+        Ok(())
+    }
 
-            // ensure this user have the pool
-            ensure!(UserPool::<T>::contains_key(&remover), Error::<T>::NotExistedCurrentPool);
+    fn ensure_route_not_frozen(who: &T::AccountId, token_in: TokenSymbol, path: &[Hop]) -> Result<(), Error<T>> {
+        ensure!(!T::AssetTrait::is_frozen(token_in, who), Error::<T>::AssetFrozen);
+        for hop in path.iter() {
+            ensure!(!T::AssetTrait::is_frozen(hop.1, who), Error::<T>::AssetFrozen);
+        }
 
-            let whole_pool = BalancerPoolToken::<T>::get();
-            ensure!(whole_pool >= pool_amount_in, Error::<T>::NotEnoughBalance);
+        Ok(())
+    }
 
-            let user_pool = UserPool::<T>::get(&remover);
-            // ensure user doesn't redeem too many
-            ensure!(user_pool.1 >= pool_amount_in, Error::<T>::NotEnoughBalance);
+    /// Store the pools after a routed swap and update the swaper's assets, fails if the route
+    /// moves the price of any pool too much in the block.
+    fn settle_route(
+        swaper: T::AccountId,
+        token_in: TokenSymbol,
+        token_amount_in: T::Balance,
+        token_out: TokenSymbol,
+        token_amount_out: T::Balance,
+        path: &[Hop],
+        pools: Vec<(PoolId, PoolOf<T>)>,
+    ) -> DispatchResult {
+        // check the price movements of all the pools before any of them is updated
+        let mut movements = Vec::with_capacity(pools.len());
+        let mut hop_amounts_out = Vec::with_capacity(pools.len());
+        let mut hop_in = token_in;
+        for ((pool_id, pool), (_, hop_out)) in pools.iter().zip(path.iter()) {
+            let pool_before = Self::pools(pool_id).ok_or(Error::<T>::PoolNotExist)?;
+            movements.push(Self::price_movement_after(*pool_id, &pool_before.tokens, &pool.tokens, hop_in, *hop_out)?);
+            // a pool is passed once, so the token out it pays is what its balance drops
+            let balance_before = pool_before.token(*hop_out).map_or_else(Zero::zero, |t| t.0);
+            let balance_after = pool.token(*hop_out).map_or_else(Zero::zero, |t| t.0);
+            hop_amounts_out.push(balance_before.saturating_sub(balance_after));
+            hop_in = *hop_out;
+        }
 
-            let mut redeemed_pool = Vec::with_capacity(user_pool.0.len());
-            for p in user_pool.0.iter() {
-                let to_redeem =  p.1.saturating_mul(pool_amount_in) / user_pool.1;
-                ensure!(to_redeem <= p.1, Error::<T>::NotEnoughBalance);
-                redeemed_pool.push((p.0, to_redeem));
-            }
+        let now = <system::Module<T>>::block_number();
+        let mut pool_ids = Vec::with_capacity(pools.len());
+        for ((pool_id, pool), movement) in pools.into_iter().zip(movements.into_iter()) {
+            PriceMovements::<T>::insert(pool_id, (now, movement));
+            Pools::<T>::insert(pool_id, pool);
+            pool_ids.push(pool_id);
+        }
 
-            // update user pool
-            UserPool::<T>::mutate(&remover, |pool| {
-                pool.1 = pool.1.saturating_sub(pool_amount_in);
-                for (p, r) in pool.0.iter_mut().zip(redeemed_pool.iter()) {
-                    p.1 = p.1.saturating_sub(r.1);
-                }
-            });
+        // the swaper pays the first pool, each pool pays the next one, and the last pool pays the
+        // swaper
+        let (mut payer, mut hop_in) = (swaper.clone(), (token_in, token_amount_in));
+        for ((pool_id, hop_out), hop_amount_out) in path.iter().zip(hop_amounts_out.into_iter()) {
+            let pool_account = Self::pool_account(*pool_id);
+            T::AssetTrait::asset_move(hop_in.0, &payer, &pool_account, hop_in.1);
+            payer = pool_account;
+            hop_in = (*hop_out, hop_amount_out);
+        }
+        T::AssetTrait::asset_move(token_out, &payer, &swaper, token_amount_out);
+
+        Self::deposit_event(RawEvent::RouteSwapped(
+            swaper,
+            pool_ids,
+            token_in,
+            token_amount_in,
+            token_out,
+            token_amount_out,
+        ));
+
+        Ok(())
+    }
 
-            // update whole pool
-            BalancerPoolToken::<T>::mutate(|pool| {
-                *pool = pool.saturating_sub(pool_amount_in);
-            });
+    /// Indexes of two tokens in the pool tokens.
+    fn token_indexes(
+        tokens: &[(TokenSymbol, T::Balance, T::PoolWeight)],
+        token_in: TokenSymbol,
+        token_out: TokenSymbol,
+    ) -> Result<(usize, usize), Error<T>> {
+        let i = tokens.iter().position(|t| t.0 == token_in).ok_or(Error::<T>::TokenNotInPool)?;
+        let j = tokens.iter().position(|t| t.0 == token_out).ok_or(Error::<T>::TokenNotInPool)?;
+        Ok((i, j))
+    }
 
-            // redeem assets
-            let mut redeemed_rewards = Vec::with_capacity(redeemed_pool.len());
-            SharedRewardPool::<T>::mutate(|reward| {
-                for r in reward.iter_mut() {
-                    let redeemed_reward = r.1.saturating_mul(pool_amount_in) / whole_pool;
-                    redeemed_rewards.push((r.0, redeemed_reward));
-                    r.1 = r.1.saturating_sub(redeemed_reward);
+    /// Pool tokens after swapping `token_amount_in` of `token_in` for `token_amount_out` of
+    /// `token_out`.
+    fn tokens_after_swap(
+        tokens: &[(TokenSymbol, T::Balance, T::PoolWeight)],
+        token_in: TokenSymbol,
+        token_amount_in: T::Balance,
+        token_out: TokenSymbol,
+        token_amount_out: T::Balance,
+    ) -> Vec<(TokenSymbol, T::Balance, T::PoolWeight)> {
+        tokens
+            .iter()
+            .map(|t| {
+                if t.0 == token_in {
+                    (t.0, t.1.saturating_add(token_amount_in), t.2)
+                } else if t.0 == token_out {
+                    (t.0, t.1.saturating_sub(token_amount_out), t.2)
+                } else {
+                    *t
                 }
-            });
+            })
+            .collect()
+    }
 
-            for (p, r) in redeemed_pool.iter().zip(redeemed_rewards.iter()) {
-                T::AssetTrait::asset_issue(p.0, &remover, p.1.saturating_add(r.1));
-            }
+    /// `amount / supply` of a pool balance, rounded up.
+    fn pool_share_round_up(amount: T::Balance, balance: T::Balance, supply: T::Balance) -> Result<T::Balance, Error<T>> {
+        let (amount, balance, supply) = (
+            amount.saturated_into::<u128>(),
+            balance.saturated_into::<u128>(),
+            supply.saturated_into::<u128>(),
+        );
+        let share = multiply_by_rational(amount, balance, supply).map_err(|_| Error::<T>::ConvertFailure)?;
+        let exact = amount.checked_mul(balance).map_or(false, |n| n % supply == 0);
+        let share = if exact { share } else { share.saturating_add(1) };
+        TryInto::<T::Balance>::try_into(share).map_err(|_| Error::<T>::ConvertFailure)
+    }
 
-            // update global pool
-            GlobalPool::<T>::mutate(|pool| {
-                for (p, r) in pool.0.iter_mut().zip(redeemed_pool.iter()) {
-                    p.1 = p.1.saturating_sub(r.1);
-                }
-            });
+    /// Amount of `token_out` paid by a pool for `token_amount_in` of `token_in`, rounded down.
+    pub(crate) fn pool_out_given_in(
+        pool_id: PoolId,
+        tokens: &[(TokenSymbol, T::Balance, T::PoolWeight)],
+        token_in: TokenSymbol,
+        token_amount_in: T::Balance,
+        token_out: TokenSymbol,
+        swap_fee: T::Fee,
+    ) -> Result<T::Balance, Error<T>> {
+        let (i, j) = Self::token_indexes(tokens, token_in, token_out)?;
+        match Self::amplification(pool_id) {
+            Some(amplification) => {
+                Self::stable_out_given_in(tokens, amplification, i, j, token_amount_in, swap_fee)
+            }
+            None => {
+                let token_amount_out = Self::calculate_out_given_in(
+                    tokens[i].1,
+                    tokens[i].2,
+                    token_amount_in,
+                    tokens[j].1,
+                    tokens[j].2,
+                    swap_fee,
+                )?;
+                Self::convert_float(token_amount_out)
+            }
+        }
+    }
 
-            Self::deposit_event(RawEvent::RemoveLiquiditySuccess);
+    /// Amount of `token_in` needed by a pool to pay `token_amount_out` of `token_out`, rounded up.
+    pub(crate) fn pool_in_given_out(
+        pool_id: PoolId,
+        tokens: &[(TokenSymbol, T::Balance, T::PoolWeight)],
+        token_in: TokenSymbol,
+        token_out: TokenSymbol,
+        token_amount_out: T::Balance,
+        swap_fee: T::Fee,
+    ) -> Result<T::Balance, Error<T>> {
+        let (i, j) = Self::token_indexes(tokens, token_in, token_out)?;
+        // the pool cannot be drained
+        ensure!(token_amount_out < tokens[j].1, Error::<T>::NotEnoughBalance);
+
+        match Self::amplification(pool_id) {
+            Some(amplification) => {
+                Self::stable_in_given_out(tokens, amplification, i, j, token_amount_out, swap_fee)
+            }
+            None => {
+                let token_amount_in = Self::calculate_in_given_out(
+                    tokens[i].1,
+                    tokens[i].2,
+                    tokens[j].1,
+                    tokens[j].2,
+                    token_amount_out,
+                    swap_fee,
+                )?;
+                Ok(Self::convert_float(token_amount_in)?.saturating_add(One::one()))
+            }
         }
+    }
 
-        // consider maxPrice and minAmountOut
-        #[weight = T::DbWeight::get().reads_writes(1, 1)]
-        fn swap(
-            origin,
-            token_in_type: TokenSymbol,
-            #[compact]token_amount_in: T::Balance,
-            min_token_amount_out: Option<T::Balance>,
-            token_out_type: TokenSymbol,
-            max_price: Option<T::Balance>
-        ) -> DispatchResult {
-            let swaper = ensure_signed(origin)?;
+    /// Spot price of `token_out` in `token_in` of a pool, including the swap fee.
+    pub(crate) fn pool_spot_price(
+        pool_id: PoolId,
+        tokens: &[(TokenSymbol, T::Balance, T::PoolWeight)],
+        token_in: TokenSymbol,
+        token_out: TokenSymbol,
+        swap_fee: T::Fee,
+    ) -> Result<I64F64, Error<T>> {
+        let (i, j) = Self::token_indexes(tokens, token_in, token_out)?;
+        match Self::amplification(pool_id) {
+            Some(amplification) => Self::stable_spot_price(tokens, amplification, i, j, swap_fee),
+            None => Self::calculate_spot_price(tokens[i].1, tokens[i].2, tokens[j].1, tokens[j].2, swap_fee),
+        }
+    }
 
-            // ensure token symbol is different
-            ensure!(token_in_type != token_out_type, Error::<T>::ForbidSameTokenSwap);
+    /// Invariant of a pool, the value function of weighted pools or `D` of stable swap pools.
+    pub(crate) fn pool_invariant(
+        pool_id: PoolId,
+        tokens: &[(TokenSymbol, T::Balance, T::PoolWeight)],
+    ) -> Result<u128, Error<T>> {
+        match Self::amplification(pool_id) {
+            Some(amplification) => {
+                let (balances, _) = Self::stable_balances(tokens)?;
+                Self::stable_invariant(&balances, amplification)
+            }
+            None => Ok(Self::value_function(tokens)?.saturated_into::<u128>()),
+        }
+    }
 
-            let balances = T::AssetTrait::get_account_asset(token_in_type, &swaper).balance;
-            // ensure this use have enough balanes to deposit
-            ensure!(balances.ge(&token_amount_in), Error::<T>::NotEnoughBalance);
-            // trade less half of balances
-            ensure!(balances.div(token_amount_in) >= T::MaximumSwapInRatio::get(), Error::<T>::ExceedMaximumSwapInRatio);
-
-//			let swaper_pool = UserPool::<T>::get(&swaper);
-//			let total_weight = TotalWeight::<T>::get();
-            let swap_fee = SwapFee::<T>::get();
-
-            let charged_fee = Self::calculate_charged_swap_fee(token_amount_in, swap_fee);
-
-            let ((token_balance_in, token_weight_in), (token_balance_out, token_weight_out)) = {
-                let mut weight_in = T::PoolWeight::from(0);
-                let mut weight_out = T::PoolWeight::from(0);
-                let mut token_balance_in = T::Balance::from(0);
-                let mut token_balance_out = T::Balance::from(0);
-                for pool in GlobalPool::<T>::get().0.iter() {
-                    if token_in_type == pool.0 {
-                        weight_in = pool.2;
-                        token_balance_in = pool.1;
-                    }
-                    if token_out_type == pool.0 {
-                        weight_out = pool.2;
-                        token_balance_out = pool.1;
+    /// Balances of a stable swap pool valued in the same unit, and the rates valuing them
+    /// multiplied by `ConvertPricePrecision`. A vToken is valued in its paired token by the
+    /// convert price if both of them are in the pool.
+    fn stable_balances(
+        tokens: &[(TokenSymbol, T::Balance, T::PoolWeight)],
+    ) -> Result<(Vec<u128>, Vec<u128>), Error<T>> {
+        let precision = T::ConvertPricePrecision::get().saturated_into::<u128>();
+
+        let mut balances = Vec::with_capacity(tokens.len());
+        let mut rates = Vec::with_capacity(tokens.len());
+        for (token_symbol, balance, _) in tokens.iter() {
+            let rate = match T::AssetTrait::get_token_pair(*token_symbol) {
+                Some((paired_token, vtoken)) if vtoken == *token_symbol && tokens.iter().any(|t| t.0 == paired_token) => {
+                    let price = T::FetchConvertPrice::fetch_convert_price(paired_token).saturated_into::<u128>();
+                    // no convert price yet, keep the peg
+                    if price.is_zero() {
+                        precision
+                    } else {
+                        price
                     }
                 }
-                ((token_balance_in, weight_in), (token_balance_out, weight_out))
+                _ => precision,
             };
+            let balance = multiply_by_rational(balance.saturated_into::<u128>(), rate, precision)
+                .map_err(|_| Error::<T>::StableSwapFailure)?;
+            balances.push(balance);
+            rates.push(rate);
+        }
 
-            let spot_price_before = {
-                let price = Self::calculate_spot_price(token_balance_in, token_weight_in, token_balance_out, token_weight_out, swap_fee)?;
-                Self::convert_float(price)?
-            };
+        Ok((balances, rates))
+    }
 
-            // compare spot price before do a swap
-            if max_price.is_some() {
-                ensure!(Some(spot_price_before) <= max_price, Error::<T>::BiggerThanExpectedPrice);
+    /**********************************************************************************************
+    // https://curve.fi/files/stableswap-paper.pdf                                               //
+    // stable swap invariant                                                                     //
+    // A = amplification                                                                         //
+    // D = invariant                                               D^(n + 1)                     //
+    // x = token balances      ann * sum(x_i) + D = ann * D + -------------------                //
+    // n = number of tokens                                        n^n * prod(x_i)               //
+    // ann = A * n, the amplification is A * n^(n - 1) of the paper as in the curve contracts,   //
+    // so A * n^n of the paper is A * n here                                                     //
+    	**********************************************************************************************/
+    pub(crate) fn stable_invariant(balances: &[u128], amplification: u32) -> Result<u128, Error<T>> {
+        let n = balances.len() as u128;
+        let sum = balances
+            .iter()
+            .try_fold(0u128, |acc, x| acc.checked_add(*x))
+            .ok_or(Error::<T>::StableSwapFailure)?;
+        if sum.is_zero() {
+            return Ok(Zero::zero());
+        }
+        ensure!(balances.iter().all(|x| !x.is_zero()), Error::<T>::StableSwapFailure);
+
+        let ann = (amplification as u128).saturating_mul(n);
+        let mut d = sum;
+        for _ in 0..MAX_STABLE_SWAP_ITERATIONS {
+            // D^(n + 1) / (n^n * prod(x))
+            let mut d_p = d;
+            for x in balances.iter() {
+                d_p = multiply_by_rational(d_p, d, x.saturating_mul(n)).map_err(|_| Error::<T>::StableSwapFailure)?;
             }
 
-            // do a swap
-            let token_amount_out = {
-                let fixed_token_amount_out = Self::calculate_out_given_in(
-                    token_balance_in,
-                    token_weight_in,
-                    token_amount_in,
-                    token_balance_out,
-                    token_weight_out,
-                    swap_fee
-                )?;
-                let token_amount_out = u128::from_fixed(fixed_token_amount_out);
+            let d_prev = d;
+            // D = (A * n * sum + D_p * n) * D / ((A * n - 1) * D + (n + 1) * D_p)
+            let numerator = ann
+                .checked_mul(sum)
+                .and_then(|v| v.checked_add(d_p.checked_mul(n)?))
+                .ok_or(Error::<T>::StableSwapFailure)?;
+            let denominator = (ann - 1)
+                .checked_mul(d)
+                .and_then(|v| v.checked_add(d_p.checked_mul(n + 1)?))
+                .ok_or(Error::<T>::StableSwapFailure)?;
+            d = multiply_by_rational(numerator, d, denominator).map_err(|_| Error::<T>::StableSwapFailure)?;
+
+            if d.max(d_prev) - d.min(d_prev) <= 1 {
+                return Ok(d);
+            }
+        }
 
-                TryInto::<T::Balance>::try_into(token_amount_out).map_err(|_| Error::<T>::ConvertFailure)?
-            };
+        Err(Error::<T>::StableSwapFailure)
+    }
 
-            // ensure token_amount_in is bigger than you exepect
-            if min_token_amount_out.is_some() {
-                ensure!(Some(token_amount_out) >= min_token_amount_out, Error::<T>::LessThanExpectedAmount);
+    /// Balance of token `j` keeping the invariant after the balance of token `i` becomes `x`.
+    pub(crate) fn stable_balance_out(
+        balances: &[u128],
+        amplification: u32,
+        i: usize,
+        j: usize,
+        x: u128,
+    ) -> Result<u128, Error<T>> {
+        let d = Self::stable_invariant(balances, amplification)?;
+        let n = balances.len() as u128;
+        let ann = (amplification as u128).saturating_mul(n);
+
+        // c = D^(n + 1) / (n^n * prod(x_k) * ann), b = sum(x_k) + D / ann, k != j
+        let mut c = d;
+        let mut sum = 0u128;
+        for (k, balance) in balances.iter().enumerate() {
+            if k == j {
+                continue;
             }
-
-            // spot price before do a swap
-            let spot_price_after = {
-                let price = Self::calculate_spot_price(
-                    token_balance_in.saturating_add(token_amount_in),
-                    token_weight_in,
-                    token_balance_out.saturating_sub(token_amount_out),
-                    token_weight_out,
-                    swap_fee
-                )?;
-                Self::convert_float(price)?
-            };
-            if max_price.is_some() {
-                ensure!(Some(spot_price_after) >= max_price, Error::<T>::LessThanExpectedPrice);
-                ensure!(spot_price_before <= spot_price_after, "The Price should rise after trade");
-                ensure!(spot_price_before <= token_amount_in.div(token_amount_out), "todo, what does it means");
+            let balance = if k == i { x } else { *balance };
+            ensure!(!balance.is_zero(), Error::<T>::StableSwapFailure);
+            sum = sum.checked_add(balance).ok_or(Error::<T>::StableSwapFailure)?;
+            c = multiply_by_rational(c, d, balance.saturating_mul(n)).map_err(|_| Error::<T>::StableSwapFailure)?;
+        }
+        c = multiply_by_rational(c, d, ann.saturating_mul(n)).map_err(|_| Error::<T>::StableSwapFailure)?;
+        let b = sum.checked_add(d / ann).ok_or(Error::<T>::StableSwapFailure)?;
+
+        // y = (y^2 + c) / (2 * y + b - D)
+        let mut y = d;
+        for _ in 0..MAX_STABLE_SWAP_ITERATIONS {
+            let y_prev = y;
+            let denominator = y
+                .checked_mul(2)
+                .and_then(|v| v.checked_add(b))
+                .and_then(|v| v.checked_sub(d))
+                .filter(|v| !v.is_zero())
+                .ok_or(Error::<T>::StableSwapFailure)?;
+            y = multiply_by_rational(y, y, denominator)
+                .map_err(|_| Error::<T>::StableSwapFailure)?
+                .saturating_add(c / denominator);
+
+            if y.max(y_prev) - y.min(y_prev) <= 1 {
+                return Ok(y);
             }
+        }
 
-            // update global pool
-            GlobalPool::<T>::mutate(|pool| {
-                for p in pool.0.iter_mut() {
-                    if token_in_type == p.0 {
-                        p.1 = p.1.saturating_add(token_amount_in);
-                    }
-                    if token_out_type == p.0 {
-                        p.1 = p.1.saturating_sub(token_amount_out);
-                    }
-                }
-            });
-
-            // update reward pool
-            SharedRewardPool::<T>::mutate(|reward| {
-                for r in reward.iter_mut() {
-                    if token_in_type == r.0 {
-                        r.1 = r.1.saturating_add(charged_fee);
-                    }
-                }
-            });
-
-            // update user pool
-            UserPool::<T>::mutate(&swaper, |pool| {
-                for p in pool.0.iter_mut() {
-                    if token_in_type == p.0 {
-                        p.1 = p.1.saturating_add(token_amount_in);
-                    }
-                    if token_out_type == p.0 {
-                        p.1 = p.1.saturating_sub(token_amount_out);
-                    }
-                }
-            });
+        Err(Error::<T>::StableSwapFailure)
+    }
 
-            // destroy token from user
-            T::AssetTrait::asset_redeem(token_in_type, &swaper, token_amount_in);
-            // what you get
-            T::AssetTrait::asset_issue(token_out_type, &swaper, token_amount_out);
+    fn stable_out_given_in(
+        tokens: &[(TokenSymbol, T::Balance, T::PoolWeight)],
+        amplification: u32,
+        i: usize,
+        j: usize,
+        token_amount_in: T::Balance,
+        swap_fee: T::Fee,
+    ) -> Result<T::Balance, Error<T>> {
+        let (balances, rates) = Self::stable_balances(tokens)?;
+        let precision = T::ConvertPricePrecision::get().saturated_into::<u128>();
+
+        // the fee is charged on the amount in
+        let charged_fee = Self::calculate_charged_swap_fee(token_amount_in, swap_fee);
+        let dx = multiply_by_rational(
+            token_amount_in.saturating_sub(charged_fee).saturated_into::<u128>(),
+            rates[i],
+            precision,
+        )
+        .map_err(|_| Error::<T>::StableSwapFailure)?;
+        let x = balances[i].checked_add(dx).ok_or(Error::<T>::StableSwapFailure)?;
+        let y = Self::stable_balance_out(&balances, amplification, i, j, x)?;
+
+        // round down, in favor of the pool
+        let dy = balances[j].saturating_sub(y).saturating_sub(1);
+        let token_amount_out = multiply_by_rational(dy, precision, rates[j]).map_err(|_| Error::<T>::StableSwapFailure)?;
+        TryInto::<T::Balance>::try_into(token_amount_out).map_err(|_| Error::<T>::ConvertFailure)
+    }
 
-            Self::deposit_event(RawEvent::SwapTokenSuccess(token_amount_in, token_amount_out));
+    fn stable_in_given_out(
+        tokens: &[(TokenSymbol, T::Balance, T::PoolWeight)],
+        amplification: u32,
+        i: usize,
+        j: usize,
+        token_amount_out: T::Balance,
+        swap_fee: T::Fee,
+    ) -> Result<T::Balance, Error<T>> {
+        let (balances, rates) = Self::stable_balances(tokens)?;
+        let precision = T::ConvertPricePrecision::get().saturated_into::<u128>();
+
+        // round up, in favor of the pool
+        let dy = multiply_by_rational(token_amount_out.saturated_into::<u128>(), rates[j], precision)
+            .map_err(|_| Error::<T>::StableSwapFailure)?
+            .saturating_add(1);
+        ensure!(dy < balances[j], Error::<T>::NotEnoughBalance);
+        let x = Self::stable_balance_out(&balances, amplification, j, i, balances[j] - dy)?;
+        let dx = x.saturating_sub(balances[i]).saturating_add(1);
+        let token_amount_in = multiply_by_rational(dx, precision, rates[i])
+            .map_err(|_| Error::<T>::StableSwapFailure)?
+            .saturating_add(1);
+
+        // the fee is charged on the amount in
+        let fee_precision = T::FeePrecision::get().saturated_into::<u128>();
+        let fee = Into::<T::Balance>::into(swap_fee).saturated_into::<u128>();
+        let token_amount_in = multiply_by_rational(token_amount_in, fee_precision, fee_precision.saturating_sub(fee))
+            .map_err(|_| Error::<T>::StableSwapFailure)?
+            .saturating_add(1);
+        TryInto::<T::Balance>::try_into(token_amount_in).map_err(|_| Error::<T>::ConvertFailure)
+    }
 
-            Ok(())
+    /**********************************************************************************************
+    // marginal price of token j in token i, differentiated from the stable swap invariant       //
+    //          x_i * ( ann * x_j + D_p )                         D^(n + 1)                      //
+    // sP =  ----------------------------- ,  where D_p = -------------------                    //
+    //          x_j * ( ann * x_i + D_p )                        n^n * prod(x_k)                 //
+    // ann = A * n as in the invariant above                                                     //
+    	**********************************************************************************************/
+    fn stable_spot_price(
+        tokens: &[(TokenSymbol, T::Balance, T::PoolWeight)],
+        amplification: u32,
+        i: usize,
+        j: usize,
+        swap_fee: T::Fee,
+    ) -> Result<I64F64, Error<T>> {
+        let (balances, rates) = Self::stable_balances(tokens)?;
+        ensure!(balances.iter().all(|b| !b.is_zero()), Error::<T>::NotEnoughBalance);
+
+        let n = balances.len() as u128;
+        let ann = (amplification as u128).saturating_mul(n);
+        let d = Self::stable_invariant(&balances, amplification)?;
+        let mut d_p = d;
+        for x in balances.iter() {
+            d_p = multiply_by_rational(d_p, d, x.saturating_mul(n)).map_err(|_| Error::<T>::StableSwapFailure)?;
         }
+
+        let price = multiply_by_rational(balances[i], PRICE_PRECISION, balances[j])
+            .and_then(|p| {
+                multiply_by_rational(
+                    p,
+                    ann.saturating_mul(balances[j]).saturating_add(d_p),
+                    ann.saturating_mul(balances[i]).saturating_add(d_p),
+                )
+            })
+            // back to the units of the tokens
+            .and_then(|p| multiply_by_rational(p, rates[j], rates[i]))
+            .map_err(|_| Error::<T>::StableSwapFailure)?;
+
+        // the price includes the fee, like the weighted spot price
+        let fee_precision = T::FeePrecision::get().saturated_into::<u128>();
+        let fee = Into::<T::Balance>::into(swap_fee).saturated_into::<u128>();
+        let price = multiply_by_rational(price, fee_precision, fee_precision.saturating_sub(fee))
+            .map_err(|_| Error::<T>::StableSwapFailure)?;
+
+        // from a price multiplied by `PRICE_PRECISION` to a fixed number
+        let bits = multiply_by_rational(price, 1u128 << 64, PRICE_PRECISION).map_err(|_| Error::<T>::FixedPointError)?;
+        ensure!(bits <= i128::max_value() as u128, Error::<T>::FixedPointError);
+        Ok(I64F64::from_bits(bits as i128))
     }
-}
 
-#[allow(dead_code)]
-impl<T: Trait> Module<T> {
     pub(crate) fn convert_float(input: I64F64) -> Result<T::Balance, Error<T>> {
         let converted = u128::from_fixed(input);
         TryInto::<T::Balance>::try_into(converted).map_err(|_| Error::<T>::ConvertFailure)
@@ -698,7 +2357,7 @@ impl<T: Trait> Module<T> {
         (balance, balance.saturating_mul(charged_fee))
     }
 
-    pub(crate) fn total_weight(pool: &[(T::AssetId, T::Balance, T::PoolWeight)]) -> T::PoolWeight {
+    pub(crate) fn total_weight<AssetId>(pool: &[(AssetId, T::Balance, T::PoolWeight)]) -> T::PoolWeight {
         pool.iter().fold(0.into(), |acc, v| acc + v.2)
     }
 
@@ -722,8 +2381,8 @@ impl<T: Trait> Module<T> {
     // https://balancer.finance/whitepaper/#value-function                                       //
     	**********************************************************************************************/
     #[allow(dead_code)]
-    pub(crate) fn value_function(
-        pool: &[(T::AssetId, T::Balance, T::PoolWeight)],
+    pub(crate) fn value_function<AssetId>(
+        pool: &[(AssetId, T::Balance, T::PoolWeight)],
     ) -> Result<T::InvariantValue, Error<T>> {
         let total_weight = Self::total_weight(pool);
 
@@ -832,7 +2491,7 @@ impl<T: Trait> Module<T> {
         };
 
         // pow exp
-        let weight_ratio = Self::weight_ratio(token_weight_in, token_weight_out)?;
+        let weight_ratio = Self::weight_ratio(token_weight_out, token_weight_in)?;
         // pow base
         let base =
             token_balance_out.saturating_div(token_balance_out.saturating_sub(token_amount_out));
@@ -969,9 +2628,9 @@ impl<T: Trait> Module<T> {
     // pS = poolSupply                 || ---------  | ^ | --------- || * bI - bI                //
     // pAo = poolAmountOut              \\    pS    /     \(wI / tW)//                           //
     // bI = balanceIn          tAi =  --------------------------------------------               //
-    // wI = weightIn                              /      wI  \                                   //
-    // tW = totalWeight                          |  1 - ----  |  * sF                            //
-    // sF = swapFee                               \      tW  /                                   //
+    // wI = weightIn                          /     /      wI  \      \                          //
+    // tW = totalWeight                      | 1 - |  1 - ----  | * sF  |                         //
+    // sF = swapFee                           \     \      tW  /      /                          //
     	**********************************************************************************************/
     pub(crate) fn calculate_single_in_given_pool_out(
         token_balance_in: T::Balance,
@@ -1013,9 +2672,11 @@ impl<T: Trait> Module<T> {
             let upper = power
                 .saturating_sub(FixedI128::<extra::U64>::from_num(1))
                 .saturating_mul(token_balance_in);
-            let down = FixedI128::<extra::U64>::from_num(1)
-                .saturating_sub(weight_ratio)
-                .saturating_mul(swap_fee);
+            let down = FixedI128::<extra::U64>::from_num(1).saturating_sub(
+                FixedI128::<extra::U64>::from_num(1)
+                    .saturating_sub(weight_ratio)
+                    .saturating_mul(swap_fee),
+            );
             upper.saturating_div(down)
         };
 
@@ -1095,6 +2756,104 @@ impl<T: Trait> Module<T> {
     }
 }
 
+impl<T: Trait, Price: AtLeast32Bit> PriceProvider<TokenSymbol, Price> for Module<T> {
+    fn get_price(token_symbol: TokenSymbol, quote: TokenSymbol, precision: Price) -> Option<Price> {
+        let pair = Self::oracle_pairs()
+            .into_iter()
+            .find(|p| p.1 == token_symbol && p.2 == quote)?;
+        let price = Self::twap(pair, T::TwapWindow::get())?;
+        let price =
+            multiply_by_rational(price, precision.saturated_into::<u128>(), PRICE_PRECISION).ok()?;
+
+        Some(price.saturated_into())
+    }
+}
+
+impl<T: Trait> TransferHandler<TokenSymbol, T::AccountId, T::Balance> for Module<T> {
+    fn handle_transfer(token_symbol: TokenSymbol, from: &T::AccountId, to: &T::AccountId, amount: T::Balance) {
+        // liquidity mining shares follow the pool tokens
+        if let Some(pool_id) = Self::share_pool(token_symbol) {
+            Self::update_mining_shares(pool_id, from, |shares| shares.saturating_sub(amount));
+            Self::update_mining_shares(pool_id, to, |shares| shares.saturating_add(amount));
+        }
+    }
+
+    fn is_burnable(token_symbol: TokenSymbol) -> bool {
+        // the pool supply is the total issuance of the pool token
+        !SharePools::contains_key(token_symbol)
+    }
+
+    fn handle_transfer_weight() -> Weight {
+        // the pool of the token, then the mining pool and the positions of both holders
+        T::DbWeight::get().reads_writes(5, 4)
+    }
+}
+
+impl<T: Trait> SwapHandler<PoolId, TokenSymbol, T::AccountId, T::Balance> for Module<T> {
+    type Error = DispatchError;
+
+    fn spot_price(
+        pool_id: PoolId,
+        token_in: TokenSymbol,
+        token_out: TokenSymbol,
+        precision: T::Balance,
+    ) -> Option<T::Balance> {
+        let pool = Self::pools(pool_id)?;
+        // it costs how many token in to buy a token out
+        let price = Self::pool_spot_price(pool_id, &pool.tokens, token_in, token_out, pool.swap_fee).ok()?;
+        let price = Self::precise_price(price);
+        if price.is_zero() {
+            return None;
+        }
+        let amount_out =
+            multiply_by_rational(precision.saturated_into::<u128>(), PRICE_PRECISION, price).ok()?;
+
+        TryInto::<T::Balance>::try_into(amount_out).ok()
+    }
+
+    fn swap_exact_in(
+        who: &T::AccountId,
+        pool_id: PoolId,
+        token_in: TokenSymbol,
+        token_amount_in: T::Balance,
+        token_out: TokenSymbol,
+        min_token_amount_out: T::Balance,
+    ) -> Result<T::Balance, Self::Error> {
+        ensure!(token_in != token_out, Error::<T>::ForbidSameTokenSwap);
+        ensure!(
+            !T::AssetTrait::is_frozen(token_in, who) && !T::AssetTrait::is_frozen(token_out, who),
+            Error::<T>::AssetFrozen
+        );
+
+        let balances = T::AssetTrait::get_account_asset(token_in, who).available;
+        ensure!(balances >= token_amount_in, Error::<T>::NotEnoughBalance);
+
+        let pool = Self::unlocked_pool(pool_id)?;
+        Self::ensure_swap_in_ratio(&pool, token_in, token_amount_in)?;
+        let token_amount_out =
+            Self::pool_out_given_in(pool_id, &pool.tokens, token_in, token_amount_in, token_out, pool.swap_fee)?;
+        ensure!(token_amount_out >= min_token_amount_out, Error::<T>::LessThanExpectedAmount);
+
+        Self::settle_swap(who.clone(), pool_id, pool, token_in, token_amount_in, token_out, token_amount_out)?;
+
+        Ok(token_amount_out)
+    }
+
+    fn swap_exact_in_weight() -> Weight {
+        weight_for::swap::<T>()
+    }
+
+    fn max_amount_in(pool_id: PoolId, token_in: TokenSymbol) -> Option<T::Balance> {
+        let (token_balance_in, _) = Self::pools(pool_id)?.token(token_in)?;
+        // no limit if the ratio is zero
+        Some(
+            token_balance_in
+                .checked_div(&T::MaximumSwapInRatio::get())
+                .unwrap_or_else(Bounded::max_value),
+        )
+    }
+}
+
 #[allow(dead_code)]
 mod weight_for {
     use super::Trait;
@@ -1110,4 +2869,9 @@ mod weight_for {
     pub(crate) fn add_single_liquidity<T: Trait>() -> Weight {
         todo!();
     }
+
+    /// swap weight, the frozen checks, the pool, its price movement and both account assets
+    pub(crate) fn swap<T: Trait>() -> Weight {
+        T::DbWeight::get().reads_writes(12, 7)
+    }
 }
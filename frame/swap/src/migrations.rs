@@ -0,0 +1,244 @@
+// Copyright 2020 Keysians Technologies.
+// This file is part of Keysians.
+
+// Keysians is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Keysians is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Keysians.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Storage migrations of the pallet, a module for each release whose layout changes.
+
+use crate::*;
+
+/// Migrate the storage from its version to the latest release step by step. Returns the weight
+/// consumed.
+pub(crate) fn migrate<T: Trait>() -> Weight {
+    let mut weight = T::DbWeight::get().reads(1);
+
+    if StorageVersion::get() == Releases::V1_0_0 {
+        weight = weight.saturating_add(v2::migrate::<T>());
+        StorageVersion::put(Releases::V2_0_0);
+        weight = weight.saturating_add(T::DbWeight::get().writes(1));
+    }
+
+    // the migration to a new release goes here, after the migrations to all the releases before it
+
+    weight
+}
+
+/// Storage items of the release 1.0.0 with their original types. The pallet has a single pool
+/// kept in storage values, and the shares are keyed by the providers.
+pub(crate) mod v1 {
+    use super::*;
+    use frame_support::{
+        storage::migration::{get_storage_value, remove_storage_prefix, StorageKeyIterator},
+        Blake2_128Concat, StorageHasher,
+    };
+
+    pub(crate) const MODULE: &[u8] = b"Swap";
+
+    /// `BalancerPoolToken: T::Balance`, supply of the pool.
+    pub(crate) const BALANCER_POOL_TOKEN: &[u8] = b"BalancerPoolToken";
+    /// `GlobalPool: (Vec<(TokenSymbol, T::Balance, T::PoolWeight)>, T::InvariantValue)`, tokens
+    /// of the pool with their balance and weight.
+    pub(crate) const GLOBAL_POOL: &[u8] = b"GlobalPool";
+    /// `UserPool: map hasher(blake2_128_concat) T::AccountId => (Vec<(TokenSymbol, T::Balance)>,
+    /// T::Balance)`, (deposits, shares) of each provider.
+    pub(crate) const USER_POOL: &[u8] = b"UserPool";
+    /// `UserSinglePool: map hasher(blake2_128_concat) (T::AccountId, TokenSymbol) => (T::Balance,
+    /// T::Balance)`, (deposit, shares) of each single asset provider.
+    pub(crate) const USER_SINGLE_POOL: &[u8] = b"UserSinglePool";
+    /// `NumberOfSupportedTokens: u8`
+    pub(crate) const NUMBER_OF_SUPPORTED_TOKENS: &[u8] = b"NumberOfSupportedTokens";
+    /// `TotalWeight: T::PoolWeight`
+    pub(crate) const TOTAL_WEIGHT: &[u8] = b"TotalWeight";
+    /// `TokenWeight: map hasher(blake2_128_concat) TokenSymbol => T::PoolWeight`
+    pub(crate) const TOKEN_WEIGHT: &[u8] = b"TokenWeight";
+    /// `SwapFee: T::Fee`
+    pub(crate) const SWAP_FEE: &[u8] = b"SwapFee";
+    /// `ExitFee: T::Fee`
+    pub(crate) const EXIT_FEE: &[u8] = b"ExitFee";
+    /// `SharedRewardPool: Vec<(TokenSymbol, T::Balance)>`, swap fees shared by the providers.
+    pub(crate) const SHARED_REWARD_POOL: &[u8] = b"SharedRewardPool";
+
+    /// All the storage items of the release.
+    pub(crate) const ITEMS: [&[u8]; 10] = [
+        BALANCER_POOL_TOKEN,
+        GLOBAL_POOL,
+        USER_POOL,
+        USER_SINGLE_POOL,
+        NUMBER_OF_SUPPORTED_TOKENS,
+        TOTAL_WEIGHT,
+        TOKEN_WEIGHT,
+        SWAP_FEE,
+        EXIT_FEE,
+        SHARED_REWARD_POOL,
+    ];
+
+    pub(crate) fn balancer_pool_token<T: Trait>() -> T::Balance {
+        get_storage_value(MODULE, BALANCER_POOL_TOKEN, &[]).unwrap_or_default()
+    }
+
+    pub(crate) fn global_pool<T: Trait>() -> Option<(Vec<(TokenSymbol, T::Balance, T::PoolWeight)>, T::InvariantValue)> {
+        get_storage_value(MODULE, GLOBAL_POOL, &[])
+    }
+
+    pub(crate) fn user_pool<T: Trait>() -> Vec<(T::AccountId, (Vec<(TokenSymbol, T::Balance)>, T::Balance))> {
+        StorageKeyIterator::<T::AccountId, (Vec<(TokenSymbol, T::Balance)>, T::Balance), Blake2_128Concat>::new(
+            MODULE,
+            USER_POOL,
+        )
+        .collect()
+    }
+
+    pub(crate) fn user_single_pool<T: Trait>() -> Vec<((T::AccountId, TokenSymbol), (T::Balance, T::Balance))> {
+        StorageKeyIterator::<(T::AccountId, TokenSymbol), (T::Balance, T::Balance), Blake2_128Concat>::new(
+            MODULE,
+            USER_SINGLE_POOL,
+        )
+        .collect()
+    }
+
+    pub(crate) fn token_weight<T: Trait>(token_symbol: TokenSymbol) -> Option<T::PoolWeight> {
+        get_storage_value(MODULE, TOKEN_WEIGHT, &Blake2_128Concat::hash(&token_symbol.encode()))
+    }
+
+    pub(crate) fn swap_fee<T: Trait>() -> T::Fee {
+        get_storage_value(MODULE, SWAP_FEE, &[]).unwrap_or_default()
+    }
+
+    pub(crate) fn exit_fee<T: Trait>() -> T::Fee {
+        get_storage_value(MODULE, EXIT_FEE, &[]).unwrap_or_default()
+    }
+
+    pub(crate) fn shared_reward_pool<T: Trait>() -> Vec<(TokenSymbol, T::Balance)> {
+        get_storage_value(MODULE, SHARED_REWARD_POOL, &[]).unwrap_or_default()
+    }
+
+    /// Remove all the storage items of the release.
+    pub(crate) fn remove_all() {
+        for item in ITEMS.iter() {
+            remove_storage_prefix(MODULE, item, &[]);
+        }
+    }
+}
+
+/// Pools are kept in `Pools`, and their shares are held as pool tokens.
+pub(crate) mod v2 {
+    use super::*;
+
+    /// Move the pool of the release 1.0.0 to `Pools` with its pool token, issue the pool balances
+    /// to the pool account and the pool tokens for the shares in `UserPool` and `UserSinglePool`,
+    /// and remove the storage of the old release. The old pool has no owner, it is assigned to the default account, which gets the
+    /// supply not recorded as anyone's shares, like the initial supply of the genesis pool.
+    /// Returns the weight consumed.
+    pub(crate) fn migrate<T: Trait>() -> Weight {
+        let remove_weight = T::DbWeight::get().writes(v1::ITEMS.len() as Weight);
+
+        let tokens = match v1::global_pool::<T>() {
+            Some((tokens, _)) if !tokens.is_empty() => tokens,
+            // there is no pool to migrate
+            _ => {
+                v1::remove_all();
+                return T::DbWeight::get().reads(1).saturating_add(remove_weight);
+            }
+        };
+
+        let pool_id = Module::<T>::next_pool_id();
+        let pool_token = match Module::<T>::create_pool_token(pool_id) {
+            Ok(pool_token) => pool_token,
+            // the old storage is kept if the symbol of the pool token is taken
+            Err(_) => return T::DbWeight::get().reads(5),
+        };
+        NextPoolId::put(pool_id.saturating_add(1));
+        let mut reads_writes: Weight = 6;
+
+        // weights in `TokenWeight` take precedence over their copies in `GlobalPool`
+        let tokens: Vec<(TokenSymbol, T::Balance, T::PoolWeight)> = tokens
+            .into_iter()
+            .map(|(token_symbol, balance, weight)| {
+                (token_symbol, balance, v1::token_weight::<T>(token_symbol).unwrap_or(weight))
+            })
+            .collect();
+        let total_weight = tokens
+            .iter()
+            .fold(Zero::zero(), |acc: T::PoolWeight, t| acc.saturating_add(t.2));
+        // fees shared by the providers, of the tokens in the pool only
+        let shared_rewards = v1::shared_reward_pool::<T>();
+        let rewards = tokens
+            .iter()
+            .map(|t| {
+                let reward = shared_rewards.iter().find(|r| r.0 == t.0).map(|r| r.1);
+                (t.0, reward.unwrap_or_else(Zero::zero))
+            })
+            .collect();
+        reads_writes = reads_writes.saturating_add(tokens.len() as Weight);
+
+        // the deposits were burnt, the pool account holds the pool balances from now on
+        let pool_account = Module::<T>::pool_account(pool_id);
+        for (token_symbol, balance, _) in tokens.iter() {
+            T::AssetTrait::asset_issue(*token_symbol, &pool_account, *balance);
+        }
+        reads_writes = reads_writes.saturating_add(3 * tokens.len() as Weight);
+
+        let owner = T::AccountId::default();
+        let pool_supply = v1::balancer_pool_token::<T>();
+        Pools::<T>::insert(
+            pool_id,
+            Pool {
+                owner: owner.clone(),
+                tokens,
+                total_weight,
+                swap_fee: v1::swap_fee::<T>(),
+                exit_fee: v1::exit_fee::<T>(),
+                pool_supply,
+                rewards,
+            },
+        );
+        reads_writes = reads_writes.saturating_add(5);
+
+        let mut shares: Vec<(T::AccountId, T::Balance)> = v1::user_pool::<T>()
+            .into_iter()
+            .map(|(who, (_, shares))| (who, shares))
+            .collect();
+        shares.extend(
+            v1::user_single_pool::<T>()
+                .into_iter()
+                .map(|((who, _), (_, shares))| (who, shares)),
+        );
+        let mut issued: T::Balance = Zero::zero();
+        for (who, amount) in shares.iter() {
+            if !amount.is_zero() {
+                Module::<T>::issue_pool_token(pool_id, pool_token, who, *amount);
+                issued = issued.saturating_add(*amount);
+            }
+            reads_writes = reads_writes.saturating_add(5);
+        }
+
+        // keep the pool supply equal to the total issuance of the pool token
+        if pool_supply > issued {
+            Module::<T>::issue_pool_token(pool_id, pool_token, &owner, pool_supply - issued);
+        } else if pool_supply < issued {
+            Pools::<T>::mutate(pool_id, |pool| {
+                if let Some(pool) = pool {
+                    pool.pool_supply = issued;
+                }
+            });
+        }
+        reads_writes = reads_writes.saturating_add(5);
+
+        v1::remove_all();
+
+        T::DbWeight::get()
+            .reads_writes(reads_writes, reads_writes)
+            .saturating_add(remove_weight)
+    }
+}
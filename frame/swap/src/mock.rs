@@ -92,11 +92,28 @@ parameter_types! {
 	pub const MaximumSwapFee: u64 = 10_000; // 10%
 	pub const MinimumSwapFee: u64 = 1; // 0.0001%
 	pub const FeePrecision: u64 = DOLLARS / 10_000_000;
+	pub const MaximumPriceMovement: u64 = 200_000; // 200%
+	pub const TwapWindow: u64 = 5;
+	pub const ConvertPricePrecision: u64 = 10_000;
+}
+
+/// vDOT is convertible to 1.2 DOT, other tokens have no convert price.
+pub struct MockConvertPrice;
+
+impl FetchConvertPrice<TokenSymbol, u64> for MockConvertPrice {
+	fn fetch_convert_price(token_symbol: TokenSymbol) -> u64 {
+		if token_symbol == TokenSymbol::DOT {
+			12_000
+		} else {
+			0
+		}
+	}
 }
 
 impl crate::Trait for Test {
 	type Fee = u64;
 	type Event = TestEvent;
+	type Call = Call;
 	type AssetTrait = Assets;
 	type Balance = u64;
 	type AssetId = u32;
@@ -110,6 +127,10 @@ impl crate::Trait for Test {
 	type MaximumSwapFee = MaximumSwapFee;
 	type MinimumSwapFee = MinimumSwapFee;
 	type FeePrecision = FeePrecision;
+	type MaximumPriceMovement = MaximumPriceMovement;
+	type TwapWindow = TwapWindow;
+	type FetchConvertPrice = MockConvertPrice;
+	type ConvertPricePrecision = ConvertPricePrecision;
 }
 
 impl assets::Trait for Test {
@@ -122,6 +143,11 @@ impl assets::Trait for Test {
 	type Convert = u64;
 	type AssetRedeem = ();
 	type FetchConvertPrice = ();
+	type DustHandler = ();
+	type PriceProvider = ();
+	type PricePrecision = ();
+	type MaxPriceUpdates = ();
+	type TransferHandler = Swap;
 }
 
 pub type Swap = crate::Module<Test>;
@@ -21,8 +21,17 @@
 use crate::*;
 use crate::mock::*;
 use float_cmp::approx_eq;
-use frame_support::{assert_ok, dispatch::DispatchError};
-use node_primitives::TokenSymbol;
+use frame_support::{
+	assert_ok,
+	dispatch::DispatchError,
+	storage::migration::{have_storage_value, put_storage_value},
+	traits::OnRuntimeUpgrade,
+	Blake2_128Concat, StorageHasher, Twox128,
+};
+use node_primitives::{PriceProvider, TokenSymbol};
+use proptest::{prelude::*, test_runner::FileFailurePersistence};
+use rug::Float;
+use std::ops::RangeInclusive;
 
 #[test]
 fn total_weight_should_work() {
@@ -181,6 +190,228 @@ fn calculate_single_in_given_pool_out_should_work() {
 	assert!(single_in.is_ok());
 }
 
+// bits of the reference floats, far more than the 128 bits of I64F64
+const REFERENCE_PRECISION: u32 = 256;
+// relative tolerance of the fixed point math against the references. The powers are exact to a
+// few units of 2^-64 relative, and the error grows up to 1 / (1 - power) for the smallest trades,
+// where the power is as close to 1 as 1 - 2e-5.
+const MATH_TOLERANCE: f64 = 0.000_000_000_001;
+const BALANCES: RangeInclusive<u64> = 1_000_000..=1_000_000_000_000;
+const WEIGHTS: RangeInclusive<u64> = 1..=50;
+const SWAP_FEES: RangeInclusive<u64> = 0..=10_000; // up to 10%
+
+fn float(value: u64) -> Float {
+	Float::with_val(REFERENCE_PRECISION, value)
+}
+
+// the exact value of a fixed point number, I64F64 has 64 fraction bits
+fn from_fixed(value: I64F64) -> Float {
+	Float::with_val(REFERENCE_PRECISION, value.to_bits()) >> 64u32
+}
+
+fn is_close(expected: &Float, actual: I64F64) -> bool {
+	let error = Float::with_val(REFERENCE_PRECISION, expected - &from_fixed(actual)).abs();
+	error <= Float::with_val(REFERENCE_PRECISION, expected.abs_ref()) * MATH_TOLERANCE
+}
+
+fn fee_ratio(fee: u64) -> Float {
+	float(fee) / float(FeePrecision::get())
+}
+
+// a balance, and an amount from 0.1% of it up to `1 / max_ratio` of it
+fn balance_and_amount(max_ratio: u64) -> impl Strategy<Value = (u64, u64)> {
+	BALANCES.prop_flat_map(move |balance| (Just(balance), balance / 1_000..=balance / max_ratio))
+}
+
+fn reference_out_given_in(
+	token_balance_in: u64,
+	token_weight_in: u64,
+	token_amount_in: u64,
+	token_balance_out: u64,
+	token_weight_out: u64,
+	swap_fee: u64,
+) -> Float {
+	let ratio = float(token_amount_in) * (1 - fee_ratio(swap_fee)) / float(token_balance_in);
+	let exponent = float(token_weight_in) / float(token_weight_out);
+	-float(token_balance_out) * (-ratio.ln_1p() * exponent).exp_m1()
+}
+
+fn reference_in_given_out(
+	token_balance_in: u64,
+	token_weight_in: u64,
+	token_balance_out: u64,
+	token_weight_out: u64,
+	token_amount_out: u64,
+	swap_fee: u64,
+) -> Float {
+	let ratio = float(token_amount_out) / float(token_balance_out - token_amount_out);
+	let exponent = float(token_weight_out) / float(token_weight_in);
+	float(token_balance_in) * (ratio.ln_1p() * exponent).exp_m1() / (1 - fee_ratio(swap_fee))
+}
+
+fn reference_pool_out_given_single_in(
+	token_balance_in: u64,
+	token_weight_in: u64,
+	token_amount_in: u64,
+	token_total_weight: u64,
+	pool_supply: u64,
+	swap_fee: u64,
+) -> Float {
+	let weight = float(token_weight_in) / float(token_total_weight);
+	let ratio = float(token_amount_in) * (1 - (1 - weight.clone()) * fee_ratio(swap_fee)) / float(token_balance_in);
+	float(pool_supply) * (ratio.ln_1p() * weight).exp_m1()
+}
+
+fn reference_single_in_given_pool_out(
+	token_balance_in: u64,
+	token_weight_in: u64,
+	token_total_weight: u64,
+	pool_amount_out: u64,
+	pool_supply: u64,
+	swap_fee: u64,
+) -> Float {
+	let weight = float(token_weight_in) / float(token_total_weight);
+	let ratio = float(pool_amount_out) / float(pool_supply);
+	float(token_balance_in) * (ratio.ln_1p() / weight.clone()).exp_m1() / (1 - (1 - weight) * fee_ratio(swap_fee))
+}
+
+// the expected values are computed with 60 significant digits, and the rows with a `regression`
+// note pin the bugs found by the properties below
+#[test]
+fn balancer_math_should_match_fixtures() {
+	let path = concat!(env!("CARGO_MANIFEST_DIR"), "/test_data/balancer_math.json");
+	let fixtures: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap();
+
+	for fixture in fixtures.as_array().unwrap() {
+		let function = fixture["function"].as_str().unwrap();
+		let args: Vec<u64> = fixture["args"].as_array().unwrap().iter().map(|arg| arg.as_u64().unwrap()).collect();
+		let expected = Float::with_val(REFERENCE_PRECISION, Float::parse(fixture["expected"].as_str().unwrap()).unwrap());
+
+		let actual = match function {
+			"out_given_in" => Swap::calculate_out_given_in(args[0], args[1], args[2], args[3], args[4], args[5]),
+			"in_given_out" => Swap::calculate_in_given_out(args[0], args[1], args[2], args[3], args[4], args[5]),
+			"pool_out_given_single_in" => {
+				Swap::calculate_pool_out_given_single_in(args[0], args[1], args[2], args[3], args[4], args[5])
+			}
+			"single_in_given_pool_out" => {
+				Swap::calculate_single_in_given_pool_out(args[0], args[1], args[2], args[3], args[4], args[5])
+			}
+			_ => panic!("unknown function {} in the fixtures", function),
+		};
+		let actual = actual.unwrap();
+		assert!(is_close(&expected, actual), "{}{:?}: expected {}, got {}", function, args, expected, actual);
+	}
+}
+
+proptest! {
+	// failing cases are kept next to the fixtures, and replayed first on the next run
+	#![proptest_config(ProptestConfig {
+		failure_persistence: Some(Box::new(FileFailurePersistence::Direct("test_data/balancer_math.proptest-regressions"))),
+		..ProptestConfig::default()
+	})]
+
+	#[test]
+	fn swap_math_should_match_reference_and_round_trip(
+		(balance_in, amount_in) in balance_and_amount(2),
+		balance_out in BALANCES,
+		weight_in in WEIGHTS,
+		weight_out in WEIGHTS,
+		swap_fee in SWAP_FEES
+	) {
+		let amount_out = Swap::calculate_out_given_in(balance_in, weight_in, amount_in, balance_out, weight_out, swap_fee).unwrap();
+		let expected = reference_out_given_in(balance_in, weight_in, amount_in, balance_out, weight_out, swap_fee);
+		prop_assert!(is_close(&expected, amount_out), "expected {}, got {}", expected, amount_out);
+		let amount_out = Swap::convert_float(amount_out).unwrap();
+		prop_assert!(amount_out < balance_out);
+
+		// buying back what was swapped out costs no more than what was paid
+		let amount_in_needed = Swap::calculate_in_given_out(balance_in, weight_in, balance_out, weight_out, amount_out, swap_fee).unwrap();
+		let expected = reference_in_given_out(balance_in, weight_in, balance_out, weight_out, amount_out, swap_fee);
+		prop_assert!(is_close(&expected, amount_in_needed), "expected {}, got {}", expected, amount_in_needed);
+		prop_assert!(from_fixed(amount_in_needed) <= float(amount_in) * (1.0 + MATH_TOLERANCE));
+	}
+
+	#[test]
+	fn swap_math_should_be_monotonic(
+		(balance_in, amount_in) in balance_and_amount(4),
+		(balance_out, amount_out) in balance_and_amount(100),
+		weight_in in WEIGHTS,
+		weight_out in WEIGHTS,
+		swap_fee in SWAP_FEES,
+		extra in 1u64..=1_000
+	) {
+		let out_given_in = |amount_in, swap_fee| {
+			Swap::calculate_out_given_in(balance_in, weight_in, amount_in, balance_out, weight_out, swap_fee).unwrap()
+		};
+		let in_given_out = |amount_out| {
+			Swap::calculate_in_given_out(balance_in, weight_in, balance_out, weight_out, amount_out, swap_fee).unwrap()
+		};
+
+		// paying more gets more, a higher fee gets less
+		prop_assert!(out_given_in(amount_in, swap_fee) <= out_given_in(amount_in + amount_in * extra / 1_000, swap_fee));
+		prop_assert!(out_given_in(amount_in, swap_fee + extra) <= out_given_in(amount_in, swap_fee));
+		// getting more costs more
+		prop_assert!(in_given_out(amount_out) <= in_given_out(amount_out + amount_out * extra / 1_000));
+	}
+
+	#[test]
+	fn single_asset_math_should_match_reference_and_not_leak_value(
+		(balance_in, amount_in) in balance_and_amount(2),
+		weight_in in WEIGHTS,
+		other_weight in WEIGHTS,
+		pool_supply in BALANCES,
+		swap_fee in SWAP_FEES
+	) {
+		let total_weight = weight_in + other_weight;
+		let pool_out = Swap::calculate_pool_out_given_single_in(balance_in, weight_in, amount_in, total_weight, pool_supply, swap_fee).unwrap();
+		let expected = reference_pool_out_given_single_in(balance_in, weight_in, amount_in, total_weight, pool_supply, swap_fee);
+		prop_assert!(is_close(&expected, pool_out), "expected {}, got {}", expected, pool_out);
+		let pool_out = Swap::convert_float(pool_out).unwrap();
+
+		// joining for the issued pool tokens costs no more than what was paid
+		let amount_in_needed = Swap::calculate_single_in_given_pool_out(balance_in, weight_in, total_weight, pool_out, pool_supply, swap_fee).unwrap();
+		let expected = reference_single_in_given_pool_out(balance_in, weight_in, total_weight, pool_out, pool_supply, swap_fee);
+		prop_assert!(is_close(&expected, amount_in_needed), "expected {}, got {}", expected, amount_in_needed);
+		prop_assert!(from_fixed(amount_in_needed) <= float(amount_in) * (1.0 + MATH_TOLERANCE));
+
+		// exiting right after joining gives back no more than what was paid
+		let amount_out = Swap::calculate_single_out_given_pool_in(
+			weight_in,
+			pool_out,
+			total_weight,
+			balance_in + amount_in,
+			pool_supply + pool_out,
+			swap_fee,
+			0,
+		).unwrap();
+		prop_assert!(Swap::convert_float(amount_out).unwrap() <= amount_in);
+	}
+
+	#[test]
+	fn single_asset_math_should_be_monotonic(
+		(balance_in, amount_in) in balance_and_amount(4),
+		weight_in in WEIGHTS,
+		other_weight in WEIGHTS,
+		pool_supply in BALANCES,
+		swap_fee in SWAP_FEES,
+		extra in 1u64..=1_000
+	) {
+		let total_weight = weight_in + other_weight;
+		let pool_out_given_single_in = |amount_in| {
+			Swap::calculate_pool_out_given_single_in(balance_in, weight_in, amount_in, total_weight, pool_supply, swap_fee).unwrap()
+		};
+		let single_in_given_pool_out = |pool_out| {
+			Swap::calculate_single_in_given_pool_out(balance_in, weight_in, total_weight, pool_out, pool_supply, swap_fee).unwrap()
+		};
+
+		// depositing more issues more, issuing more costs more
+		let pool_out = pool_out_given_single_in(amount_in);
+		prop_assert!(pool_out <= pool_out_given_single_in(amount_in + amount_in * extra / 1_000));
+		let pool_out = Swap::convert_float(pool_out).unwrap();
+		prop_assert!(single_in_given_pool_out(pool_out) <= single_in_given_pool_out(pool_out + pool_out * extra / 1_000 + 1));
+	}
+}
+
 #[test]
 fn add_liquidity_should_work() {
 	new_test_ext().execute_with(|| {
@@ -191,8 +422,7 @@ fn add_liquidity_should_work() {
 			(TokenSymbol::DOT, 1000, 1),
 			(TokenSymbol::KSM, 1000, 49)
 		];
-		let original_pool = (raw_pool, 0);
-		<GlobalPool<Test>>::put(original_pool);
+		let pool_id = 0;
 
 		// issue a vtoken to alice
 		let alice = 1u64;
@@ -200,24 +430,24 @@ fn add_liquidity_should_work() {
 		let precise = 4;
 		let dot_token_amount = 10000;
 
-		assert_ok!(Assets::create(Origin::root(), b"aUSD".to_vec(), 18)); // let dot start from 1
+		assert_ok!(Assets::create(Origin::root(), b"aUSD".to_vec(), 18, None)); // let dot start from 1
 
 		// issue dot token
-		assert_ok!(assets::Module::<Test>::create(Origin::root(), dot_symbol, precise));
+		assert_ok!(assets::Module::<Test>::create(Origin::root(), dot_symbol, precise, None));
 		let dot_id = Assets::next_asset_id() - 1;
 		let dot_type = TokenSymbol::from(dot_id);
 
 		assert_ok!(assets::Module::<Test>::issue(Origin::root(), dot_type, alice, dot_token_amount));
 		assert_eq!(<assets::AccountAssets<Test>>::get((dot_type, alice)).balance, dot_token_amount);
 
-		assert_ok!(Assets::create(Origin::root(), b"vDOT".to_vec(), 12)); // skip vDOT
+		assert_ok!(Assets::create(Origin::root(), b"vDOT".to_vec(), 12, None)); // skip vDOT
 
 		// issue ksm token
 		let ksm_symbol = b"KSM".to_vec();
 		let precise = 4;
 		let ksm_token_amount = 100000;
 
-		assert_ok!(assets::Module::<Test>::create(Origin::root(), ksm_symbol, precise));
+		assert_ok!(assets::Module::<Test>::create(Origin::root(), ksm_symbol, precise, None));
 		let ksm_id = Assets::next_asset_id() - 1;
 		let ksm_type = TokenSymbol::from(ksm_id);
 
@@ -226,41 +456,80 @@ fn add_liquidity_should_work() {
 
 		// set swap fee
 		let fee = 100;
-		<SwapFee<Test>>::put(fee);
-		assert_eq!(<SwapFee<Test>>::get(), fee);
 
 		// issue intialized pool token
 		let pool_token = 1000;
-		<BalancerPoolToken<Test>>::put(pool_token);
+
+		// create the pool, alice holds the initialized pool token
+		assert_eq!(Swap::insert_pool(alice, raw_pool, fee, pool_token), Ok(pool_id));
 
 		// add liquidity less than MinimumBalance
 		assert_eq!(
-			Swap::add_liquidity(Origin::signed(alice), 9),
+			Swap::add_liquidity(Origin::signed(alice), pool_id, 9, None),
 			Err(DispatchError::Module { index: 0, error: 5, message: Some("LessThanMinimumBalance") })
 		);
 
 		// first time to deposit to pool
 		let new_pool_token = 10;
-		assert_ok!(Swap::add_liquidity(Origin::signed(alice), new_pool_token));
-		assert_eq!(<assets::AccountAssets<Test>>::get((dot_type, alice)).balance, dot_token_amount - 100);
-		assert_eq!(<assets::AccountAssets<Test>>::get((ksm_type, alice)).balance, ksm_token_amount - 1000);
+		assert_ok!(Swap::add_liquidity(Origin::signed(alice), pool_id, new_pool_token, None));
+		assert_eq!(<assets::AccountAssets<Test>>::get((dot_type, alice)).balance, dot_token_amount - 10);
+		assert_eq!(<assets::AccountAssets<Test>>::get((ksm_type, alice)).balance, ksm_token_amount - 10);
 
-		let gpool = <GlobalPool<Test>>::get();
-		let target = vec![1100u64, 2000];
-		for (p, t) in gpool.0.iter().zip(target.iter()) {
+		let gpool = Swap::pools(pool_id).unwrap();
+		let target = vec![1010u64, 1010];
+		for (p, t) in gpool.tokens.iter().zip(target.iter()) {
 			assert_eq!(p.1, *t);
 		}
 
 		// continue to add liuquidity
-		assert_ok!(Swap::add_liquidity(Origin::signed(alice), new_pool_token));
-		let gpool = <GlobalPool<Test>>::get();
-		let target = vec![1198u64, 2980];
-		for (p, t) in gpool.0.iter().zip(target.iter()) {
+		assert_ok!(Swap::add_liquidity(Origin::signed(alice), pool_id, new_pool_token, None));
+		let gpool = Swap::pools(pool_id).unwrap();
+		let target = vec![1020u64, 1020];
+		for (p, t) in gpool.tokens.iter().zip(target.iter()) {
 			assert_eq!(p.1, *t);
 		}
 	});
 }
 
+#[test]
+fn add_liquidity_should_cost_the_same_for_the_same_shares() {
+	new_test_ext().execute_with(|| {
+		run_to_block(2);
+
+		let (alice, bob, charlie) = (1u64, 2u64, 3u64);
+
+		assert_ok!(Assets::create(Origin::root(), b"aUSD".to_vec(), 18, None)); // let dot start from 1
+		assert_ok!(Assets::create(Origin::root(), b"DOT".to_vec(), 12, None));
+		let dot_type = TokenSymbol::from(Assets::next_asset_id() - 1);
+		assert_ok!(Assets::create(Origin::root(), b"KSM".to_vec(), 12, None));
+		let ksm_type = TokenSymbol::from(Assets::next_asset_id() - 1);
+
+		assert_ok!(Assets::issue(Origin::root(), dot_type, alice, 1_000_000));
+		assert_ok!(Assets::issue(Origin::root(), ksm_type, alice, 1_000_000));
+		assert_ok!(Swap::create_pool(Origin::signed(alice), vec![(dot_type, 100_000, 1), (ksm_type, 99_999, 1)], 100));
+		let pool_id = 0;
+
+		// bob holds far less than charlie, but the same shares cost both of them the same
+		for (who, amount) in vec![(bob, 2_000), (charlie, 500_000)] {
+			assert_ok!(Assets::issue(Origin::root(), dot_type, who, amount));
+			assert_ok!(Assets::issue(Origin::root(), ksm_type, who, amount));
+		}
+		assert_ok!(Swap::add_liquidity(Origin::signed(bob), pool_id, 10, None));
+		assert_ok!(Swap::add_liquidity(Origin::signed(charlie), pool_id, 10, None));
+
+		// 10 / 1000 of 99_999 ksm is rounded up
+		assert_eq!(<assets::AccountAssets<Test>>::get((dot_type, bob)).balance, 1_000);
+		assert_eq!(<assets::AccountAssets<Test>>::get((ksm_type, bob)).balance, 1_000);
+		assert_eq!(<assets::AccountAssets<Test>>::get((dot_type, charlie)).balance, 499_000);
+		assert_eq!(<assets::AccountAssets<Test>>::get((ksm_type, charlie)).balance, 499_000);
+
+		let pool = Swap::pools(pool_id).unwrap();
+		assert_eq!(pool.pool_supply, 1_020);
+		let balances: Vec<u64> = pool.tokens.iter().map(|p| p.1).collect();
+		assert_eq!(balances, vec![102_000, 101_999]);
+	});
+}
+
 #[test]
 fn add_single_liquidity_should_work() {
 	new_test_ext().execute_with(|| {
@@ -271,8 +540,7 @@ fn add_single_liquidity_should_work() {
 			(TokenSymbol::DOT, 1000, 1),
 			(TokenSymbol::KSM, 1000, 49)
 		];
-		let original_pool = (raw_pool, 0);
-		<GlobalPool<Test>>::put(original_pool);
+		let pool_id = 0;
 
 		// issue a vtoken to alice
 		let alice = 1u64;
@@ -280,67 +548,65 @@ fn add_single_liquidity_should_work() {
 		let precise = 4;
 		let dot_token_amount = 1000;
 
-		assert_ok!(Assets::create(Origin::root(), b"aUSD".to_vec(), 18)); // let dot start from 1
+		assert_ok!(Assets::create(Origin::root(), b"aUSD".to_vec(), 18, None)); // let dot start from 1
 
 		// issue dot token
-		assert_ok!(assets::Module::<Test>::create(Origin::root(), dot_symbol, precise));
+		assert_ok!(assets::Module::<Test>::create(Origin::root(), dot_symbol, precise, None));
 		let dot_id = Assets::next_asset_id() - 1;
 		let dot_type = TokenSymbol::from(dot_id);
 
 		assert_ok!(assets::Module::<Test>::issue(Origin::root(), dot_type, alice, dot_token_amount));
 		assert_eq!(<assets::AccountAssets<Test>>::get((dot_type, alice)).balance, dot_token_amount);
 
-		assert_ok!(Assets::create(Origin::root(), b"vDOT".to_vec(), 12)); // skip vDOT
+		assert_ok!(Assets::create(Origin::root(), b"vDOT".to_vec(), 12, None)); // skip vDOT
 
 		// create ksm token, but issue nothing
 		let ksm_symbol = b"KSM".to_vec();
-		assert_ok!(assets::Module::<Test>::create(Origin::root(), ksm_symbol, precise));
+		assert_ok!(assets::Module::<Test>::create(Origin::root(), ksm_symbol, precise, None));
 		let ksm_id = Assets::next_asset_id() - 1;
 		let ksm_type = TokenSymbol::from(ksm_id);
 
 		// set swap fee
 		let fee = 100;
-		<SwapFee<Test>>::put(fee);
-		assert_eq!(<SwapFee<Test>>::get(), fee);
 
 		// issue intialized pool token
 		let pool_token = 1000;
-		<BalancerPoolToken<Test>>::put(pool_token);
 
-		// set weight
-		let total_weight = 50;
-		<TotalWeight::<Test>>::put(total_weight);
+		// create the pool, alice holds the initialized pool token
+		assert_eq!(Swap::insert_pool(alice, raw_pool, fee, pool_token), Ok(pool_id));
 
 		let token_amount_in = 100;
 
 		// add a token alice doesn't have
 		assert_eq!(
-			Swap::add_single_liquidity(Origin::signed(alice), TokenSymbol::IOST, token_amount_in),
+			Swap::add_single_liquidity(Origin::signed(alice), pool_id, TokenSymbol::IOST, token_amount_in, None),
 			Err(DispatchError::Module { index: 0, error: 0, message: Some("TokenNotExist") })
 		);
 
 		// test with a created token but with 0 balance
 		assert_eq!(
-			Swap::add_single_liquidity(Origin::signed(alice), ksm_type, token_amount_in),
+			Swap::add_single_liquidity(Origin::signed(alice), pool_id, ksm_type, token_amount_in, None),
 			Err(DispatchError::Module { index: 0, error: 1, message: Some("NotEnoughBalance") })
 		);
 
 		assert_eq!(
-			Swap::add_single_liquidity(Origin::signed(alice), dot_type, dot_token_amount + 1),
+			Swap::add_single_liquidity(Origin::signed(alice), pool_id, dot_type, dot_token_amount + 1, None),
 			Err(DispatchError::Module { index: 0, error: 1, message: Some("NotEnoughBalance") })
 		);
 
 		// first time to add liquidity
-		assert_ok!(Swap::add_single_liquidity(Origin::signed(alice), dot_type, token_amount_in));
+		assert_ok!(Swap::add_single_liquidity(Origin::signed(alice), pool_id, dot_type, token_amount_in, None));
 		assert_eq!(<assets::AccountAssets<Test>>::get((dot_type, alice)).balance, dot_token_amount - 100);
-		assert_eq!(<BalancerPoolToken<Test>>::get(), 1001);
-		assert_eq!(<UserSinglePool<Test>>::get((alice, dot_type)), (100, 1));
+		assert_eq!(Swap::pools(pool_id).unwrap().pool_supply, 1001);
+		let share_token = Swap::pool_token(pool_id).unwrap();
+		assert_eq!(<assets::AccountAssets<Test>>::get((share_token, alice)).balance, 1001);
 
 		// continue to add liuquidity
-		assert_ok!(Swap::add_single_liquidity(Origin::signed(alice), dot_type, token_amount_in));
+		assert_ok!(Swap::add_single_liquidity(Origin::signed(alice), pool_id, dot_type, token_amount_in, None));
 		assert_eq!(<assets::AccountAssets<Test>>::get((dot_type, alice)).balance, dot_token_amount - 200);
-		assert_eq!(<BalancerPoolToken<Test>>::get(), 1002);
-		assert_eq!(<UserSinglePool<Test>>::get((alice, dot_type)), (200, 2));
+		assert_eq!(Swap::pools(pool_id).unwrap().pool_supply, 1002);
+		assert_eq!(<assets::AccountAssets<Test>>::get((share_token, alice)).balance, 1002);
+		assert_eq!(Assets::token_details(share_token).total_supply, 1002);
 	});
 }
 
@@ -354,8 +620,7 @@ fn swap_should_work() {
 			(TokenSymbol::DOT, 0, 1),
 			(TokenSymbol::KSM, 0, 49)
 		];
-		let original_pool = (raw_pool, 0);
-		<GlobalPool<Test>>::put(original_pool);
+		let pool_id = 0;
 
 		// issue a vtoken to alice
 		let alice = 1u64;
@@ -363,10 +628,10 @@ fn swap_should_work() {
 		let precise = 4;
 		let dot_token_amount = 1_000_000;
 
-		assert_ok!(Assets::create(Origin::root(), b"aUSD".to_vec(), 18)); // let dot start from 1
+		assert_ok!(Assets::create(Origin::root(), b"aUSD".to_vec(), 18, None)); // let dot start from 1
 
 		// issue dot token
-		assert_ok!(assets::Module::<Test>::create(Origin::root(), dot_symbol, precise));
+		assert_ok!(assets::Module::<Test>::create(Origin::root(), dot_symbol, precise, None));
 		let dot_id = Assets::next_asset_id() - 1;
 		let dot_type = TokenSymbol::from(dot_id);
 
@@ -374,12 +639,12 @@ fn swap_should_work() {
 		assert_ok!(assets::Module::<Test>::issue(Origin::root(), dot_type, alice, dot_token_amount));
 		assert_eq!(<assets::AccountAssets<Test>>::get((dot_type, alice)).balance, dot_token_amount);
 
-		assert_ok!(Assets::create(Origin::root(), b"vDOT".to_vec(), 12)); // skip vDOT
+		assert_ok!(Assets::create(Origin::root(), b"vDOT".to_vec(), 12, None)); // skip vDOT
 
 		// create ksm token
 		let ksm_symbol = b"KSM".to_vec();
 		let ksm_token_amount = 1_000_000;
-		assert_ok!(assets::Module::<Test>::create(Origin::root(), ksm_symbol, precise));
+		assert_ok!(assets::Module::<Test>::create(Origin::root(), ksm_symbol, precise, None));
 		let ksm_id = Assets::next_asset_id() - 1;
 		let ksm_type = TokenSymbol::from(ksm_id);
 
@@ -388,102 +653,103 @@ fn swap_should_work() {
 
 		// set swap fee
 		let fee = 100; // 0.1%
-		<SwapFee<Test>>::put(fee);
-		assert_eq!(<SwapFee<Test>>::get(), fee);
 
 		// issue intialized pool token
 		let pool_token = 1000;
-		<BalancerPoolToken<Test>>::put(pool_token);
 
-		// init reward pool
-		let reward = vec![
-			(dot_type, 0),
-			(ksm_type, 0),
-		];
-		<SharedRewardPool::<Test>>::put(reward);
+		// create the pool, alice holds the initialized pool token
+		assert_eq!(Swap::insert_pool(alice, raw_pool, fee, pool_token), Ok(pool_id));
 
 		// trade with the same token
 		assert_eq!(
-			Swap::swap(Origin::signed(alice), dot_type, 700, None, dot_type, None),
+			Swap::swap(Origin::signed(alice), pool_id, dot_type, 700, None, dot_type, None, None),
 			Err(DispatchError::Module { index: 0, error: 9, message: Some("ForbidSameTokenSwap") })
 		);
 
 		// trade amount bigger alice has
 		assert_eq!(
-			Swap::swap(Origin::signed(alice), dot_type, dot_token_amount + 1, None, ksm_type, None),
+			Swap::swap(Origin::signed(alice), pool_id, dot_type, dot_token_amount + 1, None, ksm_type, None, None),
 			Err(DispatchError::Module { index: 0, error: 1, message: Some("NotEnoughBalance") })
 		);
 
 		// trade more than half of all amount
 		assert_eq!(
-			Swap::swap(Origin::signed(alice), dot_type, 700_000, None, ksm_type, None),
+			Swap::swap(Origin::signed(alice), pool_id, dot_type, 700_000, None, ksm_type, None, None),
 			Err(DispatchError::Module { index: 0, error: 11, message: Some("ExceedMaximumSwapInRatio") })
 		);
 
 		// add liquidity
 		let new_pool_token = 500;
-		assert_ok!(Swap::add_liquidity(Origin::signed(alice), new_pool_token));
+		assert_ok!(Swap::add_liquidity(Origin::signed(alice), pool_id, new_pool_token, None));
 		assert_eq!(<assets::AccountAssets<Test>>::get((dot_type, alice)).balance, dot_token_amount / 2);
 		assert_eq!(<assets::AccountAssets<Test>>::get((ksm_type, alice)).balance, ksm_token_amount / 2);
 
-		let alice_pool = <UserPool::<Test>>::get(alice);
-		assert_eq!(alice_pool.0[0].1, dot_token_amount / 2);
-		assert_eq!(alice_pool.0[1].1, ksm_token_amount / 2);
-		assert_eq!(alice_pool.1, new_pool_token);
+		let share_token = Swap::pool_token(pool_id).unwrap();
+		assert_eq!(<assets::AccountAssets<Test>>::get((share_token, alice)).balance, new_pool_token + pool_token);
 
-		let gpool = <GlobalPool<Test>>::get();
+		let gpool = Swap::pools(pool_id).unwrap();
 		let expected = vec![
 			(dot_type, 500000, 1),
 			(ksm_type, 500000, 49)
 		];
-		assert_eq!(gpool.0, expected);
-		assert_eq!(<BalancerPoolToken<Test>>::get(), new_pool_token + pool_token);
+		assert_eq!(gpool.tokens, expected);
+		assert_eq!(Swap::pools(pool_id).unwrap().pool_supply, new_pool_token + pool_token);
 
 		// do a trade
-		assert_ok!(Swap::swap(Origin::signed(alice), dot_type, 5000, None, ksm_type, None));
+		assert_ok!(Swap::swap(Origin::signed(alice), pool_id, dot_type, 5000, None, ksm_type, None, None));
 		// assert charged fee
-		assert_eq!(<SharedRewardPool::<Test>>::get()[0], (dot_type, 5));
+		assert_eq!(Swap::pools(pool_id).unwrap().rewards[0], (dot_type, 5));
 		// global pool check
-		let gpool = <GlobalPool<Test>>::get();
+		let gpool = Swap::pools(pool_id).unwrap();
 		let expected = vec![
 			(dot_type, 505000, 1),
 			(ksm_type, 499899, 49) // should be 500000 - 100.51453390162312, but lost precision
 		];
-		assert_eq!(gpool.0, expected);
-		// user pool check
-		let user_pool = <UserPool::<Test>>::get(alice);
-		assert_eq!(user_pool.1, new_pool_token);
-		let expected = vec![
-			(dot_type, 505000),
-			(ksm_type, 499899) // should be 500000 - 100.51453390162312, but lost precision
-		];
-		assert_eq!(user_pool.0, expected);
+		assert_eq!(gpool.tokens, expected);
+		// pool tokens are untouched by trades
+		assert_eq!(<assets::AccountAssets<Test>>::get((share_token, alice)).balance, new_pool_token + pool_token);
 
 		// check alice account after trade
 		assert_eq!(<assets::AccountAssets<Test>>::get((dot_type, alice)).balance, dot_token_amount / 2 - 5000);
 
 		// swap back
-		assert_ok!(Swap::swap(Origin::signed(alice), ksm_type, 101, None, dot_type, None));
-		let gpool = <GlobalPool<Test>>::get();
+		assert_ok!(Swap::swap(Origin::signed(alice), pool_id, ksm_type, 101, None, dot_type, None, None));
+		let gpool = Swap::pools(pool_id).unwrap();
 		let expected = vec![
 			(dot_type, 500031, 1),
 			(ksm_type, 500000, 49) // losing precision causes this problem
 		];
-		assert_eq!(gpool.0, expected);
+		assert_eq!(gpool.tokens, expected);
+
+		// quit from a third of liquidity
+		assert_ok!(Swap::remove_assets_liquidity(Origin::signed(alice), pool_id, new_pool_token, None));
+		assert_eq!(Swap::pools(pool_id).unwrap().rewards[0], (dot_type, 4));
+
+		let gpool = Swap::pools(pool_id).unwrap();
+		let expected = vec![
+			(dot_type, 333354, 1), // 500031 - 500031 / 3
+			(ksm_type, 333334, 49)
+		];
+		assert_eq!(gpool.tokens, expected);
+		assert_eq!(gpool.pool_supply, pool_token);
 
-		// quit from liquidity
-		assert_ok!(Swap::remove_assets_liquidity(Origin::signed(alice), new_pool_token));
-		assert_eq!(<SharedRewardPool::<Test>>::get()[0], (dot_type, 4));
+		// int(5/3) = 1 is reward
+		assert_eq!(<assets::AccountAssets<Test>>::get((dot_type, alice)).balance, 499969 + 166677 + 1);
 
-		let gpool = <GlobalPool<Test>>::get();
+		// quit from the rest
+		assert_ok!(Swap::remove_assets_liquidity(Origin::signed(alice), pool_id, pool_token, None));
+		let gpool = Swap::pools(pool_id).unwrap();
 		let expected = vec![
 			(dot_type, 0, 1),
 			(ksm_type, 0, 49)
 		];
-		assert_eq!(gpool.0, expected);
+		assert_eq!(gpool.tokens, expected);
+		assert_eq!(gpool.pool_supply, 0);
+		assert_eq!(Assets::token_details(share_token).total_supply, 0);
 
-		// int(5/3) = 1 is reward
-		assert_eq!(<assets::AccountAssets<Test>>::get((dot_type, alice)).balance, dot_token_amount + 1);
+		// alice is the only provider, all the rewards are hers
+		assert_eq!(<assets::AccountAssets<Test>>::get((dot_type, alice)).balance, dot_token_amount + 5);
+		assert_eq!(Swap::check_integrity(), vec![]);
 	});
 }
 
@@ -497,8 +763,7 @@ fn remove_single_liquidity_should_work() {
 			(TokenSymbol::DOT, 0, 1),
 			(TokenSymbol::KSM, 0, 49)
 		];
-		let original_pool = (raw_pool, 0);
-		<GlobalPool<Test>>::put(original_pool);
+		let pool_id = 0;
 
 		// issue a vtoken to alice
 		let alice = 1u64;
@@ -509,10 +774,10 @@ fn remove_single_liquidity_should_work() {
 		let precise = 4;
 		let dot_token_amount = 1_000_000;
 
-		assert_ok!(Assets::create(Origin::root(), b"aUSD".to_vec(), 18)); // let dot start from 1
+		assert_ok!(Assets::create(Origin::root(), b"aUSD".to_vec(), 18, None)); // let dot start from 1
 
 		// issue dot token
-		assert_ok!(assets::Module::<Test>::create(Origin::root(), dot_symbol, precise));
+		assert_ok!(assets::Module::<Test>::create(Origin::root(), dot_symbol, precise, None));
 		let dot_id = Assets::next_asset_id() - 1;
 		let dot_type = TokenSymbol::from(dot_id);
 
@@ -520,12 +785,12 @@ fn remove_single_liquidity_should_work() {
 		assert_ok!(assets::Module::<Test>::issue(Origin::root(), dot_type, alice, dot_token_amount));
 		assert_eq!(<assets::AccountAssets<Test>>::get((dot_type, alice)).balance, dot_token_amount);
 
-		assert_ok!(Assets::create(Origin::root(), b"vDOT".to_vec(), 12)); // skip vDOT
+		assert_ok!(Assets::create(Origin::root(), b"vDOT".to_vec(), 12, None)); // skip vDOT
 
 		// create ksm token
 		let ksm_symbol = b"KSM".to_vec();
 		let ksm_token_amount = 1_000_000;
-		assert_ok!(assets::Module::<Test>::create(Origin::root(), ksm_symbol, precise));
+		assert_ok!(assets::Module::<Test>::create(Origin::root(), ksm_symbol, precise, None));
 		let ksm_id = Assets::next_asset_id() - 1;
 		let ksm_type = TokenSymbol::from(ksm_id);
 
@@ -539,66 +804,52 @@ fn remove_single_liquidity_should_work() {
 
 		// set swap fee
 		let fee = 1000; // 1%
-		<SwapFee<Test>>::put(fee);
-		assert_eq!(<SwapFee<Test>>::get(), fee);
 
 		// issue intialized pool token
 		let pool_token = 1000;
-		<BalancerPoolToken<Test>>::put(pool_token);
-
-		// set weight
-		let total_weight = 50;
-		<TotalWeight::<Test>>::put(total_weight);
 
-		// init reward pool
-		let reward = vec![
-			(dot_type, 0),
-			(ksm_type, 0),
-		];
-		<SharedRewardPool::<Test>>::put(reward);
+		// create the pool, alice holds the initialized pool token
+		assert_eq!(Swap::insert_pool(alice, raw_pool, fee, pool_token), Ok(pool_id));
 
 		// add liquidity
 		let new_pool_token = 500;
-		assert_ok!(Swap::add_liquidity(Origin::signed(alice), new_pool_token));
+		assert_ok!(Swap::add_liquidity(Origin::signed(alice), pool_id, new_pool_token, None));
 		assert_eq!(<assets::AccountAssets<Test>>::get((dot_type, alice)).balance, dot_token_amount / 2);
 		assert_eq!(<assets::AccountAssets<Test>>::get((ksm_type, alice)).balance, ksm_token_amount / 2);
-		assert_eq!(<BalancerPoolToken<Test>>::get(), 1500);
+		assert_eq!(Swap::pools(pool_id).unwrap().pool_supply, 1500);
 
 		let token_amount_in = 100000;
 		// bob wants to add single liquidity
-		assert_ok!(Swap::add_single_liquidity(Origin::signed(bob), dot_type, token_amount_in));
+		assert_ok!(Swap::add_single_liquidity(Origin::signed(bob), pool_id, dot_type, token_amount_in, None));
 		assert_eq!(<assets::AccountAssets<Test>>::get((dot_type, bob)).balance, dot_token_amount - token_amount_in);
-		assert_eq!(<BalancerPoolToken<Test>>::get(), 1505); // lose precision, 1500 + 5.4796312543396398422
-		assert_eq!(<UserSinglePool<Test>>::get((bob, dot_type)), (token_amount_in, 5)); // lose precision
+		assert_eq!(Swap::pools(pool_id).unwrap().pool_supply, 1505); // lose precision, 1500 + 5.4796312543396398422
+		let share_token = Swap::pool_token(pool_id).unwrap();
+		assert_eq!(<assets::AccountAssets<Test>>::get((share_token, bob)).balance, 5); // lose precision
 
 		// bob doesn't have this token in pool
 		assert_eq!(
-			Swap::remove_single_asset_liquidity(Origin::signed(jim), dot_type, 13),
+			Swap::remove_single_asset_liquidity(Origin::signed(jim), pool_id, dot_type, 13, None),
 			Err(DispatchError::Module { index: 0, error: 7, message: Some("NotExistedCurrentSinglePool") })
 		);
-		// alice doesn't have vdot
+		// bob redeems too much
 		assert_eq!(
-			Swap::remove_single_asset_liquidity(Origin::signed(alice), dot_type, 13),
-			Err(DispatchError::Module { index: 0, error: 7, message: Some("NotExistedCurrentSinglePool") })
-		);
-		// alice redeems too much
-		assert_eq!(
-			Swap::remove_single_asset_liquidity(Origin::signed(bob), dot_type, 5 + 1),
+			Swap::remove_single_asset_liquidity(Origin::signed(bob), pool_id, dot_type, 5 + 1, None),
 			Err(DispatchError::Module { index: 0, error: 1, message: Some("NotEnoughBalance") })
 		);
 
 		// do a swap
-		assert_ok!(Swap::swap(Origin::signed(alice), dot_type, 1000, None, ksm_type, None));
+		assert_ok!(Swap::swap(Origin::signed(alice), pool_id, dot_type, 1000, None, ksm_type, None, None));
 		// check alice gets how many ksm
 		assert_eq!(<assets::AccountAssets<Test>>::get((ksm_type, alice)).balance, 500016); // lose precision
-		assert_eq!(<SharedRewardPool::<Test>>::get()[0].1, 10);
+		assert_eq!(Swap::pools(pool_id).unwrap().rewards[0].1, 10);
 
 		// remove liqudity
-		assert_ok!(Swap::remove_single_asset_liquidity(Origin::signed(bob), dot_type, 5));
-		assert_eq!(<SharedRewardPool::<Test>>::get()[0].1, 10);
+		assert_ok!(Swap::remove_single_asset_liquidity(Origin::signed(bob), pool_id, dot_type, 5, None));
+		assert_eq!(Swap::pools(pool_id).unwrap().rewards[0].1, 10);
 		assert_eq!(<assets::AccountAssets<Test>>::get((dot_type, bob)).balance, 992123); // lose precision
-		assert_eq!(<UserSinglePool<Test>>::get((bob, dot_type)), (7877, 0)); // 999951 + 49 == 1_000_000
-		assert_eq!(<BalancerPoolToken<Test>>::get(), 1500);
+		assert_eq!(<assets::AccountAssets<Test>>::get((share_token, bob)).balance, 0);
+		assert_eq!(Swap::pools(pool_id).unwrap().pool_supply, 1500);
+		assert_eq!(Swap::check_integrity(), vec![]);
 	});
 }
 
@@ -612,8 +863,7 @@ fn remove_assets_liquidity_should_work() {
 			(TokenSymbol::DOT, 0, 1),
 			(TokenSymbol::KSM, 0, 49)
 		];
-		let original_pool = (raw_pool, 0);
-		<GlobalPool<Test>>::put(original_pool);
+		let pool_id = 0;
 
 		// issue a vtoken to alice
 		let alice = 1u64;
@@ -623,22 +873,22 @@ fn remove_assets_liquidity_should_work() {
 		let precise = 4;
 		let dot_token_amount = 10_000;
 
-		assert_ok!(Assets::create(Origin::root(), b"aUSD".to_vec(), 18)); // let dot start from 1
+		assert_ok!(Assets::create(Origin::root(), b"aUSD".to_vec(), 18, None)); // let dot start from 1
 
 		// issue dot token
-		assert_ok!(assets::Module::<Test>::create(Origin::root(), dot_symbol, precise));
+		assert_ok!(assets::Module::<Test>::create(Origin::root(), dot_symbol, precise, None));
 		let dot_id = Assets::next_asset_id() - 1;
 		let dot_type = TokenSymbol::from(dot_id);
 
 		assert_ok!(assets::Module::<Test>::issue(Origin::root(), dot_type, alice, dot_token_amount));
 		assert_eq!(<assets::AccountAssets<Test>>::get((dot_type, alice)).balance, dot_token_amount);
 
-		assert_ok!(Assets::create(Origin::root(), b"vDOT".to_vec(), 12)); // skip vDOT
+		assert_ok!(Assets::create(Origin::root(), b"vDOT".to_vec(), 12, None)); // skip vDOT
 
 		// create ksm token
 		let ksm_symbol = b"KSM".to_vec();
 		let ksm_token_amount = 100_000;
-		assert_ok!(assets::Module::<Test>::create(Origin::root(), ksm_symbol, precise));
+		assert_ok!(assets::Module::<Test>::create(Origin::root(), ksm_symbol, precise, None));
 		let ksm_id = Assets::next_asset_id() - 1;
 		let ksm_type = TokenSymbol::from(ksm_id);
 
@@ -648,60 +898,1151 @@ fn remove_assets_liquidity_should_work() {
 
 		// set swap fee
 		let fee = 100;
-		<SwapFee<Test>>::put(fee);
-		assert_eq!(<SwapFee<Test>>::get(), fee);
 
 		// issue intialized pool token
 		let pool_token = 1000;
-		<BalancerPoolToken<Test>>::put(pool_token);
 
-		// init reward pool
-		let reward = vec![
-			(dot_type, 0),
-			(ksm_type, 0),
-		];
-		<SharedRewardPool::<Test>>::put(reward);
+		// create the pool, alice holds the initialized pool token
+		assert_eq!(Swap::insert_pool(alice, raw_pool, fee, pool_token), Ok(pool_id));
 
 		// first time to deposit to pool
 		let new_pool_token = 10;
-		assert_ok!(Swap::add_liquidity(Origin::signed(alice), new_pool_token));
+		assert_ok!(Swap::add_liquidity(Origin::signed(alice), pool_id, new_pool_token, None));
 		assert_eq!(<assets::AccountAssets<Test>>::get((dot_type, alice)).balance, dot_token_amount - 100);
 		assert_eq!(<assets::AccountAssets<Test>>::get((ksm_type, alice)).balance, ksm_token_amount - 1000);
 
-		let gpool = <GlobalPool<Test>>::get();
+		let gpool = Swap::pools(pool_id).unwrap();
 		let target = vec![100u64, 1000];
-		for (p, t) in gpool.0.iter().zip(target.iter()) {
+		for (p, t) in gpool.tokens.iter().zip(target.iter()) {
 			assert_eq!(p.1, *t);
 		}
 
-		let user_pool = <UserPool::<Test>>::get(alice);
-		let target = vec![100u64, 1000];
-		for (p, t) in user_pool.0.iter().zip(target.iter()) {
-			assert_eq!(p.1, *t);
-		}
-		assert_eq!(user_pool.1, new_pool_token);
+		let share_token = Swap::pool_token(pool_id).unwrap();
+		assert_eq!(<assets::AccountAssets<Test>>::get((share_token, alice)).balance, new_pool_token + pool_token);
 
 		// suppose bob doesn't have any pool
 		assert_eq!(
-			Swap::remove_assets_liquidity(Origin::signed(bob), new_pool_token),
+			Swap::remove_assets_liquidity(Origin::signed(bob), pool_id, new_pool_token, None),
 			Err(DispatchError::Module { index: 0, error: 8, message: Some("NotExistedCurrentPool") })
 		);
 		// alice redeems too much
 		assert_eq!(
-			Swap::remove_assets_liquidity(Origin::signed(alice), new_pool_token + 1),
+			Swap::remove_assets_liquidity(Origin::signed(alice), pool_id, new_pool_token + pool_token + 1, None),
 			Err(DispatchError::Module { index: 0, error: 1, message: Some("NotEnoughBalance") })
 		);
 
-		// remove liquidity
-		assert_ok!(Swap::remove_assets_liquidity(Origin::signed(alice), new_pool_token));
+		// remove all the liquidity
+		assert_ok!(Swap::remove_assets_liquidity(Origin::signed(alice), pool_id, new_pool_token + pool_token, None));
 		assert_eq!(<assets::AccountAssets<Test>>::get((dot_type, alice)).balance, dot_token_amount);
 		assert_eq!(<assets::AccountAssets<Test>>::get((ksm_type, alice)).balance, ksm_token_amount);
 
-		let gpool = <GlobalPool<Test>>::get();
-		assert!(gpool.0.iter().all(|p| p.1 == 0));
+		let gpool = Swap::pools(pool_id).unwrap();
+		assert!(gpool.tokens.iter().all(|p| p.1 == 0));
+
+		assert_eq!(gpool.pool_supply, 0);
+		assert_eq!(<assets::AccountAssets<Test>>::get((share_token, alice)).balance, 0);
+	});
+}
+
+#[test]
+fn create_pool_should_work() {
+	new_test_ext().execute_with(|| {
+		run_to_block(2);
+
+		let alice = 1u64;
+		let precise = 4;
+		let token_amount = 10_000;
+
+		assert_ok!(Assets::create(Origin::root(), b"aUSD".to_vec(), 18, None)); // let dot start from 1
+
+		// issue dot token
+		assert_ok!(Assets::create(Origin::root(), b"DOT".to_vec(), precise, None));
+		let dot_type = TokenSymbol::from(Assets::next_asset_id() - 1);
+		assert_ok!(Assets::issue(Origin::root(), dot_type, alice, token_amount));
+
+		assert_ok!(Assets::create(Origin::root(), b"vDOT".to_vec(), 12, None)); // skip vDOT
+		let vdot_type = TokenSymbol::from(Assets::next_asset_id() - 1);
+
+		// issue ksm token
+		assert_ok!(Assets::create(Origin::root(), b"KSM".to_vec(), precise, None));
+		let ksm_type = TokenSymbol::from(Assets::next_asset_id() - 1);
+		assert_ok!(Assets::issue(Origin::root(), ksm_type, alice, token_amount));
+
+		let fee = 100;
+
+		// a pool needs two tokens at least
+		assert_eq!(
+			Swap::create_pool(Origin::signed(alice), vec![(dot_type, 1000, 1)], fee),
+			Err(DispatchError::Module { index: 0, error: 19, message: Some("InvalidPoolTokens") })
+		);
+		// the same token twice
+		assert_eq!(
+			Swap::create_pool(Origin::signed(alice), vec![(dot_type, 1000, 1), (dot_type, 1000, 49)], fee),
+			Err(DispatchError::Module { index: 0, error: 19, message: Some("InvalidPoolTokens") })
+		);
+		// too low swap fee
+		assert_eq!(
+			Swap::create_pool(Origin::signed(alice), vec![(dot_type, 1000, 1), (ksm_type, 2000, 49)], 0),
+			Err(DispatchError::Module { index: 0, error: 20, message: Some("InvalidSwapFee") })
+		);
+		// deposit more than alice has
+		assert_eq!(
+			Swap::create_pool(Origin::signed(alice), vec![(dot_type, token_amount + 1, 1), (ksm_type, 2000, 49)], fee),
+			Err(DispatchError::Module { index: 0, error: 1, message: Some("NotEnoughBalance") })
+		);
+
+		assert_ok!(Swap::create_pool(Origin::signed(alice), vec![(dot_type, 1000, 1), (ksm_type, 2000, 49)], fee));
+		assert_eq!(Swap::next_pool_id(), 1);
+		assert_eq!(
+			System::events().last().unwrap().event,
+			TestEvent::pallet_swap(RawEvent::PoolCreated(0, alice))
+		);
+
+		let pool = Swap::pools(0).unwrap();
+		assert_eq!(pool.owner, alice);
+		assert_eq!(pool.tokens, vec![(dot_type, 1000, 1), (ksm_type, 2000, 49)]);
+		assert_eq!(pool.total_weight, 50);
+		assert_eq!(pool.swap_fee, fee);
+		assert_eq!(pool.pool_supply, InitPoolSupply::get());
+		assert_eq!(pool.rewards, vec![(dot_type, 0), (ksm_type, 0)]);
+
+		assert_eq!(<assets::AccountAssets<Test>>::get((dot_type, alice)).balance, token_amount - 1000);
+		assert_eq!(<assets::AccountAssets<Test>>::get((ksm_type, alice)).balance, token_amount - 2000);
+		// alice gets the pool token
+		let share_token = Swap::pool_token(0).unwrap();
+		assert_eq!(Swap::share_pool(share_token), Some(0));
+		assert_eq!(Assets::token_details(share_token).symbol, b"BPT-0".to_vec());
+		assert_eq!(<assets::AccountAssets<Test>>::get((share_token, alice)).balance, InitPoolSupply::get());
+
+		// the other pools are independent
+		assert_ok!(Swap::create_pool(Origin::signed(alice), vec![(ksm_type, 1000, 1), (dot_type, 1000, 1)], fee));
+		assert_eq!(Swap::pools(1).unwrap().total_weight, 2);
+		assert_eq!(Swap::pools(0).unwrap().tokens, vec![(dot_type, 1000, 1), (ksm_type, 2000, 49)]);
+
+		// pool doesn't exist
+		assert_eq!(
+			Swap::swap(Origin::signed(alice), 2, dot_type, 100, None, ksm_type, None, None),
+			Err(DispatchError::Module { index: 0, error: 17, message: Some("PoolNotExist") })
+		);
+		// vdot is not in the pool
+		assert_eq!(
+			Swap::swap(Origin::signed(alice), 0, dot_type, 100, None, vdot_type, None, None),
+			Err(DispatchError::Module { index: 0, error: 18, message: Some("TokenNotInPool") })
+		);
+	});
+}
+
+#[test]
+fn routed_swap_should_work() {
+	new_test_ext().execute_with(|| {
+		run_to_block(2);
+
+		let alice = 1u64;
+		let bob = 2u64;
+		let token_amount = 1_000_000;
+
+		assert_ok!(Assets::create(Origin::root(), b"aUSD".to_vec(), 18, None)); // let dot start from 1
+		assert_ok!(Assets::create(Origin::root(), b"DOT".to_vec(), 12, None));
+		let dot_type = TokenSymbol::from(Assets::next_asset_id() - 1);
+		assert_ok!(Assets::create(Origin::root(), b"vDOT".to_vec(), 12, None));
+		let vdot_type = TokenSymbol::from(Assets::next_asset_id() - 1);
+		assert_ok!(Assets::create(Origin::root(), b"KSM".to_vec(), 12, None));
+		let ksm_type = TokenSymbol::from(Assets::next_asset_id() - 1);
+
+		for token in [dot_type, vdot_type, ksm_type].iter() {
+			assert_ok!(Assets::issue(Origin::root(), *token, alice, token_amount));
+		}
+		assert_ok!(Assets::issue(Origin::root(), dot_type, bob, 10_000));
+
+		// no direct pool between dot and vdot, but both of them are paired with ksm
+		let fee = 100;
+		assert_ok!(Swap::create_pool(Origin::signed(alice), vec![(dot_type, 100_000, 1), (ksm_type, 100_000, 1)], fee));
+		assert_ok!(Swap::create_pool(Origin::signed(alice), vec![(ksm_type, 100_000, 1), (vdot_type, 100_000, 1)], fee));
+		let path = vec![(0, ksm_type), (1, vdot_type)];
+
+		// swap after deadline
+		assert_eq!(
+			Swap::swap_exact_in_routed(Origin::signed(bob), dot_type, 1000, path.clone(), 900, Some(1)),
+			Err(DispatchError::Module { index: 0, error: 22, message: Some("DeadlineExceeded") })
+		);
+		// pass a pool twice
+		assert_eq!(
+			Swap::swap_exact_in_routed(Origin::signed(bob), dot_type, 1000, vec![(0, ksm_type), (0, dot_type)], 900, Some(2)),
+			Err(DispatchError::Module { index: 0, error: 21, message: Some("InvalidRoute") })
+		);
+		// ksm is not in the pool 1
+		assert_eq!(
+			Swap::swap_exact_in_routed(Origin::signed(bob), dot_type, 1000, vec![(1, vdot_type)], 900, Some(2)),
+			Err(DispatchError::Module { index: 0, error: 18, message: Some("TokenNotInPool") })
+		);
+		// get less than expected, as fee is charged on each hop
+		assert_eq!(
+			Swap::swap_exact_in_routed(Origin::signed(bob), dot_type, 1000, path.clone(), 1000, Some(2)),
+			Err(DispatchError::Module { index: 0, error: 14, message: Some("LessThanExpectedAmount") })
+		);
+
+		// the route through ksm is the only one, and the best one
+		let (best_path, amount_out) = Swap::best_route(dot_type, 1000, vdot_type).unwrap();
+		assert_eq!(best_path, path);
+		assert!(amount_out > 900 && amount_out < 1000);
+
+		assert_ok!(Swap::swap_exact_in_routed(Origin::signed(bob), dot_type, 1000, path.clone(), 900, Some(2)));
+		assert_eq!(<assets::AccountAssets<Test>>::get((dot_type, bob)).balance, 10_000 - 1000);
+		assert_eq!(<assets::AccountAssets<Test>>::get((vdot_type, bob)).balance, amount_out);
+		assert_eq!(
+			System::events().last().unwrap().event,
+			TestEvent::pallet_swap(RawEvent::RouteSwapped(bob, vec![0, 1], dot_type, 1000, vdot_type, amount_out))
+		);
+
+		// ksm only passes through the pools
+		let pool_0 = Swap::pools(0).unwrap();
+		let pool_1 = Swap::pools(1).unwrap();
+		assert_eq!(pool_0.tokens[0].1, 101_000);
+		assert_eq!(100_000 - pool_0.tokens[1].1, pool_1.tokens[0].1 - 100_000);
+		assert_eq!(pool_1.tokens[1].1, 100_000 - amount_out);
+		assert_eq!(pool_0.rewards[0], (dot_type, 1));
+
+		// pay for exact amount of vdot
+		assert_eq!(
+			Swap::swap_exact_out_routed(Origin::signed(bob), dot_type, path.clone(), 500, 500, Some(2)),
+			Err(DispatchError::Module { index: 0, error: 15, message: Some("BiggerThanExpectedAmount") })
+		);
+		assert_ok!(Swap::swap_exact_out_routed(Origin::signed(bob), dot_type, path.clone(), 500, 600, Some(2)));
+		let dot_paid = 10_000 - 1000 - <assets::AccountAssets<Test>>::get((dot_type, bob)).balance;
+		assert!(dot_paid > 500 && dot_paid <= 600);
+		assert!(<assets::AccountAssets<Test>>::get((vdot_type, bob)).balance >= amount_out + 500);
+		assert_eq!(Swap::check_integrity(), vec![]);
+	});
+}
+
+#[test]
+fn swap_exact_out_should_work() {
+	new_test_ext().execute_with(|| {
+		run_to_block(2);
+
+		let alice = 1u64;
+		let bob = 2u64;
+		let token_amount = 1_000_000;
+
+		assert_ok!(Assets::create(Origin::root(), b"aUSD".to_vec(), 18, None)); // let dot start from 1
+		assert_ok!(Assets::create(Origin::root(), b"DOT".to_vec(), 12, None));
+		let dot_type = TokenSymbol::from(Assets::next_asset_id() - 1);
+		assert_ok!(Assets::create(Origin::root(), b"KSM".to_vec(), 12, None));
+		let ksm_type = TokenSymbol::from(Assets::next_asset_id() - 1);
+
+		assert_ok!(Assets::issue(Origin::root(), dot_type, alice, token_amount));
+		assert_ok!(Assets::issue(Origin::root(), ksm_type, alice, token_amount));
+		assert_ok!(Assets::issue(Origin::root(), dot_type, bob, 10_000));
+
+		let fee = 100; // 0.1%
+		assert_ok!(Swap::create_pool(Origin::signed(alice), vec![(dot_type, 100_000, 1), (ksm_type, 100_000, 1)], fee));
+		let pool_id = 0;
+
+		// the pool cannot be drained
+		assert_eq!(
+			Swap::swap_exact_out(Origin::signed(bob), pool_id, dot_type, 10_000, ksm_type, 100_000, None),
+			Err(DispatchError::Module { index: 0, error: 1, message: Some("NotEnoughBalance") })
+		);
+		// pay more than expected
+		assert_eq!(
+			Swap::swap_exact_out(Origin::signed(bob), pool_id, dot_type, 500, ksm_type, 500, None),
+			Err(DispatchError::Module { index: 0, error: 15, message: Some("BiggerThanExpectedAmount") })
+		);
+
+		assert_ok!(Swap::swap_exact_out(Origin::signed(bob), pool_id, dot_type, 600, ksm_type, 500, None));
+		assert_eq!(<assets::AccountAssets<Test>>::get((ksm_type, bob)).balance, 500);
+
+		// 500 * 100_000 / (100_000 - 500) / (1 - 0.1%) = 503.015..., rounded up
+		let dot_paid = 10_000 - <assets::AccountAssets<Test>>::get((dot_type, bob)).balance;
+		assert_eq!(dot_paid, 504);
+
+		let pool = Swap::pools(pool_id).unwrap();
+		assert_eq!(pool.tokens, vec![(dot_type, 100_000 + dot_paid, 1), (ksm_type, 100_000 - 500, 1)]);
+		assert_eq!(pool.rewards[0], (dot_type, 0)); // 504 * 0.1% is less than 1
+
+		// with unequal weights, amount in = B_in * ((B_out / (B_out - out)) ^ (w_out / w_in) - 1) / (1 - fee)
+		assert_ok!(Swap::create_pool(Origin::signed(alice), vec![(dot_type, 100_000, 49), (ksm_type, 100_000, 1)], fee));
+		let weighted_pool = 1;
+		let dot_before = <assets::AccountAssets<Test>>::get((dot_type, alice)).balance;
+		assert_ok!(Swap::swap_exact_out(Origin::signed(alice), weighted_pool, dot_type, 216, ksm_type, 10_000, None));
+		// 100_000 * ((100_000 / 90_000) ^ (1 / 49) - 1) / (1 - 0.1%) = 215.468..., rounded up
+		let dot_paid = dot_before - <assets::AccountAssets<Test>>::get((dot_type, alice)).balance;
+		assert_eq!(dot_paid, 216);
+		assert_eq!(
+			Swap::pools(weighted_pool).unwrap().tokens,
+			vec![(dot_type, 100_216, 49), (ksm_type, 90_000, 1)]
+		);
+
+		// the other way around, the weight ratio is 49
+		let ksm_before = <assets::AccountAssets<Test>>::get((ksm_type, alice)).balance;
+		assert_eq!(
+			Swap::swap_exact_out(Origin::signed(alice), weighted_pool, ksm_type, 9_264, dot_type, 200, None),
+			Err(DispatchError::Module { index: 0, error: 15, message: Some("BiggerThanExpectedAmount") })
+		);
+		assert_ok!(Swap::swap_exact_out(Origin::signed(alice), weighted_pool, ksm_type, 9_265, dot_type, 200, None));
+		// 90_000 * ((100_216 / 100_016) ^ 49 - 1) / (1 - 0.1%) = 9_264.647..., rounded up
+		let ksm_paid = ksm_before - <assets::AccountAssets<Test>>::get((ksm_type, alice)).balance;
+		assert_eq!(ksm_paid, 9_265);
+	});
+}
+
+#[test]
+fn reserved_balance_should_not_be_spent() {
+	new_test_ext().execute_with(|| {
+		run_to_block(2);
+
+		let alice = 1u64;
+		let bob = 2u64;
+
+		assert_ok!(Assets::create(Origin::root(), b"aUSD".to_vec(), 18, None)); // let dot start from 1
+		assert_ok!(Assets::create(Origin::root(), b"DOT".to_vec(), 12, None));
+		let dot_type = TokenSymbol::from(Assets::next_asset_id() - 1);
+		assert_ok!(Assets::create(Origin::root(), b"KSM".to_vec(), 12, None));
+		let ksm_type = TokenSymbol::from(Assets::next_asset_id() - 1);
+
+		assert_ok!(Assets::issue(Origin::root(), dot_type, alice, 1_000_000));
+		assert_ok!(Assets::issue(Origin::root(), ksm_type, alice, 1_000_000));
+		assert_ok!(Assets::issue(Origin::root(), dot_type, bob, 10_000));
+		assert_ok!(Swap::create_pool(Origin::signed(alice), vec![(dot_type, 100_000, 1), (ksm_type, 100_000, 1)], 100));
+		let pool_id = 0;
+
+		// most of the dot of bob is reserved by another module
+		assert_ok!(<Assets as AssetTrait<_, _, _, _, _>>::reserve(*b"reserved", dot_type, &bob, 9_500));
+
+		let not_enough_balance = DispatchError::Module { index: 0, error: 1, message: Some("NotEnoughBalance") };
+		assert_eq!(Swap::swap(Origin::signed(bob), pool_id, dot_type, 1_000, None, ksm_type, None, None), Err(not_enough_balance));
+		assert_eq!(Swap::swap_exact_out(Origin::signed(bob), pool_id, dot_type, 1_000, ksm_type, 600, None), Err(not_enough_balance));
+		assert_eq!(Swap::add_single_liquidity(Origin::signed(bob), pool_id, dot_type, 600, None), Err(not_enough_balance));
+
+		// the available part can still be swapped
+		assert_ok!(Swap::swap(Origin::signed(bob), pool_id, dot_type, 200, None, ksm_type, None, None));
+		let bob_dot = <assets::AccountAssets<Test>>::get((dot_type, bob));
+		assert_eq!((bob_dot.balance, bob_dot.reserved, bob_dot.available), (9_800, 9_500, 300));
+	});
+}
+
+#[test]
+fn twap_should_work() {
+	new_test_ext().execute_with(|| {
+		run_to_block(2);
+
+		let alice = 1u64;
+		let token_amount = 1_000_000;
+
+		assert_ok!(Assets::create(Origin::root(), b"aUSD".to_vec(), 18, None)); // let dot start from 1
+		assert_ok!(Assets::create(Origin::root(), b"DOT".to_vec(), 12, None));
+		let dot_type = TokenSymbol::from(Assets::next_asset_id() - 1);
+		assert_ok!(Assets::create(Origin::root(), b"KSM".to_vec(), 12, None));
+		let ksm_type = TokenSymbol::from(Assets::next_asset_id() - 1);
+
+		assert_ok!(Assets::issue(Origin::root(), dot_type, alice, token_amount));
+		assert_ok!(Assets::issue(Origin::root(), ksm_type, alice, token_amount));
+		assert_ok!(Swap::create_pool(Origin::signed(alice), vec![(dot_type, 100_000, 1), (ksm_type, 200_000, 1)], 100));
+
+		// a dot is 2 ksm
+		let pair = (0, dot_type, ksm_type);
+		assert_eq!(Swap::oracle_price(pair), Some(2 * PRICE_PRECISION));
+
+		// only root can add a pair
+		assert_eq!(Swap::add_oracle_pair(Origin::signed(alice), pair), Err(DispatchError::BadOrigin));
+		assert_ok!(Swap::add_oracle_pair(Origin::root(), pair));
+		assert_eq!(
+			Swap::add_oracle_pair(Origin::root(), pair),
+			Err(DispatchError::Module { index: 0, error: 24, message: Some("OraclePairExisted") })
+		);
+		// nothing is recorded yet
+		assert_eq!(Swap::twap(pair, 1), None);
+
+		// prices at the end of block 2, 3 and 4 are recorded
+		run_to_block(5);
+		assert_eq!(Swap::twap(pair, 2), Some(2 * PRICE_PRECISION));
+		assert_eq!(Swap::twap(pair, 3), None);
+		// the whole window is not recorded yet
+		assert_eq!(<Swap as PriceProvider<TokenSymbol, u64>>::get_price(dot_type, ksm_type, 1_000), None);
+
+		// sell dot, price of dot goes down
+		assert_ok!(Swap::swap(Origin::signed(alice), 0, dot_type, 50_000, None, ksm_type, None, None));
+		let new_price = Swap::oracle_price(pair).unwrap();
+		assert!(new_price < PRICE_PRECISION);
+		// price changes in the block doesn't affect the average
+		assert_eq!(Swap::twap(pair, 2), Some(2 * PRICE_PRECISION));
+
+		// block 3 and 4 at the old price, block 5, 6 and 7 at the new price
+		run_to_block(8);
+		let expected = (2 * 2 * PRICE_PRECISION + 3 * new_price) / 5;
+		assert_eq!(Swap::twap(pair, 5), Some(expected));
+		assert_eq!(
+			<Swap as PriceProvider<TokenSymbol, u64>>::get_price(dot_type, ksm_type, 1_000),
+			Some((expected * 1_000 / PRICE_PRECISION) as u64)
+		);
+		// there is no price for the reversed pair
+		assert_eq!(<Swap as PriceProvider<TokenSymbol, u64>>::get_price(ksm_type, dot_type, 1_000), None);
+
+		// prices out of the window are pruned
+		run_to_block(9);
+		assert_eq!(Swap::price_cumulative(pair, 2), None);
+		assert!(Swap::twap(pair, 5).is_some());
+
+		assert_ok!(Swap::remove_oracle_pair(Origin::root(), pair));
+		assert_eq!(Swap::twap(pair, 1), None);
+		assert_eq!(
+			Swap::remove_oracle_pair(Origin::root(), pair),
+			Err(DispatchError::Module { index: 0, error: 25, message: Some("OraclePairNotExist") })
+		);
+	});
+}
+
+#[test]
+fn quotes_should_match_execution() {
+	new_test_ext().execute_with(|| {
+		run_to_block(2);
+
+		let alice = 1u64;
+		let bob = 2u64;
+		let token_amount = 1_000_000;
+
+		assert_ok!(Assets::create(Origin::root(), b"aUSD".to_vec(), 18, None)); // let dot start from 1
+		assert_ok!(Assets::create(Origin::root(), b"DOT".to_vec(), 12, None));
+		let dot_type = TokenSymbol::from(Assets::next_asset_id() - 1);
+		assert_ok!(Assets::create(Origin::root(), b"KSM".to_vec(), 12, None));
+		let ksm_type = TokenSymbol::from(Assets::next_asset_id() - 1);
+
+		assert_ok!(Assets::issue(Origin::root(), dot_type, alice, token_amount));
+		assert_ok!(Assets::issue(Origin::root(), ksm_type, alice, token_amount));
+		assert_ok!(Assets::issue(Origin::root(), dot_type, bob, 50_000));
+
+		let fee = 100; // 0.1%
+		assert_ok!(Swap::create_pool(Origin::signed(alice), vec![(dot_type, 100_000, 1), (ksm_type, 100_000, 1)], fee));
+		let pool_id = 0;
+
+		assert_eq!(
+			Swap::quote_swap(pool_id, dot_type, 1_000, dot_type).map_err(DispatchError::from),
+			Err(DispatchError::Module { index: 0, error: 9, message: Some("ForbidSameTokenSwap") })
+		);
+		assert_eq!(
+			Swap::quote_swap(pool_id + 1, dot_type, 1_000, ksm_type).map_err(DispatchError::from),
+			Err(DispatchError::Module { index: 0, error: 17, message: Some("PoolNotExist") })
+		);
+		assert_eq!(
+			Swap::quote_swap(pool_id, dot_type, 1_000, TokenSymbol::IOST).map_err(DispatchError::from),
+			Err(DispatchError::Module { index: 0, error: 18, message: Some("TokenNotInPool") })
+		);
+
+		// quote a swap, then do it
+		let (amount_out, charged_fee, price_before, price_after) = Swap::quote_swap(pool_id, dot_type, 1_000, ksm_type).unwrap();
+		// 1 / (1 - 0.1%) = 1.001001...
+		assert_eq!(price_before / 1_000_000, 1_001_001);
+		assert!(price_after > price_before);
+		assert_eq!(charged_fee, 1);
+
+		assert_ok!(Swap::swap(Origin::signed(bob), pool_id, dot_type, 1_000, None, ksm_type, None, None));
+		assert_eq!(<assets::AccountAssets<Test>>::get((ksm_type, bob)).balance, amount_out);
+		assert_eq!(Swap::pools(pool_id).unwrap().rewards[0], (dot_type, charged_fee));
+		// the price after the swap is the price before the next one
+		assert_eq!(Swap::quote_swap(pool_id, dot_type, 1_000, ksm_type).unwrap().2, price_after);
+
+		// quote adding liquidity, then do it
+		let pool_supply = Swap::pools(pool_id).unwrap().pool_supply;
+		let issued = Swap::quote_add_single_liquidity(pool_id, dot_type, 10_000).unwrap();
+		assert!(issued > 0);
+		assert_ok!(Swap::add_single_liquidity(Origin::signed(bob), pool_id, dot_type, 10_000, None));
+		assert_eq!(Swap::pools(pool_id).unwrap().pool_supply, pool_supply + issued);
+
+		// quote removing liquidity, then do it
+		assert_eq!(
+			Swap::quote_remove_single_asset_liquidity(pool_id, dot_type, pool_supply + issued + 1).map_err(DispatchError::from),
+			Err(DispatchError::Module { index: 0, error: 1, message: Some("NotEnoughBalance") })
+		);
+		let dot_balance = <assets::AccountAssets<Test>>::get((dot_type, bob)).balance;
+		let paid = Swap::quote_remove_single_asset_liquidity(pool_id, dot_type, issued).unwrap();
+		assert!(paid > 0);
+		assert_ok!(Swap::remove_single_asset_liquidity(Origin::signed(bob), pool_id, dot_type, issued, None));
+		assert_eq!(<assets::AccountAssets<Test>>::get((dot_type, bob)).balance, dot_balance + paid);
+	});
+}
+
+#[test]
+fn set_fees_should_work() {
+	new_test_ext().execute_with(|| {
+		run_to_block(2);
+
+		let alice = 1u64;
+		assert_ok!(Assets::create(Origin::root(), b"aUSD".to_vec(), 18, None)); // let dot start from 1
+		assert_ok!(Assets::create(Origin::root(), b"DOT".to_vec(), 12, None));
+		let dot_type = TokenSymbol::from(Assets::next_asset_id() - 1);
+		assert_ok!(Assets::create(Origin::root(), b"KSM".to_vec(), 12, None));
+		let ksm_type = TokenSymbol::from(Assets::next_asset_id() - 1);
+		assert_ok!(Assets::issue(Origin::root(), dot_type, alice, 100_000));
+		assert_ok!(Assets::issue(Origin::root(), ksm_type, alice, 100_000));
+
+		assert_ok!(Swap::create_pool(Origin::signed(alice), vec![(dot_type, 100_000, 1), (ksm_type, 100_000, 1)], 100));
+		let pool_id = 0;
+
+		assert_eq!(Swap::set_swap_fee(Origin::signed(alice), pool_id, 200), Err(DispatchError::BadOrigin));
+		assert_eq!(
+			Swap::set_swap_fee(Origin::root(), pool_id, 0),
+			Err(DispatchError::Module { index: 0, error: 20, message: Some("InvalidSwapFee") })
+		);
+		assert_eq!(
+			Swap::set_swap_fee(Origin::root(), pool_id, MaximumSwapFee::get() + 1),
+			Err(DispatchError::Module { index: 0, error: 20, message: Some("InvalidSwapFee") })
+		);
+		assert_eq!(
+			Swap::set_swap_fee(Origin::root(), pool_id + 1, 200),
+			Err(DispatchError::Module { index: 0, error: 17, message: Some("PoolNotExist") })
+		);
+		assert_ok!(Swap::set_swap_fee(Origin::root(), pool_id, 200));
+		assert_eq!(Swap::pools(pool_id).unwrap().swap_fee, 200);
+
+		assert_eq!(
+			Swap::set_exit_fee(Origin::root(), pool_id, MaximumSwapFee::get() + 1),
+			Err(DispatchError::Module { index: 0, error: 26, message: Some("InvalidExitFee") })
+		);
+		assert_ok!(Swap::set_exit_fee(Origin::root(), pool_id, 0));
+		assert_ok!(Swap::set_exit_fee(Origin::root(), pool_id, 300));
+		assert_eq!(Swap::pools(pool_id).unwrap().exit_fee, 300);
+		assert_eq!(
+			System::events().last().unwrap().event,
+			TestEvent::pallet_swap(RawEvent::ExitFeeChanged(pool_id, 300))
+		);
+	});
+}
+
+#[test]
+fn weight_ramp_should_work() {
+	new_test_ext().execute_with(|| {
+		run_to_block(2);
+
+		let alice = 1u64;
+		assert_ok!(Assets::create(Origin::root(), b"aUSD".to_vec(), 18, None)); // let dot start from 1
+		assert_ok!(Assets::create(Origin::root(), b"DOT".to_vec(), 12, None));
+		let dot_type = TokenSymbol::from(Assets::next_asset_id() - 1);
+		assert_ok!(Assets::create(Origin::root(), b"KSM".to_vec(), 12, None));
+		let ksm_type = TokenSymbol::from(Assets::next_asset_id() - 1);
+		assert_ok!(Assets::issue(Origin::root(), dot_type, alice, 100_000));
+		assert_ok!(Assets::issue(Origin::root(), ksm_type, alice, 100_000));
+
+		assert_ok!(Swap::create_pool(Origin::signed(alice), vec![(dot_type, 100_000, 10), (ksm_type, 100_000, 10)], 100));
+		let pool_id = 0;
+
+		let end_weights = vec![(ksm_type, 40), (dot_type, 10)];
+		assert_eq!(
+			Swap::update_weights_gradually(Origin::signed(alice), pool_id, end_weights.clone(), 4, 7),
+			Err(DispatchError::BadOrigin)
+		);
+		// the ramp starts in the past
+		assert_eq!(
+			Swap::update_weights_gradually(Origin::root(), pool_id, end_weights.clone(), 2, 7),
+			Err(DispatchError::Module { index: 0, error: 27, message: Some("InvalidWeightRamp") })
+		);
+		assert_eq!(
+			Swap::update_weights_gradually(Origin::root(), pool_id, end_weights.clone(), 7, 7),
+			Err(DispatchError::Module { index: 0, error: 27, message: Some("InvalidWeightRamp") })
+		);
+		// every token needs a non-zero weight
+		assert_eq!(
+			Swap::update_weights_gradually(Origin::root(), pool_id, vec![(ksm_type, 40)], 4, 7),
+			Err(DispatchError::Module { index: 0, error: 19, message: Some("InvalidPoolTokens") })
+		);
+		assert_eq!(
+			Swap::update_weights_gradually(Origin::root(), pool_id, vec![(ksm_type, 40), (dot_type, 0)], 4, 7),
+			Err(DispatchError::Module { index: 0, error: 19, message: Some("InvalidPoolTokens") })
+		);
+		assert_eq!(
+			Swap::update_weights_gradually(Origin::root(), pool_id, vec![(ksm_type, 40), (TokenSymbol::IOST, 10)], 4, 7),
+			Err(DispatchError::Module { index: 0, error: 18, message: Some("TokenNotInPool") })
+		);
+
+		assert_ok!(Swap::update_weights_gradually(Origin::root(), pool_id, end_weights, 4, 7));
+		let weights = || {
+			let pool = Swap::pools(pool_id).unwrap();
+			(pool.tokens.iter().map(|t| t.2).collect::<Vec<_>>(), pool.total_weight)
+		};
+
+		run_to_block(4);
+		assert_eq!(weights(), (vec![10, 10], 20));
+		run_to_block(5);
+		assert_eq!(weights(), (vec![10, 20], 30));
+		run_to_block(6);
+		assert_eq!(weights(), (vec![10, 30], 40));
+		run_to_block(7);
+		assert_eq!(weights(), (vec![10, 40], 50));
+		assert!(Swap::weight_ramps().is_empty());
+
+		// ramp down, then stop halfway
+		assert_ok!(Swap::update_weights_gradually(Origin::root(), pool_id, vec![(dot_type, 10), (ksm_type, 20)], 8, 10));
+		run_to_block(9);
+		assert_eq!(weights(), (vec![10, 30], 40));
+		assert_ok!(Swap::stop_weight_ramp(Origin::root(), pool_id));
+		assert_eq!(
+			Swap::stop_weight_ramp(Origin::root(), pool_id),
+			Err(DispatchError::Module { index: 0, error: 29, message: Some("WeightRampNotExist") })
+		);
+		run_to_block(10);
+		assert_eq!(weights(), (vec![10, 30], 40));
+	});
+}
+
+#[test]
+fn liquidity_mining_should_work() {
+	new_test_ext().execute_with(|| {
+		run_to_block(2);
+
+		let alice = 1u64;
+		let bob = 2u64;
+		assert_ok!(Assets::create(Origin::root(), b"aUSD".to_vec(), 18, None)); // let dot start from 1
+		assert_ok!(Assets::create(Origin::root(), b"DOT".to_vec(), 12, None));
+		let dot_type = TokenSymbol::from(Assets::next_asset_id() - 1);
+		assert_ok!(Assets::create(Origin::root(), b"KSM".to_vec(), 12, None));
+		let ksm_type = TokenSymbol::from(Assets::next_asset_id() - 1);
+		assert_ok!(Assets::create(Origin::root(), b"EOS".to_vec(), 12, None));
+		let reward_type = TokenSymbol::from(Assets::next_asset_id() - 1);
+
+		assert_ok!(Assets::issue(Origin::root(), dot_type, alice, 100_000));
+		assert_ok!(Assets::issue(Origin::root(), ksm_type, alice, 100_000));
+		assert_ok!(Assets::issue(Origin::root(), dot_type, bob, 1_000));
+		assert_ok!(Assets::issue(Origin::root(), ksm_type, bob, 1_000));
+
+		// alice holds all 1000 pool tokens
+		assert_ok!(Swap::create_pool(Origin::signed(alice), vec![(dot_type, 100_000, 1), (ksm_type, 100_000, 1)], 100));
+		let pool_id = 0;
+
+		let emissions = vec![(reward_type, 100)];
+		assert_eq!(Swap::set_mining_rewards(Origin::signed(alice), pool_id, emissions.clone()), Err(DispatchError::BadOrigin));
+		assert_eq!(
+			Swap::set_mining_rewards(Origin::root(), pool_id, vec![(reward_type, 100); 5]),
+			Err(DispatchError::Module { index: 0, error: 30, message: Some("TooManyRewardTokens") })
+		);
+		assert_eq!(
+			Swap::set_mining_rewards(Origin::root(), pool_id, vec![(TokenSymbol::IOST, 100)]),
+			Err(DispatchError::Module { index: 0, error: 0, message: Some("TokenNotExist") })
+		);
+		assert_ok!(Swap::set_mining_rewards(Origin::root(), pool_id, emissions));
+
+		// alice earns all emissions of block 3 and 4
+		run_to_block(4);
+
+		// bob holds the same pool tokens as alice, but earns nothing in the block he deposits
+		assert_ok!(Swap::add_liquidity(Origin::signed(bob), pool_id, 1_000, None));
+		assert_eq!(Swap::mining_pools(pool_id).total_shares, 2_000);
+		assert_eq!(
+			Swap::claim_rewards(Origin::signed(bob), pool_id),
+			Err(DispatchError::Module { index: 0, error: 31, message: Some("NoRewardsToClaim") })
+		);
+
+		// they share the emissions of block 5 and 6
+		run_to_block(6);
+		assert_ok!(Swap::claim_rewards(Origin::signed(alice), pool_id));
+		assert_eq!(<assets::AccountAssets<Test>>::get((reward_type, alice)).balance, 300);
+		assert_eq!(
+			System::events().last().unwrap().event,
+			TestEvent::pallet_swap(RawEvent::RewardsClaimed(alice, pool_id, vec![(reward_type, 300)]))
+		);
+		assert_ok!(Swap::claim_rewards(Origin::signed(bob), pool_id));
+		assert_eq!(<assets::AccountAssets<Test>>::get((reward_type, bob)).balance, 100);
+		assert_eq!(
+			Swap::claim_rewards(Origin::signed(alice), pool_id),
+			Err(DispatchError::Module { index: 0, error: 31, message: Some("NoRewardsToClaim") })
+		);
+
+		// bob leaves, alice earns block 7 alone
+		assert_ok!(Swap::remove_assets_liquidity(Origin::signed(bob), pool_id, 1_000, None));
+		run_to_block(7);
+		assert_ok!(Swap::set_mining_rewards(Origin::root(), pool_id, vec![]));
+		run_to_block(9);
+		assert_ok!(Swap::claim_rewards(Origin::signed(alice), pool_id));
+		assert_eq!(<assets::AccountAssets<Test>>::get((reward_type, alice)).balance, 400);
+		assert_eq!(
+			Swap::claim_rewards(Origin::signed(bob), pool_id),
+			Err(DispatchError::Module { index: 0, error: 31, message: Some("NoRewardsToClaim") })
+		);
+		assert_eq!(Swap::check_integrity(), vec![]);
+	});
+}
+
+#[test]
+fn flash_swap_should_work() {
+	new_test_ext().execute_with(|| {
+		run_to_block(2);
+
+		let alice = 1u64;
+		let bob = 2u64;
+		assert_ok!(Assets::create(Origin::root(), b"aUSD".to_vec(), 18, None)); // let dot start from 1
+		assert_ok!(Assets::create(Origin::root(), b"DOT".to_vec(), 12, None));
+		let dot_type = TokenSymbol::from(Assets::next_asset_id() - 1);
+		assert_ok!(Assets::create(Origin::root(), b"KSM".to_vec(), 12, None));
+		let ksm_type = TokenSymbol::from(Assets::next_asset_id() - 1);
+
+		assert_ok!(Assets::issue(Origin::root(), dot_type, alice, 1_000_000));
+		assert_ok!(Assets::issue(Origin::root(), ksm_type, alice, 1_000_000));
+		assert_ok!(Assets::issue(Origin::root(), ksm_type, bob, 1_000));
+
+		let fee = 100; // 0.1%
+		assert_ok!(Swap::create_pool(Origin::signed(alice), vec![(dot_type, 100_000, 1), (ksm_type, 100_000, 1)], fee));
+		// ksm is more expensive in this pool
+		assert_ok!(Swap::create_pool(Origin::signed(alice), vec![(dot_type, 200_000, 1), (ksm_type, 100_000, 1)], fee));
+
+		// borrow ksm from pool 0, sell it in pool 1, then pay dot back to pool 0
+		let borrows = vec![(ksm_type, 1_000)];
+		let arbitrage = Box::new(Call::Swap(crate::Call::swap(1, ksm_type, 1_000, None, dot_type, None, None)));
+		let (dot_out, _, _, _) = Swap::quote_swap(1, ksm_type, 1_000, dot_type).unwrap();
+
+		// pay back less than the value taken away
+		assert_eq!(
+			Swap::flash_swap(Origin::signed(bob), 0, borrows.clone(), vec![(dot_type, 1_000)], arbitrage.clone(), None),
+			Err(DispatchError::Module { index: 0, error: 32, message: Some("FlashSwapInvariantBroken") })
+		);
+		// cannot pay back
+		assert_eq!(
+			Swap::flash_swap(Origin::signed(bob), 0, borrows.clone(), vec![(dot_type, dot_out + 1)], arbitrage.clone(), None),
+			Err(DispatchError::Module { index: 0, error: 1, message: Some("NotEnoughBalance") })
+		);
+		// the call fails
+		assert_eq!(
+			Swap::flash_swap(
+				Origin::signed(bob),
+				0,
+				borrows.clone(),
+				vec![(dot_type, 1_020)],
+				Box::new(Call::Swap(crate::Call::claim_rewards(0))),
+				None
+			),
+			Err(DispatchError::Module { index: 0, error: 31, message: Some("NoRewardsToClaim") })
+		);
+		// nothing is changed by the failed flash swaps
+		assert_eq!(<assets::AccountAssets<Test>>::get((ksm_type, bob)).balance, 1_000);
+		assert_eq!(<assets::AccountAssets<Test>>::get((dot_type, bob)).balance, 0);
+		assert_eq!(Swap::pools(0).unwrap().tokens, vec![(dot_type, 100_000, 1), (ksm_type, 100_000, 1)]);
+		assert_eq!(Swap::pools(1).unwrap().tokens, vec![(dot_type, 200_000, 1), (ksm_type, 100_000, 1)]);
+
+		assert_ok!(Swap::flash_swap(Origin::signed(bob), 0, borrows.clone(), vec![(dot_type, 1_020)], arbitrage, None));
+		assert_eq!(
+			System::events().last().unwrap().event,
+			TestEvent::pallet_swap(RawEvent::FlashSwapped(bob, 0, borrows, vec![(dot_type, 1_020)]))
+		);
+		// bob keeps the profit
+		assert_eq!(<assets::AccountAssets<Test>>::get((ksm_type, bob)).balance, 1_000);
+		assert_eq!(<assets::AccountAssets<Test>>::get((dot_type, bob)).balance, dot_out - 1_020);
+
+		let pool = Swap::pools(0).unwrap();
+		assert_eq!(pool.tokens, vec![(dot_type, 101_020, 1), (ksm_type, 99_000, 1)]);
+		assert_eq!(pool.rewards[0], (dot_type, 1)); // 1020 * 0.1%
+		assert_eq!(Swap::check_integrity(), vec![]);
+	});
+}
+
+#[test]
+fn flash_swap_should_not_reenter_the_pool() {
+	new_test_ext().execute_with(|| {
+		run_to_block(2);
+
+		let alice = 1u64;
+		let bob = 2u64;
+		assert_ok!(Assets::create(Origin::root(), b"aUSD".to_vec(), 18, None)); // let dot start from 1
+		assert_ok!(Assets::create(Origin::root(), b"DOT".to_vec(), 12, None));
+		let dot_type = TokenSymbol::from(Assets::next_asset_id() - 1);
+		assert_ok!(Assets::create(Origin::root(), b"KSM".to_vec(), 12, None));
+		let ksm_type = TokenSymbol::from(Assets::next_asset_id() - 1);
+
+		assert_ok!(Assets::issue(Origin::root(), dot_type, alice, 1_000_000));
+		assert_ok!(Assets::issue(Origin::root(), ksm_type, alice, 1_000_000));
+		assert_ok!(Assets::issue(Origin::root(), dot_type, bob, 10_000));
+		assert_ok!(Assets::issue(Origin::root(), ksm_type, bob, 10_000));
+		assert_ok!(Swap::create_pool(Origin::signed(alice), vec![(dot_type, 100_000, 1), (ksm_type, 100_000, 1)], 100));
+
+		// liquidity added by the call would pay the loan back with nothing repaid
+		let borrows = vec![(ksm_type, 1_000)];
+		let pool_locked = DispatchError::Module { index: 0, error: 38, message: Some("PoolLocked") };
+		let reentries = vec![
+			Call::Swap(crate::Call::add_liquidity(0, 20, None)),
+			Call::Swap(crate::Call::add_single_liquidity(0, dot_type, 2_000, None)),
+			Call::Swap(crate::Call::swap(0, dot_type, 1_000, None, ksm_type, None, None)),
+			Call::Swap(crate::Call::flash_swap(0, borrows.clone(), vec![], Box::new(Call::Swap(crate::Call::claim_rewards(0))), None)),
+		];
+		for call in reentries {
+			assert_eq!(Swap::flash_swap(Origin::signed(bob), 0, borrows.clone(), vec![], Box::new(call), None), Err(pool_locked));
+		}
+
+		// the lock is released after a flash swap
+		assert!(!Swap::flash_locked(0));
+		assert_eq!(Swap::pools(0).unwrap().tokens, vec![(dot_type, 100_000, 1), (ksm_type, 100_000, 1)]);
+		assert_ok!(Swap::add_liquidity(Origin::signed(bob), 0, 20, None));
+	});
+}
+
+#[test]
+fn stable_pool_should_work() {
+	new_test_ext().execute_with(|| {
+		run_to_block(2);
+
+		let alice = 1u64;
+		let bob = 2u64;
+		assert_ok!(Assets::create(Origin::root(), b"aUSD".to_vec(), 18, None)); // let dot start from 1
+		assert_ok!(Assets::create(Origin::root(), b"DOT".to_vec(), 12, None));
+		let dot_type = TokenSymbol::from(Assets::next_asset_id() - 1);
+		assert_ok!(Assets::register_token(Origin::root(), b"vDOT".to_vec(), 12, b"vDOT".to_vec(), vec![], Some(dot_type)));
+		let vdot_type = TokenSymbol::from(Assets::next_asset_id() - 1);
+		assert_ok!(Assets::create(Origin::root(), b"KSM".to_vec(), 12, None));
+		let ksm_type = TokenSymbol::from(Assets::next_asset_id() - 1);
+
+		assert_ok!(Assets::issue(Origin::root(), dot_type, alice, 1_000_000));
+		assert_ok!(Assets::issue(Origin::root(), vdot_type, alice, 1_000_000));
+		assert_ok!(Assets::issue(Origin::root(), ksm_type, alice, 1_000_000));
+		assert_ok!(Assets::issue(Origin::root(), dot_type, bob, 10_000));
+
+		let fee = 100; // 0.1%
+		assert_eq!(
+			Swap::create_stable_pool(Origin::signed(alice), vec![(dot_type, 100_000), (ksm_type, 100_000)], 0, fee),
+			Err(DispatchError::Module { index: 0, error: 35, message: Some("InvalidAmplification") })
+		);
+		assert_ok!(Swap::create_stable_pool(Origin::signed(alice), vec![(dot_type, 100_000), (ksm_type, 100_000)], 100, fee));
+		assert_ok!(Swap::create_pool(Origin::signed(alice), vec![(dot_type, 100_000, 1), (ksm_type, 100_000, 1)], fee));
+		let (stable_pool, weighted_pool) = (0, 1);
+		assert_eq!(Swap::amplification(stable_pool), Some(100));
+		assert_eq!(Swap::amplification(weighted_pool), None);
+
+		// 1 / (1 - 0.1%) = 1.001001... around the peg
+		let (stable_out, stable_fee, price_before, price_after) = Swap::quote_swap(stable_pool, dot_type, 1_000, ksm_type).unwrap();
+		assert_eq!(price_before / 1_000_000, 1_001_001);
+		assert!(price_after > price_before);
+		assert_eq!(stable_fee, 1);
+		// much less slippage than the weighted pool
+		let (weighted_out, _, _, _) = Swap::quote_swap(weighted_pool, dot_type, 1_000, ksm_type).unwrap();
+		assert_eq!(weighted_out, 989);
+		assert!(stable_out > 990 && stable_out < 999);
+
+		assert_ok!(Swap::swap(Origin::signed(bob), stable_pool, dot_type, 1_000, None, ksm_type, None, None));
+		assert_eq!(<assets::AccountAssets<Test>>::get((ksm_type, bob)).balance, stable_out);
+		assert_eq!(
+			Swap::pools(stable_pool).unwrap().tokens,
+			vec![(dot_type, 101_000, 1), (ksm_type, 100_000 - stable_out, 1)]
+		);
+
+		// pay no more than the amount in for the same amount out
+		assert_ok!(Swap::swap_exact_out(Origin::signed(bob), stable_pool, dot_type, 1_010, ksm_type, stable_out, None));
+		let dot_paid = 10_000 - 1_000 - <assets::AccountAssets<Test>>::get((dot_type, bob)).balance;
+		assert!(dot_paid >= 1_000 && dot_paid <= 1_010);
+
+		// single asset liquidity and weight ramps are for weighted pools
+		assert_eq!(
+			Swap::add_single_liquidity(Origin::signed(bob), stable_pool, dot_type, 1_000, None),
+			Err(DispatchError::Module { index: 0, error: 34, message: Some("UnsupportedForStablePool") })
+		);
+		assert_eq!(
+			Swap::update_weights_gradually(Origin::root(), stable_pool, vec![(dot_type, 2), (ksm_type, 1)], 4, 5),
+			Err(DispatchError::Module { index: 0, error: 34, message: Some("UnsupportedForStablePool") })
+		);
+
+		// a vDOT is worth 1.2 DOT, so the pool is balanced
+		assert_ok!(Swap::create_stable_pool(Origin::signed(alice), vec![(dot_type, 120_000), (vdot_type, 100_000)], 100, fee));
+		let vdot_pool = 2;
+		let (vdot_out, _, price_before, _) = Swap::quote_swap(vdot_pool, dot_type, 1_200, vdot_type).unwrap();
+		assert_eq!(price_before / 1_000_000, 1_201_201);
+		assert!(vdot_out > 990 && vdot_out < 999);
+		assert_eq!(Swap::check_integrity(), vec![]);
+	});
+}
+
+#[test]
+fn pool_token_transfer_should_move_mining_shares() {
+	new_test_ext().execute_with(|| {
+		run_to_block(2);
+
+		let alice = 1u64;
+		let bob = 2u64;
+		assert_ok!(Assets::create(Origin::root(), b"aUSD".to_vec(), 18, None)); // let dot start from 1
+		assert_ok!(Assets::create(Origin::root(), b"DOT".to_vec(), 12, None));
+		let dot_type = TokenSymbol::from(Assets::next_asset_id() - 1);
+		assert_ok!(Assets::create(Origin::root(), b"KSM".to_vec(), 12, None));
+		let ksm_type = TokenSymbol::from(Assets::next_asset_id() - 1);
+		assert_ok!(Assets::create(Origin::root(), b"EOS".to_vec(), 12, None));
+		let reward_type = TokenSymbol::from(Assets::next_asset_id() - 1);
+
+		assert_ok!(Assets::issue(Origin::root(), dot_type, alice, 100_000));
+		assert_ok!(Assets::issue(Origin::root(), ksm_type, alice, 100_000));
+
+		assert_ok!(Swap::create_pool(Origin::signed(alice), vec![(dot_type, 100_000, 1), (ksm_type, 100_000, 1)], 100));
+		let pool_id = 0;
+		let share_token = Swap::pool_token(pool_id).unwrap();
+		assert_ok!(Swap::set_mining_rewards(Origin::root(), pool_id, vec![(reward_type, 100)]));
+
+		// alice earns all emissions of block 3 and 4
+		run_to_block(4);
+
+		// bob holds a quarter of the pool after the transfer
+		assert_ok!(Assets::transfer(Origin::signed(alice), share_token, bob, 250));
+		assert_eq!(Swap::mining_positions((pool_id, alice)).shares, 750);
+		assert_eq!(Swap::mining_positions((pool_id, bob)).shares, 250);
+		assert_eq!(Swap::mining_pools(pool_id).total_shares, 1_000);
+
+		// they share the emissions of block 5 and 6
+		run_to_block(6);
+		assert_ok!(Swap::claim_rewards(Origin::signed(alice), pool_id));
+		assert_eq!(<assets::AccountAssets<Test>>::get((reward_type, alice)).balance, 350);
+		assert_ok!(Swap::claim_rewards(Origin::signed(bob), pool_id));
+		assert_eq!(<assets::AccountAssets<Test>>::get((reward_type, bob)).balance, 50);
+
+		// bob redeems the shares he gets from alice
+		assert_ok!(Swap::remove_assets_liquidity(Origin::signed(bob), pool_id, 250, None));
+		assert_eq!(<assets::AccountAssets<Test>>::get((dot_type, bob)).balance, 25_000);
+		assert_eq!(<assets::AccountAssets<Test>>::get((ksm_type, bob)).balance, 25_000);
+		assert_eq!(Swap::mining_positions((pool_id, bob)).shares, 0);
+		assert_eq!(Swap::pools(pool_id).unwrap().pool_supply, 750);
+		assert_eq!(Assets::token_details(share_token).total_supply, 750);
+		assert_eq!(Swap::check_integrity(), vec![]);
+	});
+}
+
+#[test]
+fn pool_tokens_should_not_be_burnt_by_holders() {
+	new_test_ext().execute_with(|| {
+		run_to_block(2);
+
+		let alice = 1u64;
+		let bob = 2u64;
+		assert_ok!(Assets::create(Origin::root(), b"aUSD".to_vec(), 18, None)); // let dot start from 1
+		assert_ok!(Assets::create(Origin::root(), b"DOT".to_vec(), 12, None));
+		let dot_type = TokenSymbol::from(Assets::next_asset_id() - 1);
+		assert_ok!(Assets::create(Origin::root(), b"KSM".to_vec(), 12, None));
+		let ksm_type = TokenSymbol::from(Assets::next_asset_id() - 1);
+
+		assert_ok!(Assets::issue(Origin::root(), dot_type, alice, 100_100));
+		assert_ok!(Assets::issue(Origin::root(), ksm_type, alice, 100_000));
+
+		assert_ok!(Swap::create_pool(Origin::signed(alice), vec![(dot_type, 100_000, 1), (ksm_type, 100_000, 1)], 100));
+		let pool_id = 0;
+		let share_token = Swap::pool_token(pool_id).unwrap();
 
-		let user_pool = <UserPool::<Test>>::get(alice);
-		assert!(user_pool.0.iter().all(|p| p.1 == 0));
-		assert_eq!(user_pool.1, 0);
+		// pool tokens are burnt by removing liquidity only
+		assert_eq!(
+			Assets::destroy(Origin::signed(alice), share_token, 100),
+			Err(DispatchError::Module { index: 0, error: 20, message: Some("TokenNotBurnable") })
+		);
+		assert_eq!(
+			Assets::redeem(Origin::signed(alice), share_token, 100, None),
+			Err(DispatchError::Module { index: 0, error: 20, message: Some("TokenNotBurnable") })
+		);
+		assert_eq!(Assets::token_details(share_token).total_supply, 1_000);
+
+		// the pool tokens below the minimum balance are not reaped as dust
+		assert_ok!(Assets::set_min_balance(Origin::root(), share_token, 100));
+		assert_ok!(Assets::transfer(Origin::signed(alice), share_token, bob, 150));
+		assert_ok!(Assets::transfer(Origin::signed(bob), share_token, alice, 100));
+		assert_eq!(<assets::AccountAssets<Test>>::get((share_token, bob)).balance, 50);
+		assert_eq!(Swap::mining_positions((pool_id, bob)).shares, 50);
+		assert_eq!(Assets::token_details(share_token).total_supply, 1_000);
+		assert_eq!(Swap::check_integrity(), vec![]);
+
+		// other tokens are still burnt by their holders
+		assert_ok!(Assets::destroy(Origin::signed(alice), dot_type, 100));
+
+		assert_ok!(Swap::remove_assets_liquidity(Origin::signed(bob), pool_id, 50, None));
+		assert_eq!(<assets::AccountAssets<Test>>::get((dot_type, bob)).balance, 5_000);
+		assert_eq!(Swap::pools(pool_id).unwrap().pool_supply, 950);
+		assert_eq!(Assets::token_details(share_token).total_supply, 950);
+		assert_eq!(Swap::check_integrity(), vec![]);
+	});
+}
+
+#[test]
+fn migrate_to_pool_tokens_should_work() {
+	new_test_ext().execute_with(|| {
+		run_to_block(2);
+
+		let bob = 2u64;
+		let jim = 3u64;
+		assert_ok!(Assets::create(Origin::root(), b"aUSD".to_vec(), 18, None)); // let dot start from 1
+		assert_ok!(Assets::create(Origin::root(), b"DOT".to_vec(), 12, None));
+		let dot_type = TokenSymbol::from(Assets::next_asset_id() - 1);
+		assert_ok!(Assets::create(Origin::root(), b"KSM".to_vec(), 12, None));
+		let ksm_type = TokenSymbol::from(Assets::next_asset_id() - 1);
+
+		// the single pool of the old release, the initial 1000 shares are not recorded
+		put_storage_value(b"Swap", b"BalancerPoolToken", &[], 1_305u64);
+		put_storage_value(b"Swap", b"GlobalPool", &[], (vec![(dot_type, 13_050u64, 2u64), (ksm_type, 13_050u64, 2u64)], 0u64));
+		put_storage_value(b"Swap", b"TotalWeight", &[], 2u64);
+		put_storage_value(b"Swap", b"TokenWeight", &Blake2_128Concat::hash(&dot_type.encode()), 1u64);
+		put_storage_value(b"Swap", b"TokenWeight", &Blake2_128Concat::hash(&ksm_type.encode()), 1u64);
+		put_storage_value(b"Swap", b"SwapFee", &[], 100u64);
+		put_storage_value(b"Swap", b"ExitFee", &[], 50u64);
+		put_storage_value(b"Swap", b"SharedRewardPool", &[], vec![(TokenSymbol::aUSD, 0u64), (dot_type, 7u64), (ksm_type, 0u64)]);
+		put_storage_value(b"Swap", b"NumberOfSupportedTokens", &[], 2u8);
+		put_storage_value(
+			b"Swap",
+			b"UserPool",
+			&Blake2_128Concat::hash(&bob.encode()),
+			(vec![(dot_type, 3_000u64), (ksm_type, 3_000u64)], 300u64),
+		);
+		put_storage_value(b"Swap", b"UserSinglePool", &Blake2_128Concat::hash(&(jim, dot_type).encode()), (50u64, 5u64));
+		put_storage_value(b"Swap", b"UserSinglePool", &Blake2_128Concat::hash(&(jim, ksm_type).encode()), (0u64, 0u64));
+		let pool_id = 0;
+		assert_eq!(Swap::pools(pool_id), None);
+		assert_eq!(StorageVersion::get(), Releases::V1_0_0);
+
+		Swap::on_runtime_upgrade();
+		assert_eq!(StorageVersion::get(), Releases::V2_0_0);
+
+		// the pool is kept in `Pools`, with the weights of `TokenWeight`
+		assert_eq!(Swap::next_pool_id(), 1);
+		assert_eq!(Swap::pools(pool_id), Some(Pool {
+			owner: 0,
+			total_weight: 2,
+			swap_fee: 100,
+			exit_fee: 50,
+			pool_supply: 1_305,
+			rewards: vec![(dot_type, 7), (ksm_type, 0)],
+			tokens: vec![(dot_type, 13_050, 1), (ksm_type, 13_050, 1)],
+		}));
+
+		// the shares are pool tokens, the unrecorded ones are held by the default account
+		let share_token = Swap::pool_token(pool_id).unwrap();
+		assert_eq!(Swap::share_pool(share_token), Some(pool_id));
+		assert_eq!(Assets::token_details(share_token).symbol, b"BPT-0".to_vec());
+		assert_eq!(<assets::AccountAssets<Test>>::get((share_token, 0u64)).balance, 1_000);
+		assert_eq!(<assets::AccountAssets<Test>>::get((share_token, bob)).balance, 300);
+		assert_eq!(<assets::AccountAssets<Test>>::get((share_token, jim)).balance, 5);
+		assert_eq!(Assets::token_details(share_token).total_supply, 1_305);
+		assert_eq!(Swap::mining_positions((pool_id, bob)).shares, 300);
+		assert_eq!(Swap::mining_positions((pool_id, jim)).shares, 5);
+		assert_eq!(Swap::check_integrity(), vec![]);
+
+		// the storage of the old release is removed
+		for item in migrations::v1::ITEMS.iter() {
+			let prefix = [Twox128::hash(b"Swap"), Twox128::hash(item)].concat();
+			assert!(!have_storage_value(b"Swap", item, &[]));
+			assert!(sp_io::storage::next_key(&prefix).map_or(true, |key| !key.starts_with(&prefix)));
+		}
+
+		// the migrated shares are redeemable, with their part of the shared fees
+		assert_ok!(Swap::remove_assets_liquidity(Origin::signed(bob), pool_id, 300, None));
+		assert_eq!(<assets::AccountAssets<Test>>::get((dot_type, bob)).balance, 3_001);
+		assert_eq!(<assets::AccountAssets<Test>>::get((ksm_type, bob)).balance, 3_000);
+
+		// it runs only once
+		Swap::on_runtime_upgrade();
+		assert_eq!(Swap::next_pool_id(), 1);
+		assert_eq!(Assets::token_details(share_token).total_supply, 1_005);
+		assert_eq!(Swap::check_integrity(), vec![]);
+	});
+}
+
+#[test]
+fn deadline_and_price_movement_should_work() {
+	new_test_ext().execute_with(|| {
+		run_to_block(2);
+
+		let alice = 1u64;
+		assert_ok!(Assets::create(Origin::root(), b"aUSD".to_vec(), 18, None)); // let dot start from 1
+		assert_ok!(Assets::create(Origin::root(), b"DOT".to_vec(), 12, None));
+		let dot_type = TokenSymbol::from(Assets::next_asset_id() - 1);
+		assert_ok!(Assets::create(Origin::root(), b"KSM".to_vec(), 12, None));
+		let ksm_type = TokenSymbol::from(Assets::next_asset_id() - 1);
+
+		assert_ok!(Assets::issue(Origin::root(), dot_type, alice, 1_000_000));
+		assert_ok!(Assets::issue(Origin::root(), ksm_type, alice, 1_000_000));
+
+		assert_ok!(Swap::create_pool(Origin::signed(alice), vec![(dot_type, 100_000, 1), (ksm_type, 100_000, 1)], 100));
+		let pool_id = 0;
+
+		// stale calls are rejected
+		assert_eq!(
+			Swap::swap(Origin::signed(alice), pool_id, dot_type, 1_000, None, ksm_type, None, Some(1)),
+			Err(DispatchError::Module { index: 0, error: 22, message: Some("DeadlineExceeded") })
+		);
+		assert_eq!(
+			Swap::add_liquidity(Origin::signed(alice), pool_id, 100, Some(1)),
+			Err(DispatchError::Module { index: 0, error: 22, message: Some("DeadlineExceeded") })
+		);
+		assert_eq!(
+			Swap::remove_assets_liquidity(Origin::signed(alice), pool_id, 100, Some(1)),
+			Err(DispatchError::Module { index: 0, error: 22, message: Some("DeadlineExceeded") })
+		);
+
+		// price of ksm goes up about 125%
+		assert_ok!(Swap::swap(Origin::signed(alice), pool_id, dot_type, 50_000, None, ksm_type, None, Some(2)));
+		let (block_number, movement) = Swap::price_movement(pool_id);
+		assert_eq!(block_number, 2);
+		assert!(movement > 120_000 && movement < 130_000);
+
+		// another 125% exceeds the maximum 200% of the block
+		assert_eq!(
+			Swap::swap(Origin::signed(alice), pool_id, dot_type, 75_000, None, ksm_type, None, None),
+			Err(DispatchError::Module { index: 0, error: 37, message: Some("ExceedMaximumPriceMovement") })
+		);
+		assert_eq!(
+			Swap::swap_exact_in_routed(Origin::signed(alice), dot_type, 75_000, vec![(pool_id, ksm_type)], 1, Some(2)),
+			Err(DispatchError::Module { index: 0, error: 37, message: Some("ExceedMaximumPriceMovement") })
+		);
+		assert_eq!(Swap::price_movement(pool_id), (block_number, movement));
+
+		// small swaps are still allowed
+		assert_ok!(Swap::swap(Origin::signed(alice), pool_id, dot_type, 1_000, None, ksm_type, None, None));
+		assert!(Swap::price_movement(pool_id).1 > movement);
+
+		// the movement is counted again in the next block
+		run_to_block(3);
+		assert_ok!(Swap::swap(Origin::signed(alice), pool_id, dot_type, 75_000, None, ksm_type, None, Some(3)));
+		let (block_number, movement) = Swap::price_movement(pool_id);
+		assert_eq!(block_number, 3);
+		assert!(movement > 100_000 && movement < 150_000);
+	});
+}
+
+#[test]
+fn check_integrity_should_work() {
+	new_test_ext().execute_with(|| {
+		run_to_block(2);
+
+		let alice = 1u64;
+		let bob = 2u64;
+		assert_ok!(Assets::create(Origin::root(), b"aUSD".to_vec(), 18, None)); // let dot start from 1
+		assert_ok!(Assets::create(Origin::root(), b"DOT".to_vec(), 12, None));
+		let dot_type = TokenSymbol::from(Assets::next_asset_id() - 1);
+		assert_ok!(Assets::create(Origin::root(), b"KSM".to_vec(), 12, None));
+		let ksm_type = TokenSymbol::from(Assets::next_asset_id() - 1);
+
+		assert_ok!(Assets::issue(Origin::root(), dot_type, alice, 1_000_000));
+		assert_ok!(Assets::issue(Origin::root(), ksm_type, alice, 1_000_000));
+
+		assert_ok!(Swap::create_pool(Origin::signed(alice), vec![(dot_type, 100_000, 1), (ksm_type, 100_000, 1)], 100));
+		assert_ok!(Swap::create_pool(Origin::signed(alice), vec![(dot_type, 100_000, 1), (ksm_type, 100_000, 4)], 100));
+		assert_ok!(Swap::add_liquidity(Origin::signed(alice), 0, 500, None));
+		assert_ok!(Swap::swap(Origin::signed(alice), 1, dot_type, 1_000, None, ksm_type, None, None));
+		assert_eq!(Swap::check_integrity(), vec![]);
+		// the pool account holds the pool balances
+		let pool_account = Swap::pool_account(1);
+		assert_ne!(pool_account, Swap::pool_account(0));
+		assert_eq!(<assets::AccountAssets<Test>>::get((dot_type, pool_account)).balance, 101_000);
+		assert_eq!(Swap::pools(1).unwrap().tokens[0], (dot_type, 101_000, 1));
+
+		// pool tokens issued out of the pool, the mining shares don't change
+		let share_token = Swap::pool_token(0).unwrap();
+		assert_ok!(Assets::issue(Origin::root(), share_token, bob, 10));
+		// weights changed without the total weight
+		<Pools<Test>>::mutate(1, |pool| pool.as_mut().unwrap().tokens[1].2 = 3);
+		// a pool without pool token, and a token not in the assets
+		<Pools<Test>>::insert(2, Pool {
+			owner: alice,
+			total_weight: 2,
+			swap_fee: 100,
+			exit_fee: 0,
+			pool_supply: 1_000,
+			rewards: vec![(dot_type, 0), (TokenSymbol::IOST, 0)],
+			tokens: vec![(dot_type, 1_000, 1), (TokenSymbol::IOST, 1_000, 1)],
+		});
+
+		// ksm taken out of the pool account without the pool
+		assert_ok!(Assets::transfer(Origin::signed(Swap::pool_account(0)), ksm_type, bob, 10));
+
+		// pools are not iterated in order
+		let errors = Swap::check_integrity();
+		assert_eq!(errors.len(), 6);
+		let ksm_balance = Swap::pools(0).unwrap().tokens[1].1;
+		assert!(errors.contains(&(0, IntegrityError::PoolBalanceMismatch(ksm_type, ksm_balance, ksm_balance - 10))));
+		assert!(errors.contains(&(2, IntegrityError::PoolBalanceMismatch(dot_type, 1_000, 0))));
+		assert!(errors.contains(&(0, IntegrityError::PoolSupplyMismatch(1_500, 1_510))));
+		assert!(errors.contains(&(1, IntegrityError::TotalWeightMismatch(5, 4))));
+		assert!(errors.contains(&(2, IntegrityError::TokenNotExist(TokenSymbol::IOST))));
+		assert!(errors.contains(&(2, IntegrityError::PoolTokenMismatch)));
 	});
 }